@@ -0,0 +1,438 @@
+// Algorithms on a Graph. None of them recurse, so a graph can be as deep as memory allows - each keeps its own stack.
+// Wherever there's a choice of which edge to follow next, they follow them in the order the edges were added, which
+// keeps the results the same from one run to the next.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+use crate::graph::{Graph, NodeId};
+
+// Every node that can be reached from `start`, nearest first
+pub fn bfs<N, E>(graph: &Graph<N, E>, start: NodeId) -> Vec<NodeId> {
+    let mut seen = vec![false; graph.node_count()];
+    let mut order = Vec::new();
+    let mut queue = VecDeque::new();
+    seen[start.index()] = true;
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        order.push(node);
+        for (next, _) in graph.neighbours(node) {
+            if !seen[next.index()] {
+                seen[next.index()] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    order
+}
+
+// Every node that can be reached from `start`, in the order a depth first search gets to them
+pub fn dfs<N, E>(graph: &Graph<N, E>, start: NodeId) -> Vec<NodeId> {
+    let mut seen = vec![false; graph.node_count()];
+    let mut order = Vec::new();
+    let mut stack = vec![start];
+
+    while let Some(node) = stack.pop() {
+        if seen[node.index()] {
+            continue;
+        }
+        seen[node.index()] = true;
+        order.push(node);
+        let next: Vec<NodeId> = graph.neighbours(node).map(|(next, _)| next).collect();
+        stack.extend(next.into_iter().rev().filter(|next| !seen[next.index()]));
+    }
+    order
+}
+
+// The shortest way to everywhere from one node, as worked out by dijkstra
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortestPaths {
+    start: NodeId,
+    distances: Vec<Option<u64>>,
+    previous: Vec<Option<NodeId>>, // the node before each one on its shortest path
+}
+
+impl ShortestPaths {
+    // None if `to` can't be reached
+    pub fn distance(&self, to: NodeId) -> Option<u64> {
+        self.distances.get(to.index()).copied().flatten()
+    }
+
+    // The nodes along the shortest path, from the start to `to` (both included)
+    pub fn path(&self, to: NodeId) -> Option<Vec<NodeId>> {
+        self.distance(to)?;
+        let mut path = vec![to];
+        let mut node = to;
+        while node != self.start {
+            node = self.previous[node.index()]?;
+            path.push(node);
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+// The shortest paths from `start` to every other node, where `weight` says how long each edge is
+pub fn dijkstra<N, E, W>(graph: &Graph<N, E>, start: NodeId, mut weight: W) -> ShortestPaths
+where
+    W: FnMut(&E) -> u64,
+{
+    let mut paths = ShortestPaths {
+        start,
+        distances: vec![None; graph.node_count()],
+        previous: vec![None; graph.node_count()],
+    };
+    let mut done = vec![false; graph.node_count()];
+    let mut queue = BinaryHeap::new();
+    paths.distances[start.index()] = Some(0);
+    queue.push(Reverse((0u64, start)));
+
+    while let Some(Reverse((distance, node))) = queue.pop() {
+        if done[node.index()] {
+            continue; // already got here a shorter way
+        }
+        done[node.index()] = true;
+
+        for (next, edge) in graph.neighbours(node) {
+            // a path too long to count in a u64 is never the shortest, so going that way isn't a way there at all
+            let through = match distance.checked_add(weight(edge)) {
+                Some(through) => through,
+                None => continue,
+            };
+            if paths.distances[next.index()].is_none_or(|known| through < known) {
+                paths.distances[next.index()] = Some(through);
+                paths.previous[next.index()] = Some(node);
+                queue.push(Reverse((through, next)));
+            }
+        }
+    }
+    paths
+}
+
+// A way round the graph that comes back to where it started
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle(pub Vec<NodeId>);
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let nodes: Vec<String> = self.0.iter().chain(self.0.first()).map(|node| node.index().to_string()).collect();
+        write!(f, "the graph has a cycle: {}", nodes.join(" -> "))
+    }
+}
+
+impl Error for Cycle {}
+
+// Why toposort couldn't put the nodes in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortError {
+    Cycle(Cycle),
+    Undirected, // undirected edges don't say which end comes first
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SortError::Cycle(cycle) => write!(f, "{}", cycle),
+            SortError::Undirected => write!(f, "only a directed graph can be put in order"),
+        }
+    }
+}
+
+impl Error for SortError {}
+
+// Every node, with each one before all the nodes its edges go to - the order to do things in when the edges say what
+// depends on what. Only a directed graph without cycles has one; otherwise this gives back one of the cycles, or
+// SortError::Undirected for any undirected graph. Nodes that could go in either order keep the order they were added
+// in.
+pub fn toposort<N, E>(graph: &Graph<N, E>) -> Result<Vec<NodeId>, SortError> {
+    if !graph.is_directed() {
+        return Err(SortError::Undirected);
+    }
+
+    // Kahn's algorithm: keep taking nodes nothing is left pointing at
+    let mut incoming = vec![0; graph.node_count()];
+    for (_, to, _) in graph.edges() {
+        incoming[to.index()] += 1;
+    }
+    let mut ready: BinaryHeap<Reverse<NodeId>> =
+        graph.node_ids().filter(|node| incoming[node.index()] == 0).map(Reverse).collect();
+    let mut order = Vec::new();
+    while let Some(Reverse(node)) = ready.pop() {
+        order.push(node);
+        for (next, _) in graph.neighbours(node) {
+            incoming[next.index()] -= 1;
+            if incoming[next.index()] == 0 {
+                ready.push(Reverse(next));
+            }
+        }
+    }
+
+    if order.len() == graph.node_count() {
+        Ok(order)
+    } else {
+        Err(SortError::Cycle(Cycle(find_cycle(graph).expect("nodes left over mean there's a cycle"))))
+    }
+}
+
+pub fn is_cyclic<N, E>(graph: &Graph<N, E>) -> bool {
+    find_cycle(graph).is_some()
+}
+
+// A cycle in the graph, if it has one, as the nodes along it in order. In an undirected graph, going along an edge and
+// straight back again doesn't count (unless there are two edges between the same nodes).
+pub fn find_cycle<N, E>(graph: &Graph<N, E>) -> Option<Vec<NodeId>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        New,
+        OnPath, // on the way from the current start to where the search is now
+        Done,
+    }
+
+    // A node on the path, with how many of its edges have been followed and the node it was reached from
+    struct Visit {
+        node: NodeId,
+        followed: usize,
+        from: Option<NodeId>,
+        went_back: bool, // whether the edge back to `from` has been skipped yet (undirected graphs only)
+    }
+
+    let mut state = vec![State::New; graph.node_count()];
+    for start in graph.node_ids() {
+        if state[start.index()] != State::New {
+            continue;
+        }
+        let mut path = vec![Visit { node: start, followed: 0, from: None, went_back: false }];
+        state[start.index()] = State::OnPath;
+
+        while let Some(visit) = path.last_mut() {
+            let next = match graph.edges_from(visit.node).get(visit.followed) {
+                Some(&(next, _)) => next,
+                None => {
+                    state[visit.node.index()] = State::Done;
+                    path.pop();
+                    continue;
+                }
+            };
+            visit.followed += 1;
+            if !graph.is_directed() && Some(next) == visit.from && !visit.went_back {
+                visit.went_back = true;
+                continue;
+            }
+
+            match state[next.index()] {
+                State::New => {
+                    state[next.index()] = State::OnPath;
+                    let from = Some(visit.node);
+                    path.push(Visit { node: next, followed: 0, from, went_back: false });
+                }
+                State::OnPath => {
+                    let at = path.iter().position(|visit| visit.node == next).unwrap();
+                    return Some(path[at..].iter().map(|visit| visit.node).collect());
+                }
+                State::Done => {}
+            }
+        }
+    }
+    None
+}
+
+// The strongly connected components: groups of nodes that can each be reached from every other one in the group. Every
+// node is in exactly one. They come out in reverse topological order (a component only has edges to the ones before
+// it), with the nodes in each one in the order they were added. In an undirected graph, these are the connected
+// components.
+pub fn strongly_connected_components<N, E>(graph: &Graph<N, E>) -> Vec<Vec<NodeId>> {
+    // Tarjan's algorithm, with the recursion turned into a loop
+    let count = graph.node_count();
+    let mut index: Vec<Option<usize>> = vec![None; count];
+    let mut low = vec![0; count]; // the lowest index reachable from each node, along edges to nodes still on `stack`
+    let mut on_stack = vec![false; count];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+
+    for start in graph.node_ids() {
+        if index[start.index()].is_some() {
+            continue;
+        }
+        let mut work = vec![(start, 0)]; // each node being visited, with how many of its edges have been followed
+        index[start.index()] = Some(next_index);
+        low[start.index()] = next_index;
+        next_index += 1;
+        stack.push(start);
+        on_stack[start.index()] = true;
+
+        while let Some(&mut (node, ref mut followed)) = work.last_mut() {
+            if let Some(&(next, _)) = graph.edges_from(node).get(*followed) {
+                *followed += 1;
+                match index[next.index()] {
+                    None => {
+                        index[next.index()] = Some(next_index);
+                        low[next.index()] = next_index;
+                        next_index += 1;
+                        stack.push(next);
+                        on_stack[next.index()] = true;
+                        work.push((next, 0));
+                    }
+                    Some(i) if on_stack[next.index()] => low[node.index()] = low[node.index()].min(i),
+                    Some(_) => {}
+                }
+                continue;
+            }
+
+            work.pop();
+            if let Some(&(parent, _)) = work.last() {
+                low[parent.index()] = low[parent.index()].min(low[node.index()]);
+            }
+            if Some(low[node.index()]) == index[node.index()] {
+                let mut component = Vec::new();
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member.index()] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Builds a graph of `n` nodes, numbered from 0, with an edge for each pair
+    fn fixture(directed: bool, n: usize, edges: &[(usize, usize, u64)]) -> (Graph<usize, u64>, Vec<NodeId>) {
+        let mut graph = if directed { Graph::directed() } else { Graph::undirected() };
+        let nodes: Vec<NodeId> = (0..n).map(|i| graph.add_node(i)).collect();
+        for &(from, to, weight) in edges {
+            graph.add_edge(nodes[from], nodes[to], weight);
+        }
+        (graph, nodes)
+    }
+
+    fn indexes(nodes: &[NodeId]) -> Vec<usize> {
+        nodes.iter().map(|node| node.index()).collect()
+    }
+
+    //   0 -> 1 -> 3
+    //   |    ^    |
+    //   v    |    v
+    //   2 ---+    4     5
+    const DAG: &[(usize, usize, u64)] = &[(0, 1, 7), (0, 2, 2), (2, 1, 3), (1, 3, 1), (3, 4, 5)];
+
+    #[test]
+    fn searches_visit_what_can_be_reached() {
+        let (directed, nodes) = fixture(true, 6, DAG);
+        assert_eq!(indexes(&bfs(&directed, nodes[0])), vec![0, 1, 2, 3, 4]);
+        assert_eq!(indexes(&dfs(&directed, nodes[0])), vec![0, 1, 3, 4, 2]);
+        assert_eq!(indexes(&bfs(&directed, nodes[3])), vec![3, 4]);
+        assert_eq!(indexes(&dfs(&directed, nodes[5])), vec![5]);
+
+        let (undirected, nodes) = fixture(false, 6, DAG);
+        assert_eq!(indexes(&bfs(&undirected, nodes[3])), vec![3, 1, 4, 0, 2]);
+        assert_eq!(indexes(&dfs(&undirected, nodes[3])), vec![3, 1, 0, 2, 4]);
+    }
+
+    #[test]
+    fn dijkstra_finds_the_shortest_paths() {
+        let (graph, nodes) = fixture(true, 6, DAG);
+        let paths = dijkstra(&graph, nodes[0], |&weight| weight);
+
+        assert_eq!(paths.distance(nodes[1]), Some(5)); // round by 2 beats the direct edge
+        assert_eq!(paths.path(nodes[4]).map(|path| indexes(&path)), Some(vec![0, 2, 1, 3, 4]));
+        assert_eq!(paths.distance(nodes[4]), Some(11));
+        assert_eq!(paths.path(nodes[0]), Some(vec![nodes[0]]));
+        assert_eq!(paths.distance(nodes[5]), None);
+        assert_eq!(paths.path(nodes[5]), None);
+
+        // every edge the same length gives the fewest edges
+        let hops = dijkstra(&graph, nodes[0], |_| 1);
+        assert_eq!(hops.distance(nodes[4]), Some(3));
+
+        // a path whose length doesn't fit in a u64 doesn't count, rather than wrapping round to look short
+        let (huge, nodes) = fixture(true, 3, &[(0, 1, u64::MAX), (1, 2, 1), (0, 2, 9)]);
+        let paths = dijkstra(&huge, nodes[0], |&weight| weight);
+        assert_eq!(paths.distance(nodes[1]), Some(u64::MAX));
+        assert_eq!(paths.path(nodes[2]).map(|path| indexes(&path)), Some(vec![0, 2]));
+    }
+
+    #[test]
+    fn toposort_puts_each_node_before_where_its_edges_go() {
+        let (graph, _) = fixture(true, 6, DAG);
+        assert_eq!(toposort(&graph).map(|order| indexes(&order)), Ok(vec![0, 2, 1, 3, 4, 5]));
+        assert!(!is_cyclic(&graph));
+
+        let (cyclic, _) = graph_with_cycle();
+        let cycle = match toposort(&cyclic) {
+            Err(SortError::Cycle(cycle)) => cycle,
+            other => panic!("expected a cycle, got {:?}", other),
+        };
+        assert_eq!(indexes(&cycle.0), vec![1, 2, 3]);
+        assert_eq!(cycle.to_string(), "the graph has a cycle: 1 -> 2 -> 3 -> 1");
+
+        // even without any edges, so it can't look like an undirected graph has an order
+        assert_eq!(toposort(&fixture(false, 3, &[]).0), Err(SortError::Undirected));
+        assert_eq!(toposort(&fixture(false, 6, DAG).0), Err(SortError::Undirected));
+    }
+
+    //   0 -> 1 -> 2 -> 3 -> 4
+    //        ^         |
+    //        +---------+
+    fn graph_with_cycle() -> (Graph<usize, u64>, Vec<NodeId>) {
+        fixture(true, 5, &[(0, 1, 1), (1, 2, 1), (2, 3, 1), (3, 1, 1), (3, 4, 1)])
+    }
+
+    #[test]
+    fn cycles_are_found_in_both_kinds_of_graph() {
+        let (directed, _) = graph_with_cycle();
+        assert_eq!(find_cycle(&directed).map(|cycle| indexes(&cycle)), Some(vec![1, 2, 3]));
+
+        // a tree has no cycles when undirected, however its edges point
+        let (tree, _) = fixture(false, 4, &[(0, 1, 1), (2, 0, 1), (1, 3, 1)]);
+        assert_eq!(find_cycle(&tree), None);
+
+        let (triangle, _) = fixture(false, 4, &[(0, 1, 1), (1, 2, 1), (3, 0, 1), (2, 0, 1)]);
+        assert_eq!(find_cycle(&triangle).map(|cycle| indexes(&cycle)), Some(vec![0, 1, 2]));
+
+        // two edges between the same pair of nodes are a way round, and so is an edge to itself
+        let (doubled, _) = fixture(false, 2, &[(0, 1, 1), (1, 0, 1)]);
+        assert_eq!(find_cycle(&doubled).map(|cycle| indexes(&cycle)), Some(vec![0, 1]));
+        let (looped, _) = fixture(true, 2, &[(0, 1, 1), (1, 1, 1)]);
+        assert_eq!(find_cycle(&looped).map(|cycle| indexes(&cycle)), Some(vec![1]));
+    }
+
+    #[test]
+    fn strongly_connected_components_group_nodes_that_reach_each_other() {
+        //   0 <-> 1 -> 2 -> 3 -> 4
+        //              ^         |
+        //              +---------+   5 -> 5
+        let edges = [(0, 1, 1), (1, 0, 1), (1, 2, 1), (2, 3, 1), (3, 4, 1), (4, 2, 1), (5, 5, 1)];
+        let (graph, _) = fixture(true, 6, &edges);
+        let components: Vec<Vec<usize>> =
+            strongly_connected_components(&graph).iter().map(|component| indexes(component)).collect();
+        assert_eq!(components, vec![vec![2, 3, 4], vec![0, 1], vec![5]]);
+
+        let (undirected, _) = fixture(false, 5, &[(0, 3, 1), (4, 1, 1)]);
+        let components: Vec<Vec<usize>> =
+            strongly_connected_components(&undirected).iter().map(|component| indexes(component)).collect();
+        assert_eq!(components, vec![vec![0, 3], vec![1, 4], vec![2]]);
+    }
+
+    #[test]
+    fn long_chains_dont_run_out_of_stack() {
+        let edges: Vec<(usize, usize, u64)> = (1..100_000).map(|i| (i - 1, i, 1)).collect();
+        let (graph, nodes) = fixture(true, 100_000, &edges);
+
+        assert_eq!(strongly_connected_components(&graph).len(), 100_000);
+        assert_eq!(find_cycle(&graph), None);
+        assert_eq!(dfs(&graph, nodes[0]).len(), 100_000);
+    }
+}
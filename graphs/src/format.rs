@@ -0,0 +1,821 @@
+// Reading graphs from files and writing them out again, in two formats:
+//
+// - Graphviz DOT, so results can be drawn with `dot -Tsvg`. Reading takes the common part of the language: node and
+//   edge statements (chains like `a -> b -> c` too), attribute lists, subgraphs and comments. Nodes are known by their
+//   id, and an edge's weight comes from its `weight` attribute, or failing that its `label`. Writing puts weights in
+//   labels, since that's what shows up on the drawing.
+// - Edge lists: one edge a line, as `from,to` or `from,to,weight`, the way a spreadsheet exports them as CSV (quotes
+//   and all). Fields can be separated by spaces instead of commas, a line with just a name is a node with no edges,
+//   a first line like `from,to,weight` or `source,target` is taken as a header, and # starts a comment.
+//
+// Nodes read in are named by Strings, in the order they first turn up. What the weights turn into is up to the Weight
+// type asked for: () to ignore them, a number, a String, or an Option of one of those when only some edges have them.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Write as _};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::graph::{Direction, Graph, NodeId};
+
+// An edge weight that can be written out as text and read back in. `text` is None for an edge that didn't have one.
+pub trait Weight: Sized + Clone {
+    fn to_text(&self) -> Option<String>;
+    fn from_text(text: Option<&str>) -> Result<Self, String>;
+}
+
+impl Weight for () {
+    fn to_text(&self) -> Option<String> {
+        None
+    }
+
+    // Any weight is ignored, so a weighted graph can be read just for its shape
+    fn from_text(_: Option<&str>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl Weight for String {
+    fn to_text(&self) -> Option<String> {
+        Some(self.clone())
+    }
+
+    fn from_text(text: Option<&str>) -> Result<String, String> {
+        text.map(String::from).ok_or_else(|| String::from("the edge has no weight"))
+    }
+}
+
+macro_rules! number_weights {
+    ($($number:ty),*) => {$(
+        impl Weight for $number {
+            fn to_text(&self) -> Option<String> {
+                Some(self.to_string())
+            }
+
+            fn from_text(text: Option<&str>) -> Result<$number, String> {
+                let text = text.ok_or_else(|| String::from("the edge has no weight"))?;
+                text.trim().parse().map_err(|_| format!("'{}' isn't a valid weight", text))
+            }
+        }
+    )*};
+}
+
+number_weights!(u32, u64, i32, i64, usize, f64);
+
+impl<W: Weight> Weight for Option<W> {
+    fn to_text(&self) -> Option<String> {
+        self.as_ref().and_then(W::to_text)
+    }
+
+    fn from_text(text: Option<&str>) -> Result<Option<W>, String> {
+        text.map(|text| W::from_text(Some(text))).transpose()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse(ParseError),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "couldn't read the file: {}", e),
+            LoadError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+impl From<ParseError> for LoadError {
+    fn from(e: ParseError) -> LoadError {
+        LoadError::Parse(e)
+    }
+}
+
+// Reads a graph from a file: DOT if the name ends in .dot or .gv, an edge list of directed edges otherwise
+pub fn load<W: Weight, P: AsRef<Path>>(path: P) -> Result<Graph<String, W>, LoadError> {
+    let text = fs::read_to_string(&path)?;
+    let extension = path.as_ref().extension().and_then(|extension| extension.to_str());
+    let graph = match extension {
+        Some("dot") | Some("gv") => parse_dot(&text)?,
+        _ => parse_edge_list(&text, Direction::Directed)?,
+    };
+    Ok(graph)
+}
+
+// Nodes (including any with no edges) come first, so reading the result back gives them the same NodeIds
+pub fn to_dot<N: Display, E: Weight>(graph: &Graph<N, E>) -> String {
+    let (keyword, arrow) = if graph.is_directed() { ("digraph", "->") } else { ("graph", "--") };
+    let mut dot = format!("{} {{\n", keyword);
+    for id in graph.node_ids() {
+        let _ = writeln!(dot, "    {};", quote_dot(&graph.node(id).unwrap().to_string()));
+    }
+    for (from, to, weight) in graph.edges() {
+        let name = |id| quote_dot(&graph.node(id).unwrap().to_string());
+        let _ = write!(dot, "    {} {} {}", name(from), arrow, name(to));
+        if let Some(weight) = weight.to_text() {
+            let _ = write!(dot, " [label={}]", quote_dot(&weight));
+        }
+        dot.push_str(";\n");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn quote_dot(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// How deep subgraphs can go inside each other - plenty for a real file, and shallow enough that parsing a hostile one
+// can't overflow the stack
+const MAX_DEPTH: usize = 128;
+
+pub fn parse_dot<W: Weight>(text: &str) -> Result<Graph<String, W>, ParseError> {
+    let tokens = tokenize(text)?;
+    let mut parser = DotParser { tokens, pos: 0, depth: 0, graph: Graph::directed(), names: HashMap::new() };
+    parser.graph()?;
+    Ok(parser.graph)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Id { text: String, quoted: bool },
+    Edge(Direction), // -> or --
+    Open,            // {
+    Close,           // }
+    OpenList,        // [
+    CloseList,       // ]
+    Equals,
+    Semicolon,
+    Comma,
+    Colon,
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    let at_line_start = |i: usize| chars[..i].iter().rev().take_while(|&&c| c != '\n').all(|c| c.is_whitespace());
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let start_line = line;
+        match c {
+            '\n' => {
+                line += 1;
+                i += 1;
+            }
+            c if c.is_whitespace() => i += 1,
+            '/' if next == Some('/') => i = skip_line(&chars, i),
+            '#' if at_line_start(i) => i = skip_line(&chars, i), // preprocessor output
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    line += (chars[i] == '\n') as usize;
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    return Err(ParseError { line: start_line, message: String::from("unterminated comment") });
+                }
+                i += 2;
+            }
+            '-' if next == Some('>') => {
+                tokens.push((Token::Edge(Direction::Directed), line));
+                i += 2;
+            }
+            '-' if next == Some('-') => {
+                tokens.push((Token::Edge(Direction::Undirected), line));
+                i += 2;
+            }
+            '"' => {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(ParseError { line: start_line, message: String::from("unterminated string") })
+                        }
+                        Some('"') => break,
+                        Some('\\') if chars.get(i + 1) == Some(&'"') => {
+                            text.push('"');
+                            i += 1;
+                        }
+                        Some('\\') if chars.get(i + 1) == Some(&'\\') => {
+                            text.push('\\');
+                            i += 1;
+                        }
+                        Some('\\') if chars.get(i + 1) == Some(&'\n') => {
+                            line += 1;
+                            i += 1;
+                        }
+                        Some(&c) => {
+                            line += (c == '\n') as usize;
+                            text.push(c);
+                        }
+                    }
+                    i += 1;
+                }
+                tokens.push((Token::Id { text, quoted: true }, start_line));
+                i += 1;
+            }
+            '<' => return Err(ParseError { line, message: String::from("HTML strings aren't supported") }),
+            c if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' => {
+                let start = i;
+                while chars.get(i).is_some_and(|&c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.') {
+                    // stop before an edge operator straight after an id, as in a->b
+                    if i > start && chars[i] == '-' && matches!(chars.get(i + 1), Some('>') | Some('-')) {
+                        break;
+                    }
+                    i += 1;
+                }
+                tokens.push((Token::Id { text: chars[start..i].iter().collect(), quoted: false }, line));
+            }
+            _ => {
+                let token = match c {
+                    '{' => Token::Open,
+                    '}' => Token::Close,
+                    '[' => Token::OpenList,
+                    ']' => Token::CloseList,
+                    '=' => Token::Equals,
+                    ';' => Token::Semicolon,
+                    ',' => Token::Comma,
+                    ':' => Token::Colon,
+                    c => return Err(ParseError { line, message: format!("unexpected '{}'", c) }),
+                };
+                tokens.push((token, line));
+                i += 1;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn skip_line(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() && chars[i] != '\n' {
+        i += 1;
+    }
+    i
+}
+
+struct DotParser<W> {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    depth: usize, // how many subgraphs the parser is inside
+    graph: Graph<String, W>,
+    names: HashMap<String, NodeId>,
+}
+
+impl<W: Weight> DotParser<W> {
+    // [strict] (graph | digraph) [id] { statements }
+    fn graph(&mut self) -> Result<(), ParseError> {
+        self.keyword("strict");
+        self.graph = if self.keyword("digraph") {
+            Graph::directed()
+        } else if self.keyword("graph") {
+            Graph::undirected()
+        } else {
+            return Err(self.error("expected 'graph' or 'digraph'"));
+        };
+        if !self.keyword("subgraph") {
+            self.id();
+        }
+        self.expect(Token::Open, "'{'")?;
+        self.statements()?;
+        self.expect(Token::Close, "'}'")?;
+        match self.tokens.get(self.pos) {
+            Some(_) => Err(self.error("unexpected text after the end of the graph")),
+            None => Ok(()),
+        }
+    }
+
+    // Up to the closing }, which is left for the caller. Returns the nodes that came up, for edges to subgraphs.
+    fn statements(&mut self) -> Result<Vec<NodeId>, ParseError> {
+        let mut nodes = Vec::new();
+        while !matches!(self.peek(), Some(Token::Close) | None) {
+            for node in self.statement()? {
+                if !nodes.contains(&node) {
+                    nodes.push(node);
+                }
+            }
+            self.skip(&Token::Semicolon);
+        }
+        Ok(nodes)
+    }
+
+    fn statement(&mut self) -> Result<Vec<NodeId>, ParseError> {
+        // defaults for everything after them, which don't affect the graph's shape
+        if self.keyword("graph") || self.keyword("node") || self.keyword("edge") {
+            self.attributes()?;
+            return Ok(Vec::new());
+        }
+        // an attribute of the graph itself
+        let assignment = self.tokens.get(self.pos + 1).map(|(token, _)| token) == Some(&Token::Equals);
+        if matches!(self.peek(), Some(Token::Id { .. })) && assignment {
+            self.pos += 2;
+            self.id().ok_or_else(|| self.error("expected a value after '='"))?;
+            return Ok(Vec::new());
+        }
+
+        let line = self.line();
+        let mut operands = vec![self.operand()?];
+        while let Some(Token::Edge(direction)) = self.peek().cloned() {
+            if direction != self.graph.direction() {
+                let message = match direction {
+                    Direction::Directed => "'->' in an undirected graph - use '--'",
+                    Direction::Undirected => "'--' in a directed graph - use '->'",
+                };
+                return Err(self.error(message));
+            }
+            self.pos += 1;
+            operands.push(self.operand()?);
+        }
+        let attributes = self.attributes()?;
+
+        if operands.len() > 1 {
+            let weight = attributes.iter().find(|(key, _)| key == "weight").or_else(|| {
+                attributes.iter().find(|(key, _)| key == "label")
+            });
+            let weight = W::from_text(weight.map(|(_, value)| value.as_str()))
+                .map_err(|message| ParseError { line, message })?;
+            for pair in operands.windows(2) {
+                for &from in &pair[0] {
+                    for &to in &pair[1] {
+                        self.graph.add_edge(from, to, weight.clone());
+                    }
+                }
+            }
+        }
+        Ok(operands.concat())
+    }
+
+    // A node id (with any port after it dropped), or a subgraph
+    fn operand(&mut self) -> Result<Vec<NodeId>, ParseError> {
+        if self.keyword("subgraph") {
+            self.id();
+        } else if !matches!(self.peek(), Some(Token::Open)) {
+            let name = self.id().ok_or_else(|| self.error("expected a node"))?;
+            while self.skip(&Token::Colon) {
+                self.id().ok_or_else(|| self.error("expected a port after ':'"))?;
+            }
+            return Ok(vec![self.node(name)]);
+        }
+
+        self.expect(Token::Open, "'{'")?;
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let nodes = self.statements()?;
+        self.depth -= 1;
+        self.expect(Token::Close, "'}'")?;
+        Ok(nodes)
+    }
+
+    // Any number of [key=value, ...] lists, run together
+    fn attributes(&mut self) -> Result<Vec<(String, String)>, ParseError> {
+        let mut attributes = Vec::new();
+        while self.skip(&Token::OpenList) {
+            while !self.skip(&Token::CloseList) {
+                let key = self.id().ok_or_else(|| self.error("expected an attribute name or ']'"))?;
+                let value = if self.skip(&Token::Equals) {
+                    self.id().ok_or_else(|| self.error("expected a value after '='"))?
+                } else {
+                    String::from("true")
+                };
+                attributes.push((key, value));
+                if !self.skip(&Token::Comma) {
+                    self.skip(&Token::Semicolon);
+                }
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn node(&mut self, name: String) -> NodeId {
+        if let Some(&id) = self.names.get(&name) {
+            return id;
+        }
+        let id = self.graph.add_node(name.clone());
+        self.names.insert(name, id);
+        id
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn id(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(Token::Id { text, .. }) => {
+                let text = text.clone();
+                self.pos += 1;
+                Some(text)
+            }
+            _ => None,
+        }
+    }
+
+    // Keywords are case-insensitive, and only count when they aren't in quotes
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Id { text, quoted: false }) if text.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn skip(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), ParseError> {
+        if self.skip(&token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", what)))
+        }
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.pos).or_else(|| self.tokens.last()) {
+            Some((_, line)) => *line,
+            None => 1,
+        }
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError { line: self.line(), message: message.to_string() }
+    }
+}
+
+// One edge a line as from,to[,weight], then a line for each node that has no edges
+pub fn to_edge_list<N: Display, E: Weight>(graph: &Graph<N, E>) -> String {
+    let ids: Vec<NodeId> = graph.node_ids().collect();
+    let name = |id: NodeId| graph.node(id).unwrap().to_string();
+    let mut lines: Vec<Vec<String>> = Vec::new();
+
+    // reading gives nodes their ids in the order they first turn up, so any node an edge would bring in too late
+    // (one with no edges, or only ones further down) gets a line of its own just before it's needed
+    let mut introduced = 0;
+    for (from, to, weight) in graph.edges() {
+        loop {
+            let mut new: Vec<usize> = vec![from.index(), to.index()];
+            new.dedup();
+            new.retain(|&index| index >= introduced);
+            if new.iter().enumerate().all(|(i, &index)| index == introduced + i) {
+                introduced += new.len();
+                break;
+            }
+            lines.push(vec![name(ids[introduced])]);
+            introduced += 1;
+        }
+        let mut fields = vec![name(from), name(to)];
+        fields.extend(weight.to_text());
+        lines.push(fields);
+    }
+    lines.extend(ids[introduced..].iter().map(|&id| vec![name(id)]));
+
+    // quotes don't stop a first edge like "from,to" being read as a header, but a header before it does
+    let mut list = String::new();
+    if lines.first().is_some_and(|fields| is_header(fields)) {
+        list.push_str("from,to\n");
+    }
+    for fields in lines {
+        let fields: Vec<String> = fields.iter().map(|field| quote_field(field)).collect();
+        let _ = writeln!(list, "{}", fields.join(","));
+    }
+    list
+}
+
+// Quotes a field only when it has to be, so that it reads back the same
+fn quote_field(field: &str) -> String {
+    let plain = !field.is_empty()
+        && !field.starts_with('#')
+        && field.trim() == field
+        && !field.contains(|c: char| c == ',' || c == '"' || c.is_whitespace());
+    if plain {
+        field.to_string()
+    } else {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+}
+
+// Every edge goes the way `direction` says; the format has no way to say
+pub fn parse_edge_list<W: Weight>(text: &str, direction: Direction) -> Result<Graph<String, W>, ParseError> {
+    let mut graph = Graph::new(direction);
+    let mut names: HashMap<String, NodeId> = HashMap::new();
+    let mut first = true;
+
+    for (i, line) in text.lines().enumerate() {
+        let error = |message: String| ParseError { line: i + 1, message };
+        let fields = split_fields(line).map_err(error)?;
+        if fields.is_empty() {
+            continue;
+        }
+        let header = first && is_header(&fields);
+        first = false;
+        if header {
+            continue;
+        }
+        if fields.len() > 3 {
+            return Err(error(format!("expected from,to[,weight] but there are {} fields", fields.len())));
+        }
+
+        let mut node = |name: &str| match names.get(name) {
+            Some(&id) => id,
+            None => {
+                let id = graph.add_node(name.to_string());
+                names.insert(name.to_string(), id);
+                id
+            }
+        };
+        let from = node(&fields[0]);
+        if let Some(to) = fields.get(1) {
+            let to = node(to);
+            let weight = W::from_text(fields.get(2).map(String::as_str)).map_err(error)?;
+            graph.add_edge(from, to, weight);
+        }
+    }
+    Ok(graph)
+}
+
+// Whether a first line's fields are column names like from,to,weight or source,target rather than an edge
+fn is_header(fields: &[String]) -> bool {
+    fields.len() >= 2 && {
+        let (from, to) = (fields[0].to_lowercase(), fields[1].to_lowercase());
+        (from == "from" && to == "to") || (from == "source" && to == "target")
+    }
+}
+
+// The fields on a line, split on commas if it has any and on whitespace if not. Comments and blank lines have none.
+fn split_fields(line: &str) -> Result<Vec<String>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(Vec::new());
+    }
+    if !line.contains([',', '"']) {
+        return Ok(line.split_whitespace().map(String::from).collect());
+    }
+
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        let mut field = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err(String::from("unterminated quotes")),
+                }
+            }
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+            if !matches!(chars.peek(), Some(',') | None) {
+                return Err(String::from("expected ',' after a quoted field"));
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ',' {
+                    break;
+                }
+                field.push(c);
+                chars.next();
+            }
+            field = field.trim().to_string();
+        }
+        fields.push(field);
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algo;
+
+    fn names<W>(graph: &Graph<String, W>) -> Vec<&str> {
+        graph.node_ids().map(|id| graph.node(id).unwrap().as_str()).collect()
+    }
+
+    fn edges<W: Clone>(graph: &Graph<String, W>) -> Vec<(&str, &str, W)> {
+        let name = |id| graph.node(id).unwrap().as_str();
+        graph.edges().map(|(from, to, weight)| (name(from), name(to), weight.clone())).collect()
+    }
+
+    const DEPENDENCIES: &str = r#"
+        /* what needs building before what */
+        digraph "build order" {
+            rankdir = LR; // left to right
+            node [shape=box, style="rounded"]
+            app -> {http json} -> core;
+            app -> "log utils":n [weight=2]
+            "log utils" -> core
+            docs
+            # a line from the C preprocessor
+        }
+    "#;
+
+    #[test]
+    fn it_reads_dot() {
+        let graph: Graph<String, ()> = parse_dot(DEPENDENCIES).unwrap();
+
+        assert!(graph.is_directed());
+        assert_eq!(names(&graph), vec!["app", "http", "json", "core", "log utils", "docs"]);
+        assert_eq!(graph.edge_count(), 6);
+        let order = algo::toposort(&graph).unwrap();
+        let order: Vec<&str> = order.into_iter().map(|id| graph.node(id).unwrap().as_str()).collect();
+        assert_eq!(order, vec!["app", "http", "json", "log utils", "core", "docs"]);
+
+        let weighted: Graph<String, Option<u64>> = parse_dot(DEPENDENCIES).unwrap();
+        assert_eq!(edges(&weighted)[2], ("app", "log utils", Some(2)));
+        assert_eq!(edges(&weighted)[0], ("app", "http", None));
+    }
+
+    #[test]
+    fn dot_round_trips() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_node(String::from("a \"quoted\" name"));
+        let b = graph.add_node(String::from("b"));
+        graph.add_node(String::from("on its own"));
+        graph.add_edge(a, b, 1.5);
+        graph.add_edge(b, b, 2.0);
+
+        let dot = to_dot(&graph);
+        assert_eq!(
+            dot,
+            r#"graph {
+    "a \"quoted\" name";
+    "b";
+    "on its own";
+    "a \"quoted\" name" -- "b" [label="1.5"];
+    "b" -- "b" [label="2"];
+}
+"#
+        );
+        let back: Graph<String, f64> = parse_dot(&dot).unwrap();
+        assert!(!back.is_directed());
+        assert_eq!(names(&back), names(&graph));
+        assert_eq!(edges(&back), edges(&graph));
+    }
+
+    #[test]
+    fn dot_errors_say_which_line() {
+        let error = |text| parse_dot::<u64>(text).unwrap_err().to_string();
+
+        assert_eq!(error("digraph {\n a -- b\n}"), "line 2: '--' in a directed graph - use '->'");
+        assert_eq!(error("graph {\n a -- b [weight=heavy]\n}"), "line 2: 'heavy' isn't a valid weight");
+        assert_eq!(error("digraph {\n a -> b [weight=1]\n"), "line 2: expected '}'");
+        assert_eq!(error("tree { }"), "line 1: expected 'graph' or 'digraph'");
+        assert_eq!(error("digraph {\n\n a -> <b>\n}"), "line 3: HTML strings aren't supported");
+        assert_eq!(error("digraph { a -> b }"), "line 1: the edge has no weight");
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error_rather_than_a_stack_overflow() {
+        let nested = |depth| format!("digraph {{ {}a{} }}", "{".repeat(depth), "}".repeat(depth));
+
+        let graph: Graph<String, ()> = parse_dot(&nested(MAX_DEPTH)).unwrap();
+        assert_eq!(names(&graph), vec!["a"]);
+        assert_eq!(parse_dot::<()>(&nested(MAX_DEPTH + 1)).unwrap_err().message, "nested too deeply");
+        assert_eq!(parse_dot::<()>(&nested(100_000)).unwrap_err().message, "nested too deeply");
+    }
+
+    #[test]
+    fn it_reads_edge_lists_and_csv() {
+        let text = "From,To,Weight\n# a comment\napp,core,3\n\n\"log, utils\" , core, 1\napp,\"log, utils\"\nlonely\n";
+        let graph: Graph<String, Option<u32>> = parse_edge_list(text, Direction::Directed).unwrap();
+
+        assert_eq!(names(&graph), vec!["app", "core", "log, utils", "lonely"]);
+        assert_eq!(
+            edges(&graph),
+            vec![("app", "core", Some(3)), ("app", "log, utils", None), ("log, utils", "core", Some(1))]
+        );
+
+        let spaces: Graph<String, u64> = parse_edge_list("a b 4\nb c 5", Direction::Undirected).unwrap();
+        let (a, c) = (spaces.find_node(|name| name == "a").unwrap(), spaces.find_node(|name| name == "c").unwrap());
+        assert_eq!(algo::dijkstra(&spaces, c, |&weight| weight).distance(a), Some(9));
+    }
+
+    #[test]
+    fn edge_lists_round_trip() {
+        let mut graph = Graph::directed();
+        let a = graph.add_node(String::from("a,b"));
+        let b = graph.add_node(String::from("say \"hi\""));
+        graph.add_node(String::from("#not a comment"));
+        graph.add_edge(a, b, String::from("x y"));
+        graph.add_edge(b, a, String::from("plain"));
+
+        let list = to_edge_list(&graph);
+        assert_eq!(list, "\"a,b\",\"say \"\"hi\"\"\",\"x y\"\n\"say \"\"hi\"\"\",\"a,b\",plain\n\"#not a comment\"\n");
+        let back: Graph<String, String> = parse_edge_list(&list, Direction::Directed).unwrap();
+        assert_eq!(names(&back), names(&graph));
+        assert_eq!(edges(&back), edges(&graph));
+    }
+
+    #[test]
+    fn edge_lists_keep_node_ids_and_dont_lose_an_edge_to_a_header() {
+        let mut graph = Graph::directed();
+        let lonely = graph.add_node(String::from("lonely"));
+        let from = graph.add_node(String::from("Source"));
+        let to = graph.add_node(String::from("target"));
+        let last = graph.add_node(String::from("last"));
+        graph.add_edge(to, last, ());
+        graph.add_edge(from, to, ());
+
+        let list = to_edge_list(&graph);
+        assert_eq!(list, "lonely\nSource,target\ntarget,last\n");
+        let back: Graph<String, ()> = parse_edge_list(&list, Direction::Directed).unwrap();
+        assert_eq!(back.node(lonely), graph.node(lonely));
+        assert_eq!(names(&back), names(&graph));
+        assert_eq!(edges(&back), edges(&graph));
+
+        // with "lonely" gone, the first line would look like a header
+        let mut graph = Graph::undirected();
+        let (from, to) = (graph.add_node(String::from("from")), graph.add_node(String::from("to")));
+        graph.add_edge(from, to, 1u64);
+        let list = to_edge_list(&graph);
+        assert_eq!(list, "from,to\nfrom,to,1\n");
+        let back: Graph<String, u64> = parse_edge_list(&list, Direction::Undirected).unwrap();
+        assert_eq!(edges(&back), edges(&graph));
+    }
+
+    #[test]
+    fn edge_list_errors_say_which_line() {
+        let error = |text| parse_edge_list::<u64>(text, Direction::Directed).unwrap_err().to_string();
+
+        assert_eq!(error("a,b,1\na,b"), "line 2: the edge has no weight");
+        assert_eq!(error("a,b,c,d"), "line 1: expected from,to[,weight] but there are 4 fields");
+        assert_eq!(error("\n\"a,b"), "line 2: unterminated quotes");
+        assert_eq!(error("\"a\"b,c"), "line 1: expected ',' after a quoted field");
+    }
+
+    #[test]
+    fn files_are_read_by_their_extension() {
+        let dir = std::env::temp_dir();
+        let dot = dir.join(format!("graphs-format-test-{}.dot", std::process::id()));
+        let csv = dir.join(format!("graphs-format-test-{}.csv", std::process::id()));
+        fs::write(&dot, "graph { a -- b }").unwrap();
+        fs::write(&csv, "a,b\n").unwrap();
+
+        let from_dot: Graph<String, ()> = load(&dot).unwrap();
+        let from_csv: Graph<String, ()> = load(&csv).unwrap();
+        fs::remove_file(&dot).unwrap();
+        fs::remove_file(&csv).unwrap();
+
+        assert!(!from_dot.is_directed());
+        assert!(from_csv.is_directed());
+        assert!(matches!(load::<(), _>(&dot), Err(LoadError::Io(_))));
+    }
+}
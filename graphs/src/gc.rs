@@ -0,0 +1,269 @@
+// A pointer for graphs that really do need their back-edges to be strong - doubly linked lists, graphs where any node
+// can point at any other - and so can't get away with Weak the way the tree does. A Gc is an Rc underneath, and cycles
+// of them leak just the same, until Collector::collect finds them and frees them.
+//
+// Every Gc comes from a Collector, which keeps a Weak pointer to each one. collect works out which are still wanted
+// by "trial deletion": it takes away each strong count the links between Gcs account for (found through Trace), and
+// whatever has some count left over is held from outside - by a handle somewhere in the program. Those, and everything
+// they link to, are kept. The rest can only be reached from each other, so collect takes their values out, which
+// drops the Gcs inside them, which breaks the cycles and lets the counts get down to 0.
+//
+// A value that's borrowed mutably while collect runs can't be traced, so it's kept (along with anything it links to).
+//
+// A Drop impl on a collected value mustn't read through its links: all the garbage is taken out before any of it is
+// dropped, so those Gcs are already empty and borrowing one panics.
+
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+pub trait Trace: Sized {
+    // Calls `visit` with each Gc this value holds on to, once for each clone of it held. Visiting one too often can't
+    // make collect panic, but it can make it think something is garbage while a handle to it is still around.
+    fn trace(&self, visit: &mut dyn FnMut(&Gc<Self>));
+}
+
+pub struct Gc<T: Trace> {
+    inner: Rc<RefCell<Option<T>>>, // None once collected, which nothing outside can see
+}
+
+impl<T: Trace> Gc<T> {
+    // Panics if the value is borrowed mutably
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::map(self.inner.borrow(), |value| value.as_ref().expect("a Gc was used after being collected"))
+    }
+
+    // Panics if the value is borrowed at all
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        RefMut::map(self.inner.borrow_mut(), |value| value.as_mut().expect("a Gc was used after being collected"))
+    }
+
+    pub fn ptr_eq(this: &Gc<T>, other: &Gc<T>) -> bool {
+        Rc::ptr_eq(&this.inner, &other.inner)
+    }
+
+    // Handles to it and links to it from other Gcs alike
+    pub fn strong_count(this: &Gc<T>) -> usize {
+        Rc::strong_count(&this.inner)
+    }
+}
+
+impl<T: Trace> Clone for Gc<T> {
+    fn clone(&self) -> Gc<T> {
+        Gc { inner: Rc::clone(&self.inner) }
+    }
+}
+
+impl<T: Trace + fmt::Debug> fmt::Debug for Gc<T> {
+    // Only the address, since following the links could go round a cycle forever
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Gc({:p})", Rc::as_ptr(&self.inner))
+    }
+}
+
+pub struct Collector<T: Trace> {
+    objects: RefCell<Vec<Weak<RefCell<Option<T>>>>>,
+}
+
+impl<T: Trace> Collector<T> {
+    pub fn new() -> Collector<T> {
+        Collector { objects: RefCell::new(Vec::new()) }
+    }
+
+    pub fn alloc(&self, value: T) -> Gc<T> {
+        let inner = Rc::new(RefCell::new(Some(value)));
+        self.objects.borrow_mut().push(Rc::downgrade(&inner));
+        Gc { inner }
+    }
+
+    // How many of the Gcs from this collector are still alive, garbage included
+    pub fn len(&self) -> usize {
+        self.objects.borrow().iter().filter(|object| object.strong_count() > 0).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Frees every Gc that's only kept alive by cycles, and says how many that was
+    pub fn collect(&self) -> usize {
+        let objects: Vec<Rc<RefCell<Option<T>>>> = {
+            let mut weak = self.objects.borrow_mut();
+            weak.retain(|object| object.strong_count() > 0);
+            weak.iter().filter_map(Weak::upgrade).collect()
+        };
+        let index: HashMap<*const RefCell<Option<T>>, usize> =
+            objects.iter().enumerate().map(|(i, object)| (Rc::as_ptr(object), i)).collect();
+        let links = |i: usize| -> Option<Vec<usize>> {
+            let value = objects[i].try_borrow().ok()?;
+            let mut found = Vec::new();
+            if let Some(value) = value.as_ref() {
+                value.trace(&mut |gc| found.extend(index.get(&Rc::as_ptr(&gc.inner))));
+            }
+            Some(found)
+        };
+
+        // what's left of each count once the links between Gcs are taken off (and the Rc in `objects`)
+        let mut outside: Vec<usize> = objects.iter().map(|object| Rc::strong_count(object) - 1).collect();
+        let mut traced = vec![true; objects.len()];
+        for (i, seen_inside) in traced.iter_mut().enumerate() {
+            match links(i) {
+                Some(to) => to.into_iter().for_each(|j| outside[j] = outside[j].saturating_sub(1)),
+                None => *seen_inside = false,
+            }
+        }
+
+        // keep what's held from outside, or can't be looked inside, and everything they lead to
+        let mut keep: Vec<bool> = (0..objects.len()).map(|i| outside[i] > 0 || !traced[i]).collect();
+        let mut stack: Vec<usize> = (0..objects.len()).filter(|&i| keep[i]).collect();
+        while let Some(i) = stack.pop() {
+            for j in links(i).unwrap_or_default() {
+                if !keep[j] {
+                    keep[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+
+        // taking the values out first means none of them is dropped while another is being taken
+        let garbage: Vec<T> = objects
+            .iter()
+            .zip(&keep)
+            .filter(|(_, &keep)| !keep)
+            .filter_map(|(object, _)| object.borrow_mut().take())
+            .collect();
+        let collected = garbage.len();
+        drop(garbage);
+        collected
+    }
+}
+
+impl<T: Trace> Default for Collector<T> {
+    fn default() -> Collector<T> {
+        Collector::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // A node that can link to any other, counting how many nodes have been dropped
+    struct Vertex {
+        links: Vec<Gc<Vertex>>,
+        dropped: Rc<Cell<usize>>,
+    }
+
+    impl Trace for Vertex {
+        fn trace(&self, visit: &mut dyn FnMut(&Gc<Vertex>)) {
+            self.links.iter().for_each(visit);
+        }
+    }
+
+    impl Drop for Vertex {
+        fn drop(&mut self) {
+            self.dropped.set(self.dropped.get() + 1);
+        }
+    }
+
+    fn vertices(collector: &Collector<Vertex>, n: usize, dropped: &Rc<Cell<usize>>) -> Vec<Gc<Vertex>> {
+        (0..n).map(|_| collector.alloc(Vertex { links: Vec::new(), dropped: Rc::clone(dropped) })).collect()
+    }
+
+    fn link(from: &Gc<Vertex>, to: &Gc<Vertex>) {
+        from.borrow_mut().links.push(to.clone());
+    }
+
+    #[test]
+    fn cycles_are_freed_once_nothing_outside_holds_them() {
+        let collector = Collector::new();
+        let dropped = Rc::new(Cell::new(0));
+        let v = vertices(&collector, 4, &dropped);
+        // 0 <-> 1 -> 2 -> 2, and 3 -> 0
+        link(&v[0], &v[1]);
+        link(&v[1], &v[0]);
+        link(&v[1], &v[2]);
+        link(&v[2], &v[2]);
+        link(&v[3], &v[0]);
+
+        let three = v[3].clone();
+        drop(v);
+        // everything can still be got at through `three`
+        assert_eq!(collector.collect(), 0);
+        assert_eq!(dropped.get(), 0);
+        assert_eq!(three.borrow().links[0].borrow().links[0].borrow().links.len(), 2);
+
+        drop(three);
+        assert_eq!(dropped.get(), 1); // 3 wasn't in a cycle, so went straight away
+        assert_eq!(collector.len(), 3);
+        assert_eq!(collector.collect(), 3);
+        assert_eq!(dropped.get(), 4);
+        assert!(collector.is_empty());
+    }
+
+    #[test]
+    fn only_the_unreachable_part_goes() {
+        let collector = Collector::new();
+        let dropped = Rc::new(Cell::new(0));
+        let v = vertices(&collector, 4, &dropped);
+        link(&v[0], &v[1]);
+        link(&v[1], &v[0]);
+        link(&v[2], &v[3]);
+        link(&v[3], &v[2]);
+
+        let kept = v[2].clone();
+        drop(v);
+        assert_eq!(collector.collect(), 2);
+        assert_eq!(dropped.get(), 2);
+        assert_eq!(Gc::strong_count(&kept), 2);
+        assert!(Gc::ptr_eq(&kept.borrow().links[0].borrow().links[0], &kept));
+    }
+
+    // Reports its one link twice
+    struct Stutter {
+        link: Option<Gc<Stutter>>,
+    }
+
+    impl Trace for Stutter {
+        fn trace(&self, visit: &mut dyn FnMut(&Gc<Stutter>)) {
+            if let Some(link) = &self.link {
+                visit(link);
+                visit(link);
+            }
+        }
+    }
+
+    #[test]
+    fn links_reported_twice_dont_underflow_the_counts() {
+        let collector = Collector::new();
+        let a = collector.alloc(Stutter { link: None });
+        let b = collector.alloc(Stutter { link: Some(a.clone()) });
+        a.borrow_mut().link = Some(b.clone());
+
+        drop((a, b));
+        assert_eq!(collector.collect(), 2);
+        assert!(collector.is_empty());
+    }
+
+    #[test]
+    fn values_borrowed_mutably_are_kept() {
+        let collector = Collector::new();
+        let dropped = Rc::new(Cell::new(0));
+        let v = vertices(&collector, 2, &dropped);
+        link(&v[0], &v[1]);
+        link(&v[1], &v[0]);
+        let weak = Rc::downgrade(&v[0].inner);
+
+        let borrowed = v[0].clone();
+        let borrow = borrowed.borrow_mut();
+        drop(v);
+        assert_eq!(collector.collect(), 0);
+
+        drop(borrow);
+        drop(borrowed);
+        assert_eq!(collector.collect(), 2);
+        assert!(weak.upgrade().is_none());
+    }
+}
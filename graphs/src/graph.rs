@@ -0,0 +1,154 @@
+// A general graph, directed or undirected. Unlike the tree, nothing here is an Rc: the nodes live in a Vec and edges
+// refer to them by index (a NodeId), so edges can go anywhere - cycles included - without anything owning itself.
+// Each node has a list of the edges leaving it, and each edge carries a weight, which can be () when there isn't one.
+// The algorithms that run on it are in algo.rs.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    // Where the node is in the order they were added, starting at 0
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Directed,
+    Undirected, // every edge goes both ways
+}
+
+#[derive(Debug, Clone)]
+pub struct Graph<N, E> {
+    direction: Direction,
+    nodes: Vec<N>,
+    adjacency: Vec<Vec<(NodeId, E)>>, // the edges leaving each node, in the order they were added
+    edges: usize,
+}
+
+impl<N, E> Graph<N, E> {
+    pub fn new(direction: Direction) -> Graph<N, E> {
+        Graph { direction, nodes: Vec::new(), adjacency: Vec::new(), edges: 0 }
+    }
+
+    pub fn directed() -> Graph<N, E> {
+        Graph::new(Direction::Directed)
+    }
+
+    pub fn undirected() -> Graph<N, E> {
+        Graph::new(Direction::Undirected)
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.direction == Direction::Directed
+    }
+
+    pub fn add_node(&mut self, value: N) -> NodeId {
+        self.nodes.push(value);
+        self.adjacency.push(Vec::new());
+        NodeId(self.nodes.len() - 1)
+    }
+
+    // Panics if either node isn't in the graph. An undirected edge is stored at both ends, with a copy of the weight.
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: E)
+    where
+        E: Clone,
+    {
+        assert!(from.0 < self.nodes.len() && to.0 < self.nodes.len(), "{:?} -> {:?} isn't between two nodes", from, to);
+        if self.direction == Direction::Undirected && from != to {
+            self.adjacency[to.0].push((from, weight.clone()));
+        }
+        self.adjacency[from.0].push((to, weight));
+        self.edges += 1;
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // An undirected edge counts once
+    pub fn edge_count(&self) -> usize {
+        self.edges
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&N> {
+        self.nodes.get(id.0)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut N> {
+        self.nodes.get_mut(id.0)
+    }
+
+    // The first node whose value matches
+    pub fn find_node<P>(&self, predicate: P) -> Option<NodeId>
+    where
+        P: FnMut(&N) -> bool,
+    {
+        self.nodes.iter().position(predicate).map(NodeId)
+    }
+
+    pub fn node_ids(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.nodes.len()).map(NodeId)
+    }
+
+    // The edges leaving `id`, as where each one goes and its weight
+    pub fn edges_from(&self, id: NodeId) -> &[(NodeId, E)] {
+        self.adjacency.get(id.0).map_or(&[], |edges| edges)
+    }
+
+    // Where the edges leaving `id` go, and their weights
+    pub fn neighbours(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &E)> {
+        self.adjacency.get(id.0).into_iter().flatten().map(|(to, weight)| (*to, weight))
+    }
+
+    // Every edge as (from, to, weight). Undirected edges come once, from the end with the lower index (or from the
+    // node itself, for a loop).
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId, &E)> {
+        let undirected = self.direction == Direction::Undirected;
+        self.adjacency.iter().enumerate().flat_map(move |(from, edges)| {
+            edges
+                .iter()
+                .filter(move |(to, _)| !undirected || from <= to.0)
+                .map(move |(to, weight)| (NodeId(from), *to, weight))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undirected_edges_go_both_ways_but_count_once() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        graph.add_edge(a, b, 1);
+        graph.add_edge(c, a, 2);
+        graph.add_edge(c, c, 3);
+
+        assert_eq!(graph.neighbours(a).collect::<Vec<_>>(), vec![(b, &1), (c, &2)]);
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.edges().collect::<Vec<_>>(), vec![(a, b, &1), (a, c, &2), (c, c, &3)]);
+        assert_eq!(graph.find_node(|&name| name == "c"), Some(c));
+        assert_eq!(graph.node(b), Some(&"b"));
+    }
+
+    #[test]
+    fn directed_edges_only_go_one_way() {
+        let mut graph = Graph::directed();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        graph.add_edge(a, b, ());
+
+        assert_eq!(graph.neighbours(a).count(), 1);
+        assert_eq!(graph.neighbours(b).count(), 0);
+        assert_eq!(graph.edges().count(), 1);
+    }
+}
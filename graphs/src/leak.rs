@@ -0,0 +1,186 @@
+// Finding reference cycles in a structure built out of Rcs - the kind that the reference_cycle crate makes out of a
+// Cons list, where a's tail points at b and b's tail at a, so neither count ever gets to 0. Once the last handle from
+// outside is gone, nothing can free them.
+//
+// A type says which Rcs it holds by implementing Links. find_cycles then walks everything reachable from some roots,
+// builds a Graph with an edge for each strong link and looks for strongly connected components: any with more than
+// one node in it (or a node linking to itself) is a cycle of Rcs. Weak links aren't followed, since they can't keep
+// anything alive - which is why the tree can have its children point back at their parents without ever showing up.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::algo;
+use crate::graph::{Graph, NodeId};
+use crate::tree::Node;
+
+pub trait Links: Sized {
+    // Calls `visit` with each Rc this value holds on to
+    fn links(&self, visit: &mut dyn FnMut(&Rc<Self>));
+}
+
+// Children are strong, and the parent Weak
+impl<T> Links for Node<T> {
+    fn links(&self, visit: &mut dyn FnMut(&Rc<Node<T>>)) {
+        for child in self.children() {
+            visit(&child);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StrongCycle<T> {
+    pub nodes: Vec<Rc<T>>, // in the order they were found from the roots
+    // How many strong references to these nodes come from outside the cycle, handles to them included. Once they've
+    // all gone, the cycle is leaked.
+    pub outside: usize,
+}
+
+// The strong cycles among everything that can be reached from `roots`
+pub fn find_cycles<T: Links>(roots: &[&Rc<T>]) -> Vec<StrongCycle<T>> {
+    // an Rc to every node (taken off their counts again below), and the graph of links between them
+    let mut nodes: Vec<(Rc<T>, NodeId)> = Vec::new();
+    let mut graph = Graph::directed();
+    let mut ids: HashMap<*const T, NodeId> = HashMap::new();
+    let mut id_of = |rc: &Rc<T>, nodes: &mut Vec<(Rc<T>, NodeId)>, graph: &mut Graph<(), ()>| {
+        *ids.entry(Rc::as_ptr(rc)).or_insert_with(|| {
+            let id = graph.add_node(());
+            nodes.push((Rc::clone(rc), id));
+            id
+        })
+    };
+
+    for root in roots {
+        id_of(root, &mut nodes, &mut graph);
+    }
+    let mut next = 0;
+    while next < nodes.len() {
+        let (node, from) = (Rc::clone(&nodes[next].0), nodes[next].1);
+        let mut links = Vec::new();
+        node.links(&mut |link| links.push(Rc::clone(link)));
+        for link in links {
+            let to = id_of(&link, &mut nodes, &mut graph);
+            graph.add_edge(from, to, ());
+        }
+        next += 1;
+    }
+
+    let mut cycles = Vec::new();
+    for component in algo::strongly_connected_components(&graph) {
+        let looped = component.len() == 1 && graph.neighbours(component[0]).any(|(to, _)| to == component[0]);
+        if component.len() < 2 && !looped {
+            continue;
+        }
+
+        let inside = component
+            .iter()
+            .flat_map(|&from| graph.neighbours(from).filter(|(to, _)| component.contains(to)))
+            .count();
+        let total: usize = component.iter().map(|id| Rc::strong_count(&nodes[id.index()].0) - 1).sum();
+        cycles.push(StrongCycle {
+            nodes: component.iter().map(|id| Rc::clone(&nodes[id.index()].0)).collect(),
+            outside: total - inside,
+        });
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::Tree;
+    use std::cell::RefCell;
+
+    // The list from the reference_cycle crate
+    #[derive(Debug)]
+    enum List {
+        Cons(i32, RefCell<Rc<List>>),
+        Nil,
+    }
+
+    use self::List::{Cons, Nil};
+
+    impl List {
+        fn tail(&self) -> Option<&RefCell<Rc<List>>> {
+            match self {
+                Cons(_, item) => Some(item),
+                Nil => None,
+            }
+        }
+
+        fn value(&self) -> Option<i32> {
+            match self {
+                Cons(value, _) => Some(*value),
+                Nil => None,
+            }
+        }
+    }
+
+    impl Links for List {
+        fn links(&self, visit: &mut dyn FnMut(&Rc<List>)) {
+            if let Some(tail) = self.tail() {
+                visit(&tail.borrow());
+            }
+        }
+    }
+
+    fn values(cycle: &StrongCycle<List>) -> Vec<Option<i32>> {
+        let mut values: Vec<Option<i32>> = cycle.nodes.iter().map(|node| node.value()).collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn it_finds_the_cycle_from_the_book() {
+        let a = Rc::new(Cons(5, RefCell::new(Rc::new(Nil))));
+        let b = Rc::new(Cons(10, RefCell::new(Rc::clone(&a))));
+        assert!(find_cycles(&[&b]).is_empty());
+
+        *a.tail().unwrap().borrow_mut() = Rc::clone(&b);
+        let cycles = find_cycles(&[&a]);
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(values(&cycles[0]), vec![Some(5), Some(10)]);
+        assert_eq!(cycles[0].outside, 2); // `a` and `b`
+        drop(cycles);
+
+        // break the cycle, or the test leaks too
+        *a.tail().unwrap().borrow_mut() = Rc::new(Nil);
+        assert_eq!(Rc::strong_count(&b), 1);
+    }
+
+    #[test]
+    fn it_counts_what_keeps_each_cycle_alive() {
+        // root -> 1 -> 3 -> 2 -> 1, with only the root holding on from outside
+        let one = Rc::new(Cons(1, RefCell::new(Rc::new(Nil))));
+        let two = Rc::new(Cons(2, RefCell::new(Rc::clone(&one))));
+        let three = Rc::new(Cons(3, RefCell::new(two)));
+        *one.tail().unwrap().borrow_mut() = three;
+        let root = Rc::new(Cons(0, RefCell::new(one)));
+        // and one that holds itself
+        let me = Rc::new(Cons(4, RefCell::new(Rc::new(Nil))));
+        *me.tail().unwrap().borrow_mut() = Rc::clone(&me);
+
+        let cycles = find_cycles(&[&root, &me]);
+        let found: Vec<(Vec<Option<i32>>, usize)> =
+            cycles.iter().map(|cycle| (values(cycle), cycle.outside)).collect();
+        assert_eq!(found, vec![(vec![Some(1), Some(2), Some(3)], 1), (vec![Some(4)], 1)]);
+
+        for cycle in &cycles {
+            for node in &cycle.nodes {
+                *node.tail().unwrap().borrow_mut() = Rc::new(Nil);
+            }
+        }
+        drop(cycles);
+        assert_eq!(Rc::strong_count(&me), 1);
+    }
+
+    #[test]
+    fn trees_never_have_strong_cycles() {
+        let tree = Tree::new(1);
+        let child = tree.root().add_child(2);
+        child.add_child(3);
+        tree.root().add_child(4);
+
+        assert!(find_cycles(&[tree.root()]).is_empty());
+    }
+}
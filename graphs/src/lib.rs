@@ -0,0 +1,15 @@
+// Trees and graphs. The tree is built out of Rc, Weak and RefCell - see main.rs for the example from the book it
+// started as - while the graph keeps its nodes in a Vec and links them by index, since a graph's edges can go round in
+// cycles that Rcs would never free. When a structure really does need strong cycles, leak.rs finds them and gc.rs
+// can collect them. persistent.rs has a map whose updates make new versions that share what they didn't change.
+pub mod algo;
+pub mod format;
+pub mod gc;
+pub mod graph;
+pub mod leak;
+pub mod persistent;
+pub mod tree;
+
+pub use graph::{Direction, Graph, NodeId};
+pub use persistent::PersistentMap;
+pub use tree::{CycleError, Node, Tree};
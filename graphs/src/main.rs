@@ -1,41 +1,30 @@
 // we’ll create a tree whose items know about their children items and their parent items.
+// The Node itself lives in the library now (src/tree.rs), made generic and with methods that set up both sides of each
+// parent/child link - this is the walkthrough from the book using it.
 
-use std::rc::{Rc, Weak};
-use std::cell::RefCell;
+extern crate graphs;
+use graphs::algo::SortError;
+use graphs::{algo, format, Graph, Node};
+use std::env;
+use std::process;
+use std::rc::Rc;
 
-#[derive(Debug)]
-struct Node {
-    value: i32,
-    children: RefCell<Vec<Rc<Node>>>, // We want a Node to own its children, and we want to share that ownership with variables so we can access each Node in the tree directly. To do this, we define the Vec<T> items to be values of type Rc<Node>. We also want to modify which nodes are children of another node, so we have a RefCell<T> in children around the Vec<Rc<Node>>.
+fn main() {
+    // `cargo run -- deps.dot` (or an edge list, like deps.csv) prints an order to build the graph's nodes in
+    if let Some(path) = env::args().nth(1) {
+        print_build_order(&path);
+        return;
+    }
 
-    // To make the child node aware of its parent, we need to add a parent field to our Node struct definition. The trouble is in deciding what the type of parent should be. We know it can’t contain an Rc<T>, because that would create a reference cycle with leaf.parent pointing to branch and branch.children pointing to leaf, which would cause their strong_count values to never be 0...RefCell
+    let leaf = Node::new(5);
 
-    // Thinking about the relationships another way, a parent node should own its children: if a parent node is dropped, its child nodes should be dropped as well. However, a child should not own its parent: if we drop a child node, the parent should still exist. This is a case for weak references!
+    println!("leaf parent = {:?}", leaf.parent().map(|parent| *parent.value()));
 
-    // So instead of Rc<T>, we’ll make the type of parent use Weak<T>, specifically a RefCell<Weak<Node>>. Now our Node struct definition looks like this:
+    let branch = Node::new(10);
+    leaf.reparent(&branch).unwrap(); // branch.children now holds an Rc to leaf, and leaf.parent a Weak pointer to branch
 
-    parent: RefCell<Weak<Node>>
+    println!("leaf parent = {:?}", leaf.parent().map(|parent| *parent.value())); // upgrading the Weak gives a strong reference (that will fall out of scope at the end of the statement - for us to read value)
 
-}
-
-fn main() {
-    let leaf = Rc::new(Node {
-        value: 5,
-        children: RefCell::new(vec![]),
-        parent: RefCell::new(Weak::new())
-    });
-
-    println!("leaf parent = {:?}", leaf.parent.borrow().upgrade());
-
-    let branch = Rc::new(Node {
-        value: 10,
-        children: RefCell::new(vec![Rc::clone(&leaf)]), //We clone the Rc<Node> in leaf and store that in branch, meaning the Node in leaf now has two owners: leaf and branch. We can get from branch to leaf through branch.children, but there’s no way to get from leaf to branch. The reason is that leaf has no reference to branch and doesn’t know they’re related. We want leaf to know that branch is its parent. We’ll do that next.
-        parent: RefCell::new(Weak::new())
-    });
-    *leaf.parent.borrow_mut() = Rc::downgrade(&branch); // creates a weak pointer to the branch
-
-    println!("leaf parent = {:?}", leaf.parent.borrow().upgrade()); // this now creates a strong reference (that will fall out of scope at the end of main - for us to read value)
-    
     println!("Starting test scope...");
     test_scopes();
 }
@@ -43,11 +32,7 @@ fn main() {
 // Let’s look at how the strong_count and weak_count values of the Rc<Node> instances change by creating a new inner scope and moving the creation of branch into that scope. By doing so, we can see what happens when branch is created and then dropped when it goes out of scope.
 
 fn test_scopes() {
-    let leaf = Rc::new(Node {
-        value: 3,
-        parent: RefCell::new(Weak::new()),
-        children: RefCell::new(vec![]),
-    });
+    let leaf = Node::new(3);
 
     println!(
         "leaf strong = {}, weak = {}",
@@ -56,13 +41,8 @@ fn test_scopes() {
     );
 
     {
-        let branch = Rc::new(Node {
-            value: 5,
-            parent: RefCell::new(Weak::new()),
-            children: RefCell::new(vec![Rc::clone(&leaf)]),
-        });
-
-        *leaf.parent.borrow_mut() = Rc::downgrade(&branch);
+        let branch = Node::new(5);
+        leaf.reparent(&branch).unwrap();
 
         println!(
             "branch strong = {}, weak = {}",
@@ -77,10 +57,35 @@ fn test_scopes() {
         );
     }
 
-    println!("leaf parent = {:?}", leaf.parent.borrow().upgrade());
+    println!("leaf parent = {:?}", leaf.parent().map(|parent| *parent.value()));
     println!(
         "leaf strong = {}, weak = {}",
         Rc::strong_count(&leaf),
         Rc::weak_count(&leaf),
     );
-}
\ No newline at end of file
+}
+
+fn print_build_order(path: &str) {
+    let graph: Graph<String, ()> = format::load(path).unwrap_or_else(|e| {
+        eprintln!("Problem reading {}: {}", path, e);
+        process::exit(1);
+    });
+    match algo::toposort(&graph) {
+        // each node's edges go to what it needs, so those come first
+        Ok(order) => {
+            for id in order.into_iter().rev() {
+                println!("{}", graph.node(id).unwrap());
+            }
+        }
+        Err(SortError::Undirected) => {
+            eprintln!("{} isn't directed, so its edges don't say what needs what", path);
+            process::exit(1);
+        }
+        Err(SortError::Cycle(cycle)) => {
+            let round = cycle.0.iter().chain(cycle.0.first());
+            let names: Vec<&str> = round.map(|&id| graph.node(id).unwrap().as_str()).collect();
+            eprintln!("Nothing can go first - these depend on each other: {}", names.join(" -> "));
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,412 @@
+// A map that's never changed in place. insert and remove leave the map they're called on alone and hand back a new
+// version, which shares everything it can with the old one through Rc - the same trick as the lists in
+// combine_rc_and_refcell, where b and c both have a as their tail. Here it's a balanced (AVL) tree: an update copies
+// only the nodes on the path from the root down to the key, and the subtrees hanging off that path are shared. So a
+// new version costs O(log n) nodes, and keeping every old version around (for undo, or to look back at what the map
+// was) is cheap. Cloning a map is just cloning the Rc at its root.
+//
+// Nodes that get copied need their keys and values copied too, so both have to be Clone. Wrapping a big value in an
+// Rc makes that cheap.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+type Link<K, V> = Option<Rc<MapNode<K, V>>>;
+
+struct MapNode<K, V> {
+    key: K,
+    value: V,
+    height: usize, // of the subtree under this node, counting itself
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+pub struct PersistentMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K: Ord + Clone, V: Clone> PersistentMap<K, V> {
+    pub fn new() -> PersistentMap<K, V> {
+        PersistentMap { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut link = &self.root;
+        while let Some(node) = link {
+            match key.cmp(node.key.borrow()) {
+                Ordering::Less => link = &node.left,
+                Ordering::Greater => link = &node.right,
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+        None
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    // A new version with `key` set to `value`, replacing what was there before
+    pub fn insert(&self, key: K, value: V) -> PersistentMap<K, V> {
+        let (root, replaced) = insert(&self.root, key, value);
+        PersistentMap { root: Some(root), len: if replaced { self.len } else { self.len + 1 } }
+    }
+
+    // A new version without `key`. If it wasn't there, that's this version again.
+    pub fn remove<Q>(&self, key: &Q) -> PersistentMap<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        match remove(&self.root, key) {
+            Some(root) => PersistentMap { root, len: self.len - 1 },
+            None => self.clone(),
+        }
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(left) = &node.left {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(right) = &node.right {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    // In key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new(), remaining: self.len };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    // Whether two versions are the same one, not just equal - cheap, unlike ==
+    pub fn ptr_eq(&self, other: &PersistentMap<K, V>) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+fn height<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn node<K, V>(key: K, value: V, left: Link<K, V>, right: Link<K, V>) -> Rc<MapNode<K, V>> {
+    let height = 1 + height(&left).max(height(&right));
+    Rc::new(MapNode { key, value, height, left, right })
+}
+
+// Like node, but rotates if one side has got two taller than the other. Only the nodes being rotated are copied - the
+// subtrees that move between them are still shared.
+fn balance<K: Clone, V: Clone>(key: K, value: V, left: Link<K, V>, right: Link<K, V>) -> Rc<MapNode<K, V>> {
+    let (left_height, right_height) = (height(&left), height(&right));
+    if left_height > right_height + 1 {
+        let l = left.expect("the taller side has a node");
+        if height(&l.left) >= height(&l.right) {
+            let top = node(key, value, l.right.clone(), right);
+            node(l.key.clone(), l.value.clone(), l.left.clone(), Some(top))
+        } else {
+            let lr = l.right.as_ref().expect("the taller side has a node");
+            let lower_left = node(l.key.clone(), l.value.clone(), l.left.clone(), lr.left.clone());
+            let lower_right = node(key, value, lr.right.clone(), right);
+            node(lr.key.clone(), lr.value.clone(), Some(lower_left), Some(lower_right))
+        }
+    } else if right_height > left_height + 1 {
+        let r = right.expect("the taller side has a node");
+        if height(&r.right) >= height(&r.left) {
+            let top = node(key, value, left, r.left.clone());
+            node(r.key.clone(), r.value.clone(), Some(top), r.right.clone())
+        } else {
+            let rl = r.left.as_ref().expect("the taller side has a node");
+            let lower_left = node(key, value, left, rl.left.clone());
+            let lower_right = node(r.key.clone(), r.value.clone(), rl.right.clone(), r.right.clone());
+            node(rl.key.clone(), rl.value.clone(), Some(lower_left), Some(lower_right))
+        }
+    } else {
+        node(key, value, left, right)
+    }
+}
+
+// The new subtree, and whether `key` was already in it. The recursion only goes as deep as the tree, which being
+// balanced is about log2 of its size.
+fn insert<K: Ord + Clone, V: Clone>(link: &Link<K, V>, key: K, value: V) -> (Rc<MapNode<K, V>>, bool) {
+    let old = match link {
+        Some(old) => old,
+        None => return (node(key, value, None, None), false),
+    };
+    match key.cmp(&old.key) {
+        Ordering::Less => {
+            let (left, replaced) = insert(&old.left, key, value);
+            (balance(old.key.clone(), old.value.clone(), Some(left), old.right.clone()), replaced)
+        }
+        Ordering::Greater => {
+            let (right, replaced) = insert(&old.right, key, value);
+            (balance(old.key.clone(), old.value.clone(), old.left.clone(), Some(right)), replaced)
+        }
+        Ordering::Equal => (node(key, value, old.left.clone(), old.right.clone()), true),
+    }
+}
+
+// The new subtree, or None if `key` wasn't in it (so nothing needs copying)
+fn remove<K, V, Q>(link: &Link<K, V>, key: &Q) -> Option<Link<K, V>>
+where
+    K: Ord + Clone + Borrow<Q>,
+    V: Clone,
+    Q: Ord + ?Sized,
+{
+    let old = link.as_ref()?;
+    let subtree = match key.cmp(old.key.borrow()) {
+        Ordering::Less => {
+            let left = remove(&old.left, key)?;
+            balance(old.key.clone(), old.value.clone(), left, old.right.clone())
+        }
+        Ordering::Greater => {
+            let right = remove(&old.right, key)?;
+            balance(old.key.clone(), old.value.clone(), old.left.clone(), right)
+        }
+        Ordering::Equal => match (&old.left, &old.right) {
+            (None, right) => return Some(right.clone()),
+            (left, None) => return Some(left.clone()),
+            // the smallest key on the right takes this one's place
+            (left, Some(right)) => {
+                let (key, value, rest) = remove_first(right);
+                balance(key, value, left.clone(), rest)
+            }
+        },
+    };
+    Some(Some(subtree))
+}
+
+fn remove_first<K: Clone, V: Clone>(old: &Rc<MapNode<K, V>>) -> (K, V, Link<K, V>) {
+    match &old.left {
+        None => (old.key.clone(), old.value.clone(), old.right.clone()),
+        Some(left) => {
+            let (key, value, rest) = remove_first(left);
+            (key, value, Some(balance(old.key.clone(), old.value.clone(), rest, old.right.clone())))
+        }
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a MapNode<K, V>>, // the nodes still to come whose left subtrees are done
+    remaining: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut link: &'a Link<K, V>) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+impl<'a, K: Ord + Clone, V: Clone> IntoIterator for &'a PersistentMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+// Shares everything - no keys or values are copied
+impl<K, V> Clone for PersistentMap<K, V> {
+    fn clone(&self) -> PersistentMap<K, V> {
+        PersistentMap { root: self.root.clone(), len: self.len }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Default for PersistentMap<K, V> {
+    fn default() -> PersistentMap<K, V> {
+        PersistentMap::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> FromIterator<(K, V)> for PersistentMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> PersistentMap<K, V> {
+        iter.into_iter().fold(PersistentMap::new(), |map, (key, value)| map.insert(key, value))
+    }
+}
+
+impl<K: Ord + Clone, V: Clone + PartialEq> PartialEq for PersistentMap<K, V> {
+    fn eq(&self, other: &PersistentMap<K, V>) -> bool {
+        self.ptr_eq(other) || (self.len == other.len && self.iter().eq(other.iter()))
+    }
+}
+
+impl<K: Ord + Clone, V: Clone + Eq> Eq for PersistentMap<K, V> {}
+
+impl<K: Ord + Clone + fmt::Debug, V: Clone + fmt::Debug> fmt::Debug for PersistentMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashSet};
+
+    // A small xorshift, so the tests get the same "random" operations every run
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    // Checks the keys are in order and every node is balanced, and returns the height
+    fn check_balanced<K: Ord, V>(link: &Link<K, V>, above: Option<&K>, below: Option<&K>) -> usize {
+        let node = match link {
+            Some(node) => node,
+            None => return 0,
+        };
+        assert!(above.is_none_or(|above| *above < node.key) && below.is_none_or(|below| node.key < *below));
+        let left = check_balanced(&node.left, above, Some(&node.key));
+        let right = check_balanced(&node.right, Some(&node.key), below);
+        assert!(left.max(right) - left.min(right) <= 1, "a node is out of balance");
+        assert_eq!(node.height, 1 + left.max(right));
+        node.height
+    }
+
+    // Every node reachable from `map`, by address
+    fn nodes<K, V>(map: &PersistentMap<K, V>, seen: &mut HashSet<*const MapNode<K, V>>) {
+        let mut stack: Vec<&Rc<MapNode<K, V>>> = map.root.iter().collect();
+        while let Some(node) = stack.pop() {
+            if seen.insert(Rc::as_ptr(node)) {
+                stack.extend(node.left.iter().chain(node.right.iter()));
+            }
+        }
+    }
+
+    #[test]
+    fn every_version_matches_a_btreemap() {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        let mut versions = vec![(PersistentMap::new(), BTreeMap::new())];
+        for _ in 0..3000 {
+            let (mut map, mut model) = versions.last().unwrap().clone();
+            let key = random.below(300);
+            if random.below(3) == 0 {
+                map = map.remove(&key);
+                model.remove(&key);
+            } else {
+                let value = random.below(1000);
+                map = map.insert(key, value);
+                model.insert(key, value);
+            }
+            versions.push((map, model));
+        }
+
+        // the old versions are all still there, untouched by what came after them
+        for (map, model) in &versions {
+            assert_eq!(map.len(), model.len());
+            assert!(map.iter().eq(model.iter()));
+            assert_eq!(map.first(), model.iter().next());
+            assert_eq!(map.last(), model.iter().next_back());
+            for key in 0..300 {
+                assert_eq!(map.get(&key), model.get(&key));
+            }
+            check_balanced(&map.root, None, None);
+        }
+    }
+
+    #[test]
+    fn updates_share_everything_off_the_path() {
+        let map: PersistentMap<u32, String> = (0..1024).map(|i| (i * 2, i.to_string())).collect();
+        let height = check_balanced(&map.root, None, None);
+        assert!(height <= 12, "1024 keys shouldn't need a height of {}", height);
+
+        let mut before = HashSet::new();
+        nodes(&map, &mut before);
+        for changed in &[map.insert(501, "new".to_string()), map.insert(500, "changed".to_string()), map.remove(&500)] {
+            let mut after = HashSet::new();
+            nodes(changed, &mut after);
+            assert!(after.difference(&before).count() <= height + 2);
+        }
+        assert_eq!(map.get(&500).map(String::as_str), Some("250"));
+        assert_eq!(map.len(), 1024);
+
+        // taking out something that isn't there copies nothing
+        assert!(map.remove(&501).ptr_eq(&map));
+    }
+
+    #[test]
+    fn keeping_every_version_is_cheap() {
+        let mut versions = vec![PersistentMap::new()];
+        for i in 0..2000u32 {
+            let next = versions.last().unwrap().insert(i, i);
+            versions.push(next);
+        }
+
+        let mut all = HashSet::new();
+        for version in &versions {
+            nodes(version, &mut all);
+        }
+        // a copy of everything in every version would be about two million nodes
+        assert!(all.len() < 2000 * 16, "{} nodes for 2000 versions", all.len());
+        assert_eq!(versions[1000].len(), 1000);
+        assert_eq!(versions[1000].keys().last(), Some(&999));
+        assert_eq!(versions[2000], versions[2000].clone());
+        assert_ne!(versions[1999], versions[2000]);
+    }
+}
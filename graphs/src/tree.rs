@@ -0,0 +1,338 @@
+// A tree whose nodes know about their children and their parent - the Node from the book, made generic and given
+// methods that keep the links on both sides in step. Parents own their children through Rc, and children only point
+// back at their parent through Weak, so a tree never owns itself and everything in it is freed once the last outside
+// Rc to it goes. The one way to get a cycle of Rcs would be to make a node a child of one of its own descendants, and
+// reparent refuses to do that.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::rc::{Rc, Weak};
+
+#[derive(Debug)]
+pub struct Node<T> {
+    value: T,
+    children: RefCell<Vec<Rc<Node<T>>>>, // We want a Node to own its children, and we want to share that ownership with variables so we can access each Node in the tree directly. To do this, we define the Vec<T> items to be values of type Rc<Node>. We also want to modify which nodes are children of another node, so we have a RefCell<T> in children around the Vec<Rc<Node>>.
+
+    // Thinking about the relationships another way, a parent node should own its children: if a parent node is dropped, its child nodes should be dropped as well. However, a child should not own its parent: if we drop a child node, the parent should still exist. This is a case for weak references!
+    parent: RefCell<Weak<Node<T>>>,
+}
+
+// Asked to put a node under itself or one of its own descendants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleError;
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a node can't be moved under itself or one of its descendants")
+    }
+}
+
+impl Error for CycleError {}
+
+impl<T> Node<T> {
+    // A node on its own, with no parent or children
+    pub fn new(value: T) -> Rc<Node<T>> {
+        Rc::new(Node { value, children: RefCell::new(Vec::new()), parent: RefCell::new(Weak::new()) })
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn parent(&self) -> Option<Rc<Node<T>>> {
+        self.parent.borrow().upgrade()
+    }
+
+    // The children, first to last. The Vec is a copy, so the tree can be changed while going through it.
+    pub fn children(&self) -> Vec<Rc<Node<T>>> {
+        self.children.borrow().clone()
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.parent().is_none()
+    }
+
+    // How many ancestors the node has
+    pub fn depth(&self) -> usize {
+        self.ancestors().count()
+    }
+
+    // Adds a new node holding `value` after the node's other children, and hands it back
+    pub fn add_child(self: &Rc<Self>, value: T) -> Rc<Node<T>> {
+        let child = Node::new(value);
+        *child.parent.borrow_mut() = Rc::downgrade(self);
+        self.children.borrow_mut().push(Rc::clone(&child));
+        child
+    }
+
+    // Takes the node (and everything under it) away from its parent, making it the root of a tree of its own. The
+    // parent's Rc to it goes, so unless something else holds on to it, the whole subtree is dropped.
+    pub fn detach(&self) {
+        if let Some(parent) = self.parent() {
+            parent.children.borrow_mut().retain(|child| !ptr_eq(child, self));
+        }
+        *self.parent.borrow_mut() = Weak::new();
+    }
+
+    // Moves the node (and everything under it) to the end of `parent`'s children
+    pub fn reparent(self: &Rc<Self>, parent: &Rc<Node<T>>) -> Result<(), CycleError> {
+        if Rc::ptr_eq(self, parent) || parent.ancestors().any(|ancestor| Rc::ptr_eq(&ancestor, self)) {
+            return Err(CycleError);
+        }
+        self.detach();
+        *self.parent.borrow_mut() = Rc::downgrade(parent);
+        parent.children.borrow_mut().push(Rc::clone(self));
+        Ok(())
+    }
+
+    // The parent, its parent and so on up to the root
+    pub fn ancestors(&self) -> Ancestors<T> {
+        Ancestors { next: self.parent() }
+    }
+
+    // Everything under the node, depth first: each node comes before its children, which come in order
+    pub fn descendants(&self) -> DepthFirst<T> {
+        DepthFirst { stack: self.children.borrow().iter().rev().cloned().collect() }
+    }
+
+    // The node followed by its descendants
+    pub fn depth_first(self: &Rc<Self>) -> DepthFirst<T> {
+        DepthFirst { stack: vec![Rc::clone(self)] }
+    }
+
+    // The node, then its children, then their children and so on, a level at a time
+    pub fn breadth_first(self: &Rc<Self>) -> BreadthFirst<T> {
+        BreadthFirst { queue: VecDeque::from(vec![Rc::clone(self)]) }
+    }
+}
+
+// Dropping a node drops its children, which drop theirs... one stack frame per level, which runs out on a tree that's
+// deep enough. So this takes the children out first and drops the subtree a node at a time instead.
+impl<T> Drop for Node<T> {
+    fn drop(&mut self) {
+        let mut stack = mem::take(self.children.get_mut());
+        while let Some(child) = stack.pop() {
+            if let Ok(mut child) = Rc::try_unwrap(child) {
+                stack.append(child.children.get_mut());
+            }
+        }
+    }
+}
+
+fn ptr_eq<T>(rc: &Rc<Node<T>>, node: &Node<T>) -> bool {
+    std::ptr::eq(Rc::as_ptr(rc), node)
+}
+
+pub struct Ancestors<T> {
+    next: Option<Rc<Node<T>>>,
+}
+
+impl<T> Iterator for Ancestors<T> {
+    type Item = Rc<Node<T>>;
+
+    fn next(&mut self) -> Option<Rc<Node<T>>> {
+        let node = self.next.take()?;
+        self.next = node.parent();
+        Some(node)
+    }
+}
+
+pub struct DepthFirst<T> {
+    stack: Vec<Rc<Node<T>>>,
+}
+
+impl<T> Iterator for DepthFirst<T> {
+    type Item = Rc<Node<T>>;
+
+    fn next(&mut self) -> Option<Rc<Node<T>>> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children.borrow().iter().rev().cloned());
+        Some(node)
+    }
+}
+
+pub struct BreadthFirst<T> {
+    queue: VecDeque<Rc<Node<T>>>,
+}
+
+impl<T> Iterator for BreadthFirst<T> {
+    type Item = Rc<Node<T>>;
+
+    fn next(&mut self) -> Option<Rc<Node<T>>> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.children.borrow().iter().cloned());
+        Some(node)
+    }
+}
+
+// Owns a tree through its root. Nodes can be held on to and changed directly as well; this is just somewhere to keep
+// the root and ask about the tree as a whole.
+#[derive(Debug)]
+pub struct Tree<T> {
+    root: Rc<Node<T>>,
+}
+
+impl<T> Tree<T> {
+    pub fn new(value: T) -> Tree<T> {
+        Tree { root: Node::new(value) }
+    }
+
+    pub fn root(&self) -> &Rc<Node<T>> {
+        &self.root
+    }
+
+    // How many nodes there are, the root included
+    pub fn len(&self) -> usize {
+        self.root.depth_first().count()
+    }
+
+    // Never true - a tree always has its root
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn depth_first(&self) -> DepthFirst<T> {
+        self.root.depth_first()
+    }
+
+    pub fn breadth_first(&self) -> BreadthFirst<T> {
+        self.root.breadth_first()
+    }
+
+    // The first node depth first whose value matches
+    pub fn find<P>(&self, mut predicate: P) -> Option<Rc<Node<T>>>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.depth_first().find(|node| predicate(node.value()))
+    }
+}
+
+impl<T> From<Rc<Node<T>>> for Tree<T> {
+    // The tree under `node`, which is detached from its parent if it has one
+    fn from(node: Rc<Node<T>>) -> Tree<T> {
+        node.detach();
+        Tree { root: node }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //        1
+    //      / | \
+    //     2  3  4
+    //    / \     \
+    //   5   6     7
+    fn tree() -> Tree<i32> {
+        let tree = Tree::new(1);
+        let two = tree.root().add_child(2);
+        tree.root().add_child(3);
+        let four = tree.root().add_child(4);
+        two.add_child(5);
+        two.add_child(6);
+        four.add_child(7);
+        tree
+    }
+
+    fn values(nodes: impl Iterator<Item = Rc<Node<i32>>>) -> Vec<i32> {
+        nodes.map(|node| *node.value()).collect()
+    }
+
+    fn node(tree: &Tree<i32>, value: i32) -> Rc<Node<i32>> {
+        tree.find(|&v| v == value).unwrap()
+    }
+
+    #[test]
+    fn it_walks_the_tree_in_either_order() {
+        let tree = tree();
+
+        assert_eq!(values(tree.depth_first()), vec![1, 2, 5, 6, 3, 4, 7]);
+        assert_eq!(values(tree.breadth_first()), vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(values(node(&tree, 2).descendants()), vec![5, 6]);
+        assert_eq!(values(node(&tree, 7).ancestors()), vec![4, 1]);
+        assert_eq!(node(&tree, 6).depth(), 2);
+        assert_eq!(tree.len(), 7);
+    }
+
+    #[test]
+    fn nodes_can_be_moved_around_and_taken_out() {
+        let tree = tree();
+        let two = node(&tree, 2);
+
+        two.reparent(&node(&tree, 7)).unwrap();
+        assert_eq!(values(tree.depth_first()), vec![1, 3, 4, 7, 2, 5, 6]);
+        assert_eq!(values(two.ancestors()), vec![7, 4, 1]);
+
+        let four = node(&tree, 4);
+        four.detach();
+        assert!(four.is_root());
+        assert_eq!(values(tree.depth_first()), vec![1, 3]);
+        assert_eq!(values(four.depth_first()), vec![4, 7, 2, 5, 6]);
+
+        // and back again, onto a node that wasn't in the tree at all
+        four.reparent(tree.root()).unwrap();
+        assert_eq!(tree.len(), 7);
+    }
+
+    #[test]
+    fn a_node_cant_go_under_itself() {
+        let tree = tree();
+        let two = node(&tree, 2);
+
+        assert_eq!(two.reparent(&two), Err(CycleError));
+        assert_eq!(tree.root().reparent(&node(&tree, 5)), Err(CycleError));
+        assert_eq!(values(tree.depth_first()), vec![1, 2, 5, 6, 3, 4, 7]);
+    }
+
+    #[test]
+    fn children_hold_strong_references_and_parents_weak_ones() {
+        let tree = tree();
+        let two = node(&tree, 2);
+
+        // `two` itself, and its parent's children
+        assert_eq!(Rc::strong_count(&two), 2);
+        // one for each child pointing back at it
+        assert_eq!(Rc::weak_count(&two), 2);
+        assert_eq!(Rc::weak_count(tree.root()), 3);
+
+        two.detach();
+        assert_eq!(Rc::strong_count(&two), 1);
+        assert_eq!(Rc::weak_count(tree.root()), 2);
+    }
+
+    #[test]
+    fn nothing_is_left_once_the_tree_is_dropped() {
+        let tree = tree();
+        let moved = node(&tree, 3);
+        moved.reparent(&node(&tree, 6)).unwrap();
+        node(&tree, 4).detach();
+        drop(moved);
+
+        let everything: Vec<Weak<Node<i32>>> = tree.depth_first().map(|node| Rc::downgrade(&node)).collect();
+        assert_eq!(everything.len(), 5);
+        assert!(everything.iter().all(|node| node.strong_count() == 1));
+
+        drop(tree);
+        assert!(everything.iter().all(|node| node.upgrade().is_none()));
+    }
+
+    #[test]
+    fn deep_trees_drop_without_running_out_of_stack() {
+        let tree = Tree::new(0);
+        let mut last = Rc::clone(tree.root());
+        for i in 1..200_000 {
+            last = last.add_child(i);
+        }
+        let leaf = Rc::downgrade(&last);
+        drop(last);
+
+        assert_eq!(leaf.upgrade().map(|leaf| leaf.depth()), Some(199_999));
+        drop(tree);
+        assert!(leaf.upgrade().is_none());
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::{self, Write};
+
+use crate::{describe, GuessingGame, Response, State};
+
+// A computer player that halves the range of possible answers with every guess, so it never needs more than
+// log2(n) + 1 guesses for n numbers - even against a host that's trying to make it lose.
+pub struct Bisector {
+    low: u32,
+    high: u32,
+}
+
+impl Bisector {
+    pub fn new(game: &GuessingGame) -> Bisector {
+        Bisector { low: *game.range().start(), high: *game.range().end() }
+    }
+
+    pub fn next_guess(&self) -> u32 {
+        self.low + (self.high - self.low) / 2
+    }
+
+    pub fn observe(&mut self, guess: u32, response: &Response) {
+        match response {
+            Response::TooSmall => self.low = guess + 1,
+            Response::TooBig => self.high = guess - 1,
+            _ => {}
+        }
+    }
+}
+
+// Lets the bot play the game to the end, writing each guess and hint to `output`.
+pub fn run_bot<W: Write>(game: &mut GuessingGame, output: &mut W) -> io::Result<()> {
+    let mut bot = Bisector::new(game);
+
+    while game.state() == State::Playing {
+        let guess = bot.next_guess();
+        writeln!(output, "Bot guesses {}", guess)?;
+
+        // the bot goes through the same text interface a person would
+        let response = game.guess(&guess.to_string());
+        writeln!(output, "{}", describe(&response))?;
+        bot.observe(guess, &response);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::AdversarialHost;
+
+    #[test]
+    fn it_finds_every_secret_within_seven_guesses() {
+        for secret in 1..=100 {
+            let mut game = GuessingGame::with_secret(1..=100, Some(7), secret).unwrap();
+            run_bot(&mut game, &mut io::sink()).unwrap();
+
+            assert_eq!(game.state(), State::Won, "secret {}", secret);
+        }
+    }
+
+    #[test]
+    fn it_beats_the_adversary_in_the_worst_case_number_of_guesses() {
+        let mut game = GuessingGame::with_host(1..=100, None, Box::new(AdversarialHost::new(1, 100))).unwrap();
+        let mut output = Vec::new();
+
+        run_bot(&mut game, &mut output).unwrap();
+
+        assert_eq!(game.state(), State::Won);
+        assert_eq!(game.attempts(), 7);
+        assert!(String::from_utf8(output).unwrap().starts_with("Bot guesses 50\nToo small!\n"));
+    }
+
+    #[test]
+    fn the_adversary_wins_when_there_are_too_few_guesses() {
+        let mut game = GuessingGame::with_host(1..=100, Some(6), Box::new(AdversarialHost::new(1, 100))).unwrap();
+
+        run_bot(&mut game, &mut io::sink()).unwrap();
+
+        assert_eq!(game.state(), State::Lost);
+    }
+}
@@ -0,0 +1,107 @@
+use std::cmp::Ordering;
+
+// The side of the game that knows (or claims to know) the answer.
+pub trait Host {
+    // How `guess` compares to the answer: Less means the guess is too small.
+    fn compare(&mut self, guess: u32) -> Ordering;
+
+    // An answer that agrees with every hint given so far, for when the game is lost.
+    fn reveal(&self) -> u32;
+}
+
+// The honest host: picks a number up front and sticks to it.
+pub struct SecretHost {
+    secret: u32,
+}
+
+impl SecretHost {
+    pub fn new(secret: u32) -> SecretHost {
+        SecretHost { secret }
+    }
+}
+
+impl Host for SecretHost {
+    fn compare(&mut self, guess: u32) -> Ordering {
+        guess.cmp(&self.secret)
+    }
+
+    fn reveal(&self) -> u32 {
+        self.secret
+    }
+}
+
+// The cheating host: never commits to a number. It only tracks which numbers are still consistent with its hints
+// and answers each guess so that as many of them as possible stay possible - it can't be caught lying, but it
+// forces the guesser into the worst case every time.
+pub struct AdversarialHost {
+    low: u32,
+    high: u32,
+}
+
+impl AdversarialHost {
+    pub fn new(low: u32, high: u32) -> AdversarialHost {
+        AdversarialHost { low, high }
+    }
+
+    // How many answers are still possible
+    pub fn candidates(&self) -> u32 {
+        self.high - self.low + 1
+    }
+}
+
+impl Host for AdversarialHost {
+    fn compare(&mut self, guess: u32) -> Ordering {
+        // guesses outside what's still possible get the only consistent answer
+        if guess < self.low {
+            return Ordering::Less;
+        }
+        if guess > self.high {
+            return Ordering::Greater;
+        }
+        if self.low == self.high {
+            return Ordering::Equal;
+        }
+
+        let below = guess - self.low;
+        let above = self.high - guess;
+
+        // keep whichever side has more numbers left in it
+        if above >= below {
+            self.low = guess + 1;
+            Ordering::Less
+        } else {
+            self.high = guess - 1;
+            Ordering::Greater
+        }
+    }
+
+    fn reveal(&self) -> u32 {
+        self.low
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_bigger_half_of_the_candidates() {
+        let mut host = AdversarialHost::new(1, 10);
+
+        assert_eq!(host.compare(3), Ordering::Less);
+        assert_eq!((host.low, host.high), (4, 10));
+        assert_eq!(host.compare(9), Ordering::Greater);
+        assert_eq!((host.low, host.high), (4, 8));
+        assert_eq!(host.compare(1), Ordering::Less);
+        assert_eq!(host.candidates(), 5);
+    }
+
+    #[test]
+    fn it_only_concedes_when_one_number_is_left() {
+        let mut host = AdversarialHost::new(5, 6);
+
+        assert_eq!(host.compare(5), Ordering::Less);
+        assert_eq!(host.compare(6), Ordering::Equal);
+        assert_eq!(host.reveal(), 6);
+    }
+}
@@ -0,0 +1,295 @@
+use std::cmp::Ordering;
+use std::io::{self, BufRead, Write};
+use std::ops::RangeInclusive;
+
+use rand::Rng;
+
+pub mod bot;
+pub mod host;
+
+use host::{AdversarialHost, Host, SecretHost};
+
+// What the game says back to a line of input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    TooSmall,
+    TooBig,
+    Win,
+    Lose { secret: u32 }, // the last allowed attempt was wrong
+    Invalid(Invalid),     // doesn't use up an attempt
+    GameOver,             // the game has already been won or lost
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Invalid {
+    NotANumber(String),
+    OutOfRange(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Playing,
+    Won,
+    Lost,
+}
+
+pub struct GuessingGame {
+    range: RangeInclusive<u32>,
+    max_attempts: Option<u32>,
+    host: Box<dyn Host>, // a trait object, so the same game can be refereed honestly or adversarially
+    attempts: u32,
+    state: State,
+}
+
+impl GuessingGame {
+    // A game with a random secret somewhere in `range`; `max_attempts` of None means guess until you get it.
+    pub fn new(range: RangeInclusive<u32>, max_attempts: Option<u32>) -> Result<GuessingGame, &'static str> {
+        if range.is_empty() {
+            return Err("the range of numbers is empty");
+        }
+        // gen_range leaves out its upper end, which for a range ending at u32::MAX is only reachable in a u64
+        let secret = rand::thread_rng().gen_range(u64::from(*range.start()), u64::from(*range.end()) + 1) as u32;
+
+        GuessingGame::with_secret(range, max_attempts, secret)
+    }
+
+    pub fn with_secret(range: RangeInclusive<u32>, max_attempts: Option<u32>, secret: u32) -> Result<GuessingGame, &'static str> {
+        if !range.contains(&secret) {
+            return Err("the secret is outside the range of numbers");
+        }
+
+        GuessingGame::with_host(range, max_attempts, Box::new(SecretHost::new(secret)))
+    }
+
+    // A game against a host that never picks a number, it just answers so the game lasts as long as possible.
+    pub fn adversarial(range: RangeInclusive<u32>, max_attempts: Option<u32>) -> Result<GuessingGame, &'static str> {
+        if range.is_empty() {
+            return Err("the range of numbers is empty");
+        }
+        let host = AdversarialHost::new(*range.start(), *range.end());
+
+        GuessingGame::with_host(range, max_attempts, Box::new(host))
+    }
+
+    pub fn with_host(range: RangeInclusive<u32>, max_attempts: Option<u32>, host: Box<dyn Host>) -> Result<GuessingGame, &'static str> {
+        if max_attempts == Some(0) {
+            return Err("there must be at least one attempt");
+        }
+
+        Ok(GuessingGame { range, max_attempts, host, attempts: 0, state: State::Playing })
+    }
+
+    pub fn guess(&mut self, input: &str) -> Response {
+        if self.state != State::Playing {
+            return Response::GameOver;
+        }
+
+        let guess: u32 = match input.trim().parse() {
+            Ok(num) => num,
+            Err(_) => return Response::Invalid(Invalid::NotANumber(input.trim().to_string())),
+        };
+        if !self.range.contains(&guess) {
+            return Response::Invalid(Invalid::OutOfRange(guess));
+        }
+
+        self.attempts += 1;
+
+        let response = match self.host.compare(guess) {
+            Ordering::Less => Response::TooSmall,
+            Ordering::Greater => Response::TooBig,
+            Ordering::Equal => {
+                self.state = State::Won;
+                return Response::Win;
+            }
+        };
+
+        if Some(self.attempts) == self.max_attempts {
+            self.state = State::Lost;
+            return Response::Lose { secret: self.host.reveal() };
+        }
+
+        response
+    }
+
+    pub fn range(&self) -> &RangeInclusive<u32> {
+        &self.range
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn attempts_left(&self) -> Option<u32> {
+        self.max_attempts.map(|max| max - self.attempts)
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+}
+
+// Drives a game from lines of input until it's over or the input runs out, writing what the game says to `output`.
+pub fn run<R: BufRead, W: Write>(game: &mut GuessingGame, input: &mut R, output: &mut W) -> io::Result<()> {
+    writeln!(output, "Guess the number between {} and {}!", game.range().start(), game.range().end())?;
+
+    while game.state() == State::Playing {
+        writeln!(output, "Please input your guess.")?;
+
+        let mut guess = String::new();
+        if input.read_line(&mut guess)? == 0 {
+            break;
+        }
+
+        match game.guess(&guess) {
+            Response::GameOver => break,
+            response => writeln!(output, "{}", describe(&response))?,
+        }
+    }
+
+    Ok(())
+}
+
+pub fn describe(response: &Response) -> String {
+    match response {
+        Response::TooSmall => String::from("Too small!"),
+        Response::TooBig => String::from("Too big!"),
+        Response::Win => String::from("You win!"),
+        Response::Lose { secret } => format!("Out of guesses! The number was {}", secret),
+        Response::Invalid(Invalid::NotANumber(text)) => format!("'{}' isn't a number.", text),
+        Response::Invalid(Invalid::OutOfRange(num)) => format!("{} is out of range.", num),
+        Response::GameOver => String::from("The game is over."),
+    }
+}
+
+// guessing_game [--bot] [--adversarial]
+#[derive(Debug, PartialEq)]
+pub struct Config {
+    pub bot: bool,         // let the computer do the guessing
+    pub adversarial: bool, // play against the host that never commits to a number
+}
+
+impl Config {
+    pub fn new<I: Iterator<Item = String>>(mut args: I) -> Result<Config, &'static str> {
+        args.next(); // the program name
+
+        let mut config = Config { bot: false, adversarial: false };
+        for arg in args {
+            match arg.as_str() {
+                "--bot" => config.bot = true,
+                "--adversarial" => config.adversarial = true,
+                _ => return Err("usage: guessing_game [--bot] [--adversarial]"),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut GuessingGame, inputs: &[&str]) -> Vec<Response> {
+        inputs.iter().map(|input| game.guess(input)).collect()
+    }
+
+    #[test]
+    fn it_picks_a_secret_at_the_top_of_u32() {
+        let mut game = GuessingGame::new(u32::MAX..=u32::MAX, None).unwrap();
+        assert_eq!(game.guess(&u32::MAX.to_string()), Response::Win);
+    }
+
+    #[test]
+    fn it_gives_hints_until_the_number_is_found() {
+        let mut game = GuessingGame::with_secret(1..=100, None, 42).unwrap();
+
+        assert_eq!(
+            play(&mut game, &["50", "25", "42", "42"]),
+            vec![Response::TooBig, Response::TooSmall, Response::Win, Response::GameOver]
+        );
+        assert_eq!(game.attempts(), 3);
+        assert_eq!(game.state(), State::Won);
+    }
+
+    #[test]
+    fn it_reports_invalid_input_without_using_an_attempt() {
+        let mut game = GuessingGame::with_secret(1..=10, Some(3), 7).unwrap();
+
+        assert_eq!(
+            play(&mut game, &["seven", " 11\n", "0", "7\n"]),
+            vec![
+                Response::Invalid(Invalid::NotANumber(String::from("seven"))),
+                Response::Invalid(Invalid::OutOfRange(11)),
+                Response::Invalid(Invalid::OutOfRange(0)),
+                Response::Win,
+            ]
+        );
+        assert_eq!(game.attempts(), 1);
+    }
+
+    #[test]
+    fn it_is_lost_when_the_attempts_run_out() {
+        let mut game = GuessingGame::with_secret(1..=10, Some(2), 7).unwrap();
+
+        assert_eq!(game.guess("1"), Response::TooSmall);
+        assert_eq!(game.attempts_left(), Some(1));
+        assert_eq!(game.guess("9"), Response::Lose { secret: 7 });
+        assert_eq!(game.guess("7"), Response::GameOver);
+        assert_eq!(game.state(), State::Lost);
+    }
+
+    #[test]
+    fn it_rejects_bad_settings() {
+        let (low, high) = (10, 1);
+        assert!(GuessingGame::new(low..=high, None).is_err());
+        assert!(GuessingGame::with_secret(1..=10, None, 11).is_err());
+        assert!(GuessingGame::with_secret(1..=10, Some(0), 5).is_err());
+    }
+
+    #[test]
+    fn it_picks_a_secret_in_range() {
+        for _ in 0..100 {
+            let mut game = GuessingGame::new(3..=4, Some(2)).unwrap();
+            let first = game.guess("3");
+            assert!(first == Response::Win || game.guess("4") == Response::Win);
+        }
+    }
+
+    #[test]
+    fn it_parses_the_command_line() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>().into_iter();
+
+        assert_eq!(Config::new(args(&["guessing_game"])), Ok(Config { bot: false, adversarial: false }));
+        assert_eq!(
+            Config::new(args(&["guessing_game", "--adversarial", "--bot"])),
+            Ok(Config { bot: true, adversarial: true })
+        );
+        assert!(Config::new(args(&["guessing_game", "--cheat"])).is_err());
+    }
+
+    #[test]
+    fn the_adversary_stays_consistent_with_its_hints() {
+        let mut game = GuessingGame::adversarial(1..=10, Some(3)).unwrap();
+
+        assert_eq!(play(&mut game, &["5", "8", "9"]), vec![
+            Response::TooSmall,
+            Response::TooSmall,
+            Response::Lose { secret: 10 },
+        ]);
+    }
+
+    #[test]
+    fn it_runs_from_scripted_input() {
+        let mut game = GuessingGame::with_secret(1..=100, None, 30).unwrap();
+        let mut input = "abc\n60\n30\n".as_bytes();
+        let mut output = Vec::new();
+
+        run(&mut game, &mut input, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("'abc' isn't a number."));
+        assert!(output.contains("Too big!"));
+        assert!(output.ends_with("You win!\n"));
+        assert!(!output.contains("secret"));
+    }
+}
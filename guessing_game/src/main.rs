@@ -1,40 +1,37 @@
+extern crate guessing_game;
+use std::env;
 use std::io;
-use rand::Rng;
-use std::cmp::Ordering;
+use std::process;
 
-fn main() {
-    println!("Guess the number!");
-
-    let secret_number = rand::thread_rng().gen_range(1, 101);
-
-    println!("The secret number is: {}", secret_number);
-
-    loop {
-        println!("Please input your guess.");
-
-        let mut guess = String::new();
-
-        io::stdin().read_line(&mut guess)
-            .expect("Failed to read line");
-
-        let guess: u32 = match guess.trim().parse() {
-            Ok(num) => num,
-            Err(_) => continue,
-        };
-
-        println!("You guessed: {}", guess);
-
-        match guess.cmp(&secret_number) {
-            Ordering::Less => println!("Too small!"),
-            Ordering::Greater => println!("Too big!"),
-            Ordering::Equal => {
-                println!("You win!");
-                break;
-            },
-        }
+use guessing_game::{bot, Config, GuessingGame};
 
+fn main() {
+    let config = Config::new(env::args()).unwrap_or_else(|err| {
+        eprintln!("Problem parsing arguments: {}", err);
+        process::exit(1);
+    });
+
+    let game = if config.adversarial {
+        GuessingGame::adversarial(1..=100, None)
+    } else {
+        GuessingGame::new(1..=100, None)
+    };
+    let mut game = game.unwrap_or_else(|err| {
+        eprintln!("Problem setting up the game: {}", err);
+        process::exit(1);
+    });
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    let result = if config.bot {
+        bot::run_bot(&mut game, &mut stdout.lock())
+    } else {
+        guessing_game::run(&mut game, &mut stdin.lock(), &mut stdout.lock())
+    };
+
+    if let Err(e) = result {
+        eprintln!("Application error: {}", e);
+        process::exit(1);
     }
-
 }
- 
- //
\ No newline at end of file
@@ -0,0 +1,68 @@
+// How much redrawing only what changed saves on a big screen: a text field at the top of a grid of buttons, with a key
+// typed into the field every frame. Drawing everything every frame is what Screen did before it kept a widget tree.
+//
+//     cargo bench --bench redraw
+
+extern crate gui_trait_objects;
+use gui_trait_objects::{Button, Color, Direction, Event, Framebuffer, Key, Layout, Point, Screen, Size, Sizing};
+use gui_trait_objects::TextField;
+use std::time::{Duration, Instant};
+
+const ROWS: usize = 60;
+const COLUMNS: usize = 30;
+const FRAMES: u32 = 200;
+
+fn screen() -> Screen {
+    let mut screen = Screen::new();
+    screen.add(Box::new(TextField::new(COLUMNS as i32 * 40, 12)));
+
+    let tree = screen.tree_mut();
+    let root = tree.root();
+    for row in 0..ROWS {
+        let id = tree.add_container(root, Layout::new(Direction::Horizontal), Sizing::Preferred);
+        for column in 0..COLUMNS {
+            let label = format!("{}", row * COLUMNS + column);
+            tree.add_widget(id, Box::new(Button::new(40, 12, &label)), Sizing::Preferred);
+        }
+    }
+    screen
+}
+
+// Types a key into the field, then draws the frame with `draw`
+fn time(name: &str, mut draw: impl FnMut(&mut Screen, &mut Framebuffer) -> usize) -> Duration {
+    let mut screen = screen();
+    let size = Size::new(COLUMNS as i32 * 40, (ROWS as i32 + 1) * 12);
+    let mut framebuffer = Framebuffer::new(size.width, size.height, Color::WHITE);
+    screen.run(&mut framebuffer);
+    screen.handle_event(&Event::MouseDown(Point::new(1, 1)), size);
+
+    let mut drawn = 0;
+    let start = Instant::now();
+    for i in 0..FRAMES {
+        let key = if i % 10 == 9 { Key::Backspace } else { Key::Char('x') };
+        screen.handle_event(&Event::Key(key), size);
+        drawn += draw(&mut screen, &mut framebuffer);
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<14} {:>9.3} ms a frame, {:>6} widgets drawn a frame",
+        name,
+        elapsed.as_secs_f64() * 1000.0 / f64::from(FRAMES),
+        drawn / FRAMES as usize
+    );
+    elapsed
+}
+
+fn main() {
+    let widgets = ROWS * COLUMNS + 1;
+    println!("{} widgets, {} frames each", widgets, FRAMES);
+
+    let full = time("full redraw", |screen, framebuffer| {
+        let root = screen.tree().root();
+        screen.tree_mut().mark_dirty(root); // clears the whole screen and draws everything on it
+        screen.redraw(framebuffer, Color::WHITE)
+    });
+    let dirty = time("dirty redraw", |screen, framebuffer| screen.redraw(framebuffer, Color::WHITE));
+
+    println!("dirty redraws take {:.1}% of the time", dirty.as_secs_f64() * 100.0 / full.as_secs_f64());
+}
@@ -0,0 +1,120 @@
+// Where components get drawn. A Canvas is a backend - a pixel framebuffer, a terminal, an SVG document - that knows how
+// to put a handful of primitives on its surface. Components never see it directly: they get a Context, which places
+// everything they draw relative to their own rectangle and clips it to that rectangle, so a component can't scribble
+// over its neighbours.
+
+use crate::geometry::{Point, Rect, Size};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    pub const GREY: Color = Color::rgb(192, 192, 192);
+    pub const DARK_GREY: Color = Color::rgb(96, 96, 96);
+    pub const BLUE: Color = Color::rgb(0, 90, 200);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+}
+
+pub trait Canvas {
+    fn size(&self) -> Size;
+
+    // Nothing outside `clip` may change until it's set again. Every drawing call below is in canvas coordinates.
+    fn set_clip(&mut self, clip: Rect);
+
+    fn fill_rect(&mut self, rect: Rect, color: Color);
+
+    // A one-unit-wide outline just inside `rect`
+    fn stroke_rect(&mut self, rect: Rect, color: Color);
+
+    fn line(&mut self, from: Point, to: Point, color: Color);
+
+    // `at` is the top left corner of the text
+    fn text(&mut self, at: Point, text: &str, color: Color);
+
+    // How much room `text` takes up on this canvas
+    fn text_size(&self, text: &str) -> Size;
+}
+
+pub struct Context<'a> {
+    canvas: &'a mut dyn Canvas,
+    bounds: Rect, // where the component is, in canvas coordinates
+    clip: Rect,   // the part of `bounds` that's actually visible
+}
+
+impl<'a> Context<'a> {
+    // A context covering the whole canvas
+    pub fn new(canvas: &'a mut dyn Canvas) -> Context<'a> {
+        let bounds = Rect::from_size(canvas.size());
+        Context { canvas, bounds, clip: bounds }
+    }
+
+    // A context for the part of this one at `rect` (in this context's coordinates), for drawing a component in
+    pub fn child(&mut self, rect: Rect) -> Context<'_> {
+        let bounds = rect.translate(self.bounds.origin());
+        Context { canvas: &mut *self.canvas, bounds, clip: self.clip.intersect(bounds) }
+    }
+
+    // The component's own size - everything it draws is relative to its top left corner
+    pub fn size(&self) -> Size {
+        self.bounds.size()
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::from_size(self.bounds.size())
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        if self.prepare() {
+            self.canvas.fill_rect(rect.translate(self.bounds.origin()), color);
+        }
+    }
+
+    pub fn stroke_rect(&mut self, rect: Rect, color: Color) {
+        if self.prepare() {
+            self.canvas.stroke_rect(rect.translate(self.bounds.origin()), color);
+        }
+    }
+
+    pub fn line(&mut self, from: Point, to: Point, color: Color) {
+        if self.prepare() {
+            let origin = self.bounds.origin();
+            let shift = |p: Point| Point::new(p.x + origin.x, p.y + origin.y);
+            self.canvas.line(shift(from), shift(to), color);
+        }
+    }
+
+    pub fn text(&mut self, at: Point, text: &str, color: Color) {
+        if self.prepare() {
+            self.canvas.text(Point::new(at.x + self.bounds.x, at.y + self.bounds.y), text, color);
+        }
+    }
+
+    pub fn text_size(&self, text: &str) -> Size {
+        self.canvas.text_size(text)
+    }
+
+    // Draws `text` centred in `rect`
+    pub fn centered_text(&mut self, rect: Rect, text: &str, color: Color) {
+        let size = self.text_size(text);
+        let at = Point::new(rect.x + (rect.width - size.width) / 2, rect.y + (rect.height - size.height) / 2);
+        self.text(at, text, color);
+    }
+
+    // Points the canvas at this context's clip, and says whether there's anything to draw into
+    fn prepare(&mut self) -> bool {
+        if self.clip.is_empty() {
+            return false;
+        }
+        self.canvas.set_clip(self.clip);
+        true
+    }
+}
@@ -0,0 +1,99 @@
+// Input. Events go to a Screen, which passes each one down to the component it's meant for: a MouseDown to whatever
+// is under the pointer (hit-testing against the layout), key events to whatever has the focus. A MouseUp goes to
+// everything, so whatever the mouse went down on hears about it even if the pointer has moved off it since. Tab and
+// shift-Tab move the focus between the components that can take it.
+//
+// Containers like Stack use the two functions here to do the same for their own children, which is how an event finds
+// its way down to a widget nested any number of stacks deep.
+
+use crate::geometry::{Point, Rect};
+use crate::Draw;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    MouseDown(Point),
+    MouseUp(Point),
+    Key(Key),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Backspace,
+    Delete,
+    Enter,
+    Escape,
+    Tab,
+    BackTab, // shift-Tab
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Ctrl(char), // a letter with Ctrl held down, like Ctrl('c')
+}
+
+impl Event {
+    // Where a mouse event happened; None for key events
+    pub fn position(&self) -> Option<Point> {
+        match self {
+            Event::MouseDown(point) | Event::MouseUp(point) => Some(*point),
+            Event::Key(_) => None,
+        }
+    }
+
+    // The same event as seen from inside `rect`
+    pub fn relative_to(&self, rect: Rect) -> Event {
+        let shift = |point: Point| Point::new(point.x - rect.x, point.y - rect.y);
+        match *self {
+            Event::MouseDown(point) => Event::MouseDown(shift(point)),
+            Event::MouseUp(point) => Event::MouseUp(shift(point)),
+            key => key,
+        }
+    }
+}
+
+// Sends `event` to the child it's meant for, where each child is laid out at its rect. A MouseDown goes to the topmost
+// (last) child under the pointer, and if that child takes the focus it's taken away from all the others. A MouseUp
+// goes to every child, and key events to the child with the focus. Returns whether any child used the event.
+pub fn dispatch(children: &mut [(&mut dyn Draw, Rect)], event: &Event) -> bool {
+    match event.position() {
+        Some(_) if matches!(event, Event::MouseUp(_)) => children.iter_mut().fold(false, |handled, (child, rect)| {
+            child.handle_event(&event.relative_to(*rect), rect.size()) || handled
+        }),
+        Some(point) => {
+            let hit = match children.iter().rposition(|(_, rect)| rect.contains(point)) {
+                Some(hit) => hit,
+                None => return false,
+            };
+
+            let (child, rect) = &mut children[hit];
+            let handled = child.handle_event(&event.relative_to(*rect), rect.size());
+            if child.is_focused() {
+                for (i, (other, _)) in children.iter_mut().enumerate() {
+                    if i != hit {
+                        other.blur();
+                    }
+                }
+            }
+            handled
+        }
+        None => children
+            .iter_mut()
+            .find(|(child, _)| child.is_focused())
+            .is_some_and(|(child, rect)| child.handle_event(event, rect.size())),
+    }
+}
+
+// Moves the focus on to the next focus stop among `children` (or the previous, going backwards), starting from the
+// child that has it now. Returns false when the focus has gone past the last stop, which leaves nothing focused.
+pub fn focus_next(children: &mut [&mut dyn Draw], forward: bool) -> bool {
+    let mut order: Vec<usize> = (0..children.len()).collect();
+    if !forward {
+        order.reverse();
+    }
+
+    let start = order.iter().position(|&i| children[i].is_focused()).unwrap_or(0);
+    order[start..].iter().any(|&i| children[i].focus_next(forward))
+}
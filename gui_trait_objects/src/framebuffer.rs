@@ -0,0 +1,228 @@
+// A software rasterizer: a grid of pixels in memory that Screen::run can draw into. The result can be written out as a
+// PPM image (which most image viewers open) or turned into text for snapshot tests.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::canvas::{Canvas, Color};
+use crate::font;
+use crate::geometry::{Point, Rect, Size};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framebuffer {
+    width: i32,
+    height: i32,
+    pixels: Vec<Color>,
+    clip: Rect,
+}
+
+impl Framebuffer {
+    pub fn new(width: i32, height: i32, background: Color) -> Framebuffer {
+        let (width, height) = (width.max(0), height.max(0));
+        // in usize, since a framebuffer can have more pixels than fit in an i32
+        let len = (width as usize).checked_mul(height as usize).expect("framebuffer too big");
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![background; len],
+            clip: Rect::new(0, 0, width, height),
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    // None outside the framebuffer
+    pub fn pixel(&self, x: i32, y: i32) -> Option<Color> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.pixels[self.index(x, y)])
+    }
+
+    // Sets a pixel if it's inside the current clip
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if self.clip.contains(Point::new(x, y)) {
+            let i = self.index(x, y);
+            self.pixels[i] = color;
+        }
+    }
+
+    // A binary (P6) PPM image
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for pixel in &self.pixels {
+            ppm.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+        }
+        ppm
+    }
+
+    pub fn write_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_ppm())
+    }
+
+    // One character per pixel, one line per row, using `legend` to say which character each colour gets. Colours that
+    // aren't in the legend come out as '?'. Handy for snapshot tests, since the whole image fits in a string literal.
+    pub fn to_ascii(&self, legend: &[(Color, char)]) -> String {
+        let mut ascii = String::with_capacity(self.pixels.len() + self.height as usize);
+        for row in self.pixels.chunks(self.width.max(1) as usize) {
+            for pixel in row {
+                ascii.push(legend.iter().find(|(color, _)| color == pixel).map_or('?', |&(_, c)| c));
+            }
+            ascii.push('\n');
+        }
+        ascii
+    }
+
+    // Where (x, y) is in `pixels`, for a point that's already known to be inside
+    fn index(&self, x: i32, y: i32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+impl Canvas for Framebuffer {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    fn set_clip(&mut self, clip: Rect) {
+        self.clip = clip.intersect(Rect::new(0, 0, self.width, self.height));
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let visible = rect.intersect(self.clip);
+        for y in visible.y..visible.bottom() {
+            for x in visible.x..visible.right() {
+                let i = self.index(x, y);
+                self.pixels[i] = color;
+            }
+        }
+    }
+
+    fn stroke_rect(&mut self, rect: Rect, color: Color) {
+        if rect.is_empty() {
+            return;
+        }
+        let (right, bottom) = (rect.right() - 1, rect.bottom() - 1);
+        self.fill_rect(Rect::new(rect.x, rect.y, rect.width, 1), color);
+        self.fill_rect(Rect::new(rect.x, bottom, rect.width, 1), color);
+        self.fill_rect(Rect::new(rect.x, rect.y, 1, rect.height), color);
+        self.fill_rect(Rect::new(right, rect.y, 1, rect.height), color);
+    }
+
+    // Bresenham's line algorithm, so any slope works with integer arithmetic only
+    fn line(&mut self, from: Point, to: Point, color: Color) {
+        let (dx, dy) = ((to.x - from.x).abs(), -(to.y - from.y).abs());
+        let (step_x, step_y) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+        let (mut x, mut y) = (from.x, from.y);
+        let mut error = dx + dy;
+
+        loop {
+            self.set_pixel(x, y, color);
+            if x == to.x && y == to.y {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    fn text(&mut self, at: Point, text: &str, color: Color) {
+        for (i, c) in text.chars().enumerate() {
+            let left = at.x + i as i32 * font::ADVANCE;
+            for (row, bits) in font::glyph(c).iter().enumerate() {
+                for column in 0..font::GLYPH_WIDTH {
+                    if bits & (0x10 >> column) != 0 {
+                        self.set_pixel(left + column, at.y + row as i32, color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn text_size(&self, text: &str) -> Size {
+        let chars = text.chars().count() as i32;
+        if chars == 0 {
+            return Size::new(0, 0);
+        }
+        Size::new(chars * font::ADVANCE - 1, font::GLYPH_HEIGHT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGEND: &[(Color, char)] = &[(Color::WHITE, '.'), (Color::BLACK, '#'), (Color::BLUE, 'b')];
+
+    #[test]
+    fn it_draws_clipped_rectangles_and_lines() {
+        let mut fb = Framebuffer::new(8, 5, Color::WHITE);
+        fb.stroke_rect(Rect::new(0, 0, 5, 5), Color::BLACK);
+        fb.set_clip(Rect::new(0, 0, 7, 5));
+        fb.line(Point::new(1, 1), Point::new(7, 4), Color::BLUE);
+
+        assert_eq!(
+            fb.to_ascii(LEGEND),
+            "\
+#####...
+#b..#...
+#.bb#...
+#...bb..
+#####.b.
+"
+        );
+    }
+
+    #[test]
+    fn negative_sizes_make_empty_framebuffers() {
+        let mut fb = Framebuffer::new(-3, 4, Color::WHITE);
+        fb.fill_rect(Rect::new(0, 0, 2, 2), Color::BLACK);
+
+        assert_eq!((fb.width(), fb.height()), (0, 4));
+        assert_eq!(fb.pixel(0, 0), None);
+        assert_eq!(fb.to_ascii(LEGEND), "");
+    }
+
+    #[test]
+    fn it_draws_text_with_the_bitmap_font() {
+        let mut fb = Framebuffer::new(11, 7, Color::WHITE);
+        fb.text(Point::new(0, 0), "Hi", Color::BLACK);
+
+        assert_eq!(fb.text_size("Hi"), Size::new(11, 7));
+        assert_eq!(
+            fb.to_ascii(LEGEND),
+            "\
+#...#..###.
+#...#...#..
+#...#...#..
+#####...#..
+#...#...#..
+#...#...#..
+#...#..###.
+"
+        );
+    }
+
+    #[test]
+    fn it_writes_ppm_images() {
+        let mut fb = Framebuffer::new(2, 1, Color::WHITE);
+        fb.set_pixel(1, 0, Color::rgb(1, 2, 3));
+
+        assert_eq!(fb.to_ppm(), b"P6\n2 1\n255\n\xff\xff\xff\x01\x02\x03".to_vec());
+        assert_eq!(fb.pixel(2, 0), None);
+    }
+}
@@ -0,0 +1,109 @@
+// Positions and sizes are in whatever unit the canvas works in - pixels for a Framebuffer, character cells for a
+// terminal. x grows to the right and y grows downwards, with (0, 0) in the top left corner.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Size {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Size {
+    pub fn new(width: i32, height: i32) -> Size {
+        Size { width, height }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect { x, y, width, height }
+    }
+
+    pub fn from_size(size: Size) -> Rect {
+        Rect::new(0, 0, size.width, size.height)
+    }
+
+    pub fn origin(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    pub fn right(&self) -> i32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= self.x && point.x < self.right() && point.y >= self.y && point.y < self.bottom()
+    }
+
+    // The overlap of the two, which is empty (but not necessarily zero-sized at the origin) when they don't meet
+    pub fn intersect(&self, other: Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+
+        Rect::new(x, y, (right - x).max(0), (bottom - y).max(0))
+    }
+
+    pub fn translate(&self, by: Point) -> Rect {
+        Rect::new(self.x + by.x, self.y + by.y, self.width, self.height)
+    }
+
+    // Shrunk by `amount` on every side
+    pub fn inset(&self, amount: i32) -> Rect {
+        Rect::new(
+            self.x + amount,
+            self.y + amount,
+            (self.width - 2 * amount).max(0),
+            (self.height - 2 * amount).max(0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_intersects_rectangles() {
+        let a = Rect::new(0, 0, 10, 10);
+
+        assert_eq!(a.intersect(Rect::new(5, -5, 10, 10)), Rect::new(5, 0, 5, 5));
+        assert!(a.intersect(Rect::new(20, 20, 5, 5)).is_empty());
+        assert!(a.contains(Point::new(9, 0)));
+        assert!(!a.contains(Point::new(10, 0)));
+        assert_eq!(a.inset(2), Rect::new(2, 2, 6, 6));
+        assert_eq!(a.inset(6).size(), Size::new(0, 0));
+    }
+}
@@ -0,0 +1,294 @@
+// Just enough JSON to describe screens with (see loader.rs): the whole grammar, read into a tree of Values. Errors say
+// where in the text they are, since the text is usually a file somebody wrote by hand.
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>), // in the order they were written
+}
+
+impl Value {
+    // The value of `key`, if this is an object that has it
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    // What sort of value this is, for error messages
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for SyntaxError {}
+
+// Deeper than any screen needs, and shallow enough that parsing a hostile document can't overflow the stack
+const MAX_DEPTH: usize = 128;
+
+pub fn parse(text: &str) -> Result<Value, SyntaxError> {
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("unexpected text after the end of the document"));
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize, // how many arrays and objects the parser is inside
+}
+
+impl Parser {
+    fn value(&mut self) -> Result<Value, SyntaxError> {
+        self.skip_whitespace();
+        match self.chars.get(self.pos) {
+            Some('{') | Some('[') if self.depth == MAX_DEPTH => Err(self.error("nested too deeply")),
+            Some(&c @ '{') | Some(&c @ '[') => {
+                self.depth += 1;
+                let value = if c == '{' { self.object() } else { self.array() };
+                self.depth -= 1;
+                value
+            }
+            Some('"') => self.string().map(Value::String),
+            Some('t') => self.literal("true", Value::Bool(true)),
+            Some('f') => self.literal("false", Value::Bool(false)),
+            Some('n') => self.literal("null", Value::Null),
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+            None => Err(self.error("unexpected end of the document")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, SyntaxError> {
+        self.pos += 1; // {
+        let mut fields = Vec::new();
+        if self.next_is('}') {
+            return Ok(Value::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.chars.get(self.pos) != Some(&'"') {
+                return Err(self.error("expected a key in quotes"));
+            }
+            let key = self.string()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            fields.push((key, value));
+
+            if self.next_is(',') {
+                continue;
+            }
+            self.expect('}')?;
+            return Ok(Value::Object(fields));
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, SyntaxError> {
+        self.pos += 1; // [
+        let mut items = Vec::new();
+        if self.next_is(']') {
+            return Ok(Value::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            if self.next_is(',') {
+                continue;
+            }
+            self.expect(']')?;
+            return Ok(Value::Array(items));
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SyntaxError> {
+        self.pos += 1; // "
+        let mut out = String::new();
+        loop {
+            let c = match self.chars.get(self.pos) {
+                Some(c) => *c,
+                None => return Err(self.error("unterminated string")),
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escaped = self.chars.get(self.pos).copied();
+                    self.pos += 1;
+                    match escaped {
+                        Some('n') => out.push('\n'),
+                        Some('t') => out.push('\t'),
+                        Some('r') => out.push('\r'),
+                        Some('b') => out.push('\u{8}'),
+                        Some('f') => out.push('\u{c}'),
+                        Some('u') => {
+                            let mut code = self.hex4()?;
+                            // characters outside the BMP come as a surrogate pair, like \uD83D\uDE00
+                            if (0xd800..0xdc00).contains(&code) && self.chars[self.pos..].starts_with(&['\\', 'u']) {
+                                let before = self.pos;
+                                self.pos += 2;
+                                match self.hex4()? {
+                                    low @ 0xdc00..=0xdfff => code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00),
+                                    _ => self.pos = before, // not the other half, so it's an escape of its own
+                                }
+                            }
+                            // and a surrogate on its own isn't a character at all
+                            out.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        Some(c @ '"') | Some(c @ '\\') | Some(c @ '/') => out.push(c),
+                        _ => return Err(self.error("unknown escape in string")),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    // The four hex digits of a \u escape
+    fn hex4(&mut self) -> Result<u32, SyntaxError> {
+        let digits = self.chars.get(self.pos..self.pos + 4).filter(|digits| digits.iter().all(char::is_ascii_hexdigit));
+        let code = match digits {
+            Some(digits) => u32::from_str_radix(&digits.iter().collect::<String>(), 16).unwrap(),
+            None => return Err(self.error("bad \\u escape")),
+        };
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Value, SyntaxError> {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse().map(Value::Number).map_err(|_| SyntaxError {
+            message: format!("bad number '{}'", text),
+            ..self.error_at(start, "")
+        })
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, SyntaxError> {
+        let found: String = self.chars.iter().skip(self.pos).take(word.len()).collect();
+        if found != word {
+            return Err(self.error(&format!("expected {}", word)));
+        }
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    // Skips `c` if it's next, saying whether it was
+    fn next_is(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SyntaxError> {
+        if self.next_is(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: &str) -> SyntaxError {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, pos: usize, message: &str) -> SyntaxError {
+        let before = &self.chars[..pos.min(self.chars.len())];
+        let line = before.iter().filter(|&&c| c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|&&c| c != '\n').count() + 1;
+        SyntaxError { line, column, message: message.to_string() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_nested_documents() {
+        let value = parse(r#" {"a": [1, -2.5e1, true, null], "b": {"c": "d\"é\n"}, "e": []} "#).unwrap();
+
+        assert_eq!(
+            value.get("a"),
+            Some(&Value::Array(vec![Value::Number(1.0), Value::Number(-25.0), Value::Bool(true), Value::Null]))
+        );
+        assert_eq!(value.get("b").and_then(|b| b.get("c")), Some(&Value::String(String::from("d\"é\n"))));
+        assert_eq!(value.get("e"), Some(&Value::Array(Vec::new())));
+        assert_eq!(value.get("f"), None);
+    }
+
+    #[test]
+    fn errors_say_where_they_are() {
+        let error = |text| parse(text).unwrap_err().to_string();
+
+        assert_eq!(error("{\n  \"a\": 1,\n  \"b\" 2\n}"), "line 3, column 7: expected ':'");
+        assert_eq!(error("[1, 2"), "line 1, column 6: expected ']'");
+        assert_eq!(error("{} {}"), "line 1, column 4: unexpected text after the end of the document");
+        assert_eq!(error("[1.2.3]"), "line 1, column 2: bad number '1.2.3'");
+    }
+
+    #[test]
+    fn unicode_escapes_take_four_hex_digits_and_pair_surrogates() {
+        let string = |text| parse(text).map(|value| if let Value::String(s) = value { s } else { panic!() });
+
+        assert_eq!(string(r#""\u00e9 \uD83D\uDE00""#), Ok(String::from("é 😀")));
+        assert_eq!(string(r#""\uD83D!""#), Ok(String::from("\u{fffd}!")));
+        assert_eq!(string(r#""\uD83D\u0041""#), Ok(String::from("\u{fffd}A")));
+        assert_eq!(parse(r#""\u+123""#).unwrap_err().message, "bad \\u escape");
+        assert_eq!(parse(r#""\u12""#).unwrap_err().message, "bad \\u escape");
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error_rather_than_a_stack_overflow() {
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(parse(&nested(MAX_DEPTH + 1)).unwrap_err().message, "nested too deeply");
+        assert_eq!(parse(&"[".repeat(100_000)).unwrap_err().message, "nested too deeply");
+    }
+}
@@ -0,0 +1,361 @@
+// Working out where components go. A Stack lines its children up along one axis (the main axis) - top to bottom or
+// left to right - and is itself a Draw, so stacks nest inside each other to build up a whole screen.
+//
+// Along the main axis each child is sized by its Sizing:
+//   Fixed(n)    exactly n
+//   Preferred   whatever the component's preferred_size asks for
+//   Flex(w)     a share of the space left over once the others are placed, in proportion to w
+// Across the other axis each child is placed by an Align - the stack's, unless the child has its own.
+
+use crate::canvas::Context;
+use crate::event::{self, Event};
+use crate::geometry::{Rect, Size};
+use crate::Draw;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Vertical,
+    Horizontal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sizing {
+    Fixed(i32),
+    Preferred,
+    Flex(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    Stretch, // across the stack only - along it, use Flex
+}
+
+// What the layout needs to know about one child
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Item {
+    pub preferred: Size,
+    pub sizing: Sizing,
+    pub align: Option<Align>, // None to go with the stack's alignment
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub direction: Direction,
+    pub padding: i32, // space inside the edges
+    pub spacing: i32, // space between neighbouring children
+    pub align: Align, // across the main axis
+    pub justify: Align, // along the main axis, when nothing flexes to fill it (Stretch counts as Start)
+}
+
+impl Layout {
+    pub fn new(direction: Direction) -> Layout {
+        Layout { direction, padding: 0, spacing: 0, align: Align::Start, justify: Align::Start }
+    }
+
+    // The rectangle for each item, relative to the top left of a container of `size`
+    pub fn arrange(&self, size: Size, items: &[Item]) -> Vec<Rect> {
+        let (main_size, cross_size) = self.split(size);
+        let main_space = (main_size - 2 * self.padding).max(0);
+        let cross_space = (cross_size - 2 * self.padding).max(0);
+        let gaps = self.spacing * (items.len() as i32 - 1).max(0);
+
+        // everything that isn't flexible takes its room first...
+        let mut lengths: Vec<i32> = items
+            .iter()
+            .map(|item| match item.sizing {
+                Sizing::Fixed(length) => length.max(0),
+                Sizing::Preferred => self.split(item.preferred).0.max(0),
+                Sizing::Flex(_) => 0,
+            })
+            .collect();
+        let leftover = (main_space - gaps - lengths.iter().sum::<i32>()).max(0);
+
+        // ...then the flexible ones share what's left. Working from running totals means the rounding never loses
+        // a unit.
+        let total_weight: u32 = items.iter().filter_map(|item| flex_weight(item.sizing)).sum();
+        let mut weight_so_far = 0;
+        for (length, item) in lengths.iter_mut().zip(items) {
+            if let Some(weight) = flex_weight(item.sizing) {
+                let before = share(leftover, weight_so_far, total_weight);
+                weight_so_far += weight;
+                *length = share(leftover, weight_so_far, total_weight) - before;
+            }
+        }
+
+        let used = lengths.iter().sum::<i32>() + gaps;
+        let mut main = self.padding
+            + match (total_weight, self.justify) {
+                (0, Align::Center) => (main_space - used).max(0) / 2,
+                (0, Align::End) => (main_space - used).max(0),
+                _ => 0,
+            };
+
+        let mut rects = Vec::with_capacity(items.len());
+        for (length, item) in lengths.into_iter().zip(items) {
+            let preferred_cross = self.split(item.preferred).1.clamp(0, cross_space);
+            let (cross, cross_length) = match item.align.unwrap_or(self.align) {
+                Align::Start => (0, preferred_cross),
+                Align::Center => ((cross_space - preferred_cross) / 2, preferred_cross),
+                Align::End => (cross_space - preferred_cross, preferred_cross),
+                Align::Stretch => (0, cross_space),
+            };
+
+            rects.push(self.join(main, self.padding + cross, length, cross_length));
+            main += length + self.spacing;
+        }
+        rects
+    }
+
+    // (main, cross) parts of a size
+    fn split(&self, size: Size) -> (i32, i32) {
+        match self.direction {
+            Direction::Vertical => (size.height, size.width),
+            Direction::Horizontal => (size.width, size.height),
+        }
+    }
+
+    fn join(&self, main: i32, cross: i32, main_length: i32, cross_length: i32) -> Rect {
+        match self.direction {
+            Direction::Vertical => Rect::new(cross, main, cross_length, main_length),
+            Direction::Horizontal => Rect::new(main, cross, main_length, cross_length),
+        }
+    }
+
+    // The smallest size that fits every item at its preferred (or fixed) size
+    pub fn preferred_size(&self, items: &[Item]) -> Size {
+        let mut main = self.spacing * (items.len() as i32 - 1).max(0);
+        let mut cross = 0;
+        for item in items {
+            let (preferred_main, preferred_cross) = self.split(item.preferred);
+            main += match item.sizing {
+                Sizing::Fixed(length) => length,
+                Sizing::Preferred | Sizing::Flex(_) => preferred_main,
+            };
+            cross = cross.max(preferred_cross);
+        }
+
+        let (main, cross) = (main + 2 * self.padding, cross + 2 * self.padding);
+        match self.direction {
+            Direction::Vertical => Size::new(cross, main),
+            Direction::Horizontal => Size::new(main, cross),
+        }
+    }
+}
+
+fn flex_weight(sizing: Sizing) -> Option<u32> {
+    match sizing {
+        Sizing::Flex(weight) => Some(weight),
+        _ => None,
+    }
+}
+
+// How much of `total` the first `weight` out of `total_weight` gets
+fn share(total: i32, weight: u32, total_weight: u32) -> i32 {
+    if total_weight == 0 {
+        return 0;
+    }
+    (total as i64 * weight as i64 / total_weight as i64) as i32
+}
+
+struct Child {
+    component: Box<dyn Draw>,
+    sizing: Sizing,
+    align: Option<Align>,
+}
+
+// A container that lays its children out with a Layout and draws each one in its own rectangle.
+pub struct Stack {
+    layout: Layout,
+    children: Vec<Child>,
+}
+
+impl Stack {
+    pub fn new(direction: Direction) -> Stack {
+        Stack { layout: Layout::new(direction), children: Vec::new() }
+    }
+
+    pub fn vertical() -> Stack {
+        Stack::new(Direction::Vertical)
+    }
+
+    pub fn horizontal() -> Stack {
+        Stack::new(Direction::Horizontal)
+    }
+
+    pub fn padding(mut self, padding: i32) -> Stack {
+        self.layout.padding = padding;
+        self
+    }
+
+    pub fn spacing(mut self, spacing: i32) -> Stack {
+        self.layout.spacing = spacing;
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> Stack {
+        self.layout.align = align;
+        self
+    }
+
+    pub fn justify(mut self, justify: Align) -> Stack {
+        self.layout.justify = justify;
+        self
+    }
+
+    pub fn child(self, component: Box<dyn Draw>, sizing: Sizing) -> Stack {
+        self.push(component, sizing, None)
+    }
+
+    // A child that's aligned differently from the rest
+    pub fn aligned_child(self, component: Box<dyn Draw>, sizing: Sizing, align: Align) -> Stack {
+        self.push(component, sizing, Some(align))
+    }
+
+    fn push(mut self, component: Box<dyn Draw>, sizing: Sizing, align: Option<Align>) -> Stack {
+        self.children.push(Child { component, sizing, align });
+        self
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    // Where each child goes when the stack is given `size`
+    pub fn arrange(&self, size: Size) -> Vec<Rect> {
+        self.layout.arrange(size, &self.items())
+    }
+
+    fn items(&self) -> Vec<Item> {
+        self.children
+            .iter()
+            .map(|child| Item { preferred: child.component.preferred_size(), sizing: child.sizing, align: child.align })
+            .collect()
+    }
+}
+
+impl Draw for Stack {
+    fn draw(&self, ctx: &mut Context) {
+        for (child, rect) in self.children.iter().zip(self.arrange(ctx.size())) {
+            child.component.draw(&mut ctx.child(rect));
+        }
+    }
+
+    fn preferred_size(&self) -> Size {
+        self.layout.preferred_size(&self.items())
+    }
+
+    fn handle_event(&mut self, event: &Event, size: Size) -> bool {
+        let rects = self.arrange(size);
+        let mut children: Vec<(&mut dyn Draw, Rect)> =
+            self.children.iter_mut().map(|c| c.component.as_mut() as &mut dyn Draw).zip(rects).collect();
+        event::dispatch(&mut children, event)
+    }
+
+    fn focus_next(&mut self, forward: bool) -> bool {
+        let mut children: Vec<&mut dyn Draw> =
+            self.children.iter_mut().map(|c| c.component.as_mut() as &mut dyn Draw).collect();
+        event::focus_next(&mut children, forward)
+    }
+
+    fn blur(&mut self) {
+        for child in self.children.iter_mut() {
+            child.component.blur();
+        }
+    }
+
+    fn is_focused(&self) -> bool {
+        self.children.iter().any(|child| child.component.is_focused())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Button;
+
+    fn button(width: i32, height: i32) -> Box<dyn Draw> {
+        Box::new(Button::new(width, height, ""))
+    }
+
+    #[test]
+    fn fixed_preferred_and_flex_children_share_the_main_axis() {
+        let stack = Stack::vertical()
+            .padding(2)
+            .spacing(1)
+            .child(button(10, 5), Sizing::Fixed(3))
+            .child(button(10, 5), Sizing::Preferred)
+            .child(button(10, 5), Sizing::Flex(1))
+            .child(button(10, 5), Sizing::Flex(2));
+
+        // 50 high less 4 padding, 3 gaps, 3 fixed and 5 preferred leaves 35 for the flexible ones, split 11/24
+        assert_eq!(
+            stack.arrange(Size::new(30, 50)),
+            vec![Rect::new(2, 2, 10, 3), Rect::new(2, 6, 10, 5), Rect::new(2, 12, 10, 11), Rect::new(2, 24, 10, 24)]
+        );
+        assert_eq!(stack.preferred_size(), Size::new(14, 25));
+    }
+
+    #[test]
+    fn children_are_aligned_across_the_stack() {
+        let stack = Stack::horizontal()
+            .align(Align::Center)
+            .child(button(4, 4), Sizing::Preferred)
+            .aligned_child(button(4, 4), Sizing::Preferred, Align::End)
+            .aligned_child(button(4, 4), Sizing::Preferred, Align::Stretch)
+            .aligned_child(button(4, 40), Sizing::Preferred, Align::Start);
+
+        assert_eq!(
+            stack.arrange(Size::new(20, 10)),
+            vec![Rect::new(0, 3, 4, 4), Rect::new(4, 6, 4, 4), Rect::new(8, 0, 4, 10), Rect::new(12, 0, 4, 10)]
+        );
+    }
+
+    #[test]
+    fn leftover_space_is_justified_when_nothing_flexes() {
+        let arrange = |justify| {
+            Stack::horizontal().justify(justify).child(button(4, 2), Sizing::Preferred).arrange(Size::new(10, 2))[0]
+        };
+
+        assert_eq!(arrange(Align::Start).x, 0);
+        assert_eq!(arrange(Align::Center).x, 3);
+        assert_eq!(arrange(Align::End).x, 6);
+    }
+
+    #[test]
+    fn children_never_get_negative_sizes() {
+        let stack = Stack::vertical()
+            .padding(3)
+            .child(button(10, 10), Sizing::Flex(1))
+            .child(button(10, 10), Sizing::Fixed(-4));
+
+        assert_eq!(stack.arrange(Size::new(4, 4)), vec![Rect::new(3, 3, 0, 0), Rect::new(3, 3, 0, 0)]);
+    }
+
+    #[test]
+    fn nested_stacks_draw_in_their_own_rectangles() {
+        use crate::{Color, Framebuffer};
+
+        let row = Stack::horizontal()
+            .spacing(1)
+            .child(button(3, 3), Sizing::Flex(1))
+            .child(button(3, 3), Sizing::Flex(1));
+        let screen = Stack::vertical().padding(1).child(Box::new(row), Sizing::Flex(1));
+
+        let mut framebuffer = Framebuffer::new(9, 5, Color::WHITE);
+        screen.draw(&mut Context::new(&mut framebuffer));
+
+        assert_eq!(
+            framebuffer.to_ascii(&[(Color::WHITE, '.'), (Color::BLACK, '#'), (Color::GREY, ' ')]),
+            "\
+.........
+.###.###.
+.# #.# #.
+.###.###.
+.........
+"
+        );
+    }
+}
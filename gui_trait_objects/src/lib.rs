@@ -1,24 +1,148 @@
-fn main() {
-    println!("Hello, world!");
-}
+pub mod canvas;
+pub mod event;
+pub mod font;
+pub mod framebuffer;
+pub mod geometry;
+pub mod json;
+pub mod layout;
+pub mod loader;
+pub mod svg;
+pub mod terminal;
+pub mod tree;
+pub mod widgets;
+
+pub use canvas::{Canvas, Color, Context};
+pub use event::{Event, Key};
+pub use framebuffer::Framebuffer;
+pub use geometry::{Point, Rect, Size};
+pub use layout::{Align, Direction, Layout, Sizing, Stack};
+pub use svg::SvgCanvas;
+pub use tree::{WidgetId, WidgetTree};
+pub use widgets::{Checkbox, SelectBox, TextField};
 
+// Components draw through a Context rather than straight onto a backend, so the same component can end up in a
+// Framebuffer, a terminal or anything else that implements Canvas.
 pub trait Draw {
-    fn draw(&self);
+    fn draw(&self, ctx: &mut Context);
+
+    // How much room the component would like - the screen has the final say in what it gets
+    fn preferred_size(&self) -> Size {
+        Size::new(0, 0)
+    }
+
+    // Reacts to an event - see event.rs for how events get here. Mouse positions are relative to the component's top
+    // left corner, and `size` is how big it was laid out. Returns whether the event was used.
+    fn handle_event(&mut self, _event: &Event, _size: Size) -> bool {
+        false
+    }
+
+    // Moves the focus to this component's next focus stop (previous, if not `forward`). A widget takes the focus if it
+    // doesn't have it; returning false means the focus has moved past this component, which no longer has it.
+    fn focus_next(&mut self, _forward: bool) -> bool {
+        false
+    }
+
+    fn blur(&mut self) {}
+
+    fn is_focused(&self) -> bool {
+        false
+    }
 }
 
+// The components live in a retained tree (see tree.rs), which lets them nest and remembers what needs drawing again
 pub struct Screen { // On the other hand, with the method using trait objects, one Screen instance can hold a Vec that contains a Box<Button> as well as a Box<TextField>. 
-    pub components: Vec<Box<dyn Draw>>, // NOTE: the dyn keyword - this is a trait object
+    tree: WidgetTree, // NOTE: the widgets in it are Box<dyn Draw> - trait objects
 }
 
 impl Screen {
-    pub fn run(&self) {
-        for component in self.components.iter() {
-            component.draw(); // This works differently than defining a struct that uses a generic type parameter with trait bounds... A generic type parameter can only be substituted with one concrete type at a time, whereas trait objects allow for multiple concrete types to fill in for the trait object at runtime.
+    // An empty screen that puts its components one under the other
+    pub fn new() -> Screen {
+        Screen::with_layout(Layout::new(Direction::Vertical))
+    }
+
+    // An empty screen that lays its components out with `layout`
+    pub fn with_layout(layout: Layout) -> Screen {
+        Screen { tree: WidgetTree::new(layout) }
+    }
+
+    // A screen with `components` one under the other, each at its preferred size
+    pub fn from_components(components: Vec<Box<dyn Draw>>) -> Screen {
+        let mut screen = Screen::new();
+        for component in components {
+            screen.add(component);
         }
+        screen
+    }
+
+    // Adds a component to the bottom of the screen, at its preferred size. For anything fancier, go through tree_mut.
+    pub fn add(&mut self, component: Box<dyn Draw>) -> WidgetId {
+        let root = self.tree.root();
+        self.tree.add_widget(root, component, Sizing::Preferred)
+    }
+
+    pub fn tree(&self) -> &WidgetTree {
+        &self.tree
+    }
+
+    pub fn tree_mut(&mut self) -> &mut WidgetTree {
+        &mut self.tree
+    }
+
+    // Where everything in the tree goes on a screen of `size`, in drawing order
+    pub fn layout(&mut self, size: Size) -> Vec<Rect> {
+        self.tree.layout(size);
+        let nodes = self.tree.descendants(self.tree.root());
+        nodes.into_iter().filter_map(|id| self.tree.rect(id)).collect()
+    }
+
+    // Draws everything
+    pub fn run(&mut self, canvas: &mut dyn Canvas) {
+        self.tree.draw(canvas); // This works differently than defining a struct that uses a generic type parameter with trait bounds... A generic type parameter can only be substituted with one concrete type at a time, whereas trait objects allow for multiple concrete types to fill in for the trait object at runtime.
+    }
+
+    // Draws only what's changed since the last run or redraw, on a canvas still showing that frame. Returns how many
+    // components were drawn.
+    pub fn redraw(&mut self, canvas: &mut dyn Canvas, background: Color) -> usize {
+        self.tree.redraw(canvas, background)
+    }
+
+    // Passes `event` (with screen coordinates) on to the component it's for, on a screen of `size`. Tab and shift-Tab
+    // move the focus, going round to the first component again after the last.
+    pub fn handle_event(&mut self, event: &Event, size: Size) -> bool {
+        match event {
+            Event::Key(Key::Tab) => self.focus_next(true),
+            Event::Key(Key::BackTab) => self.focus_next(false),
+            _ => self.tree.handle_event(event, size),
+        }
+    }
+
+    // Returns false only when nothing on the screen can take the focus
+    pub fn focus_next(&mut self, forward: bool) -> bool {
+        self.tree.focus_next(forward) || self.tree.focus_next(forward)
+    }
+
+    // Runs the screen on a fresh framebuffer of the given size
+    pub fn render(&mut self, width: i32, height: i32, background: Color) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(width, height, background);
+        self.run(&mut framebuffer);
+        framebuffer
+    }
+
+    // Runs the screen on an SVG document of the given size
+    pub fn to_svg(&mut self, width: i32, height: i32, background: Color) -> String {
+        let mut canvas = SvgCanvas::new(Size::new(width, height), background);
+        self.run(&mut canvas);
+        canvas.to_svg()
     }
 }
 
-/**
+impl Default for Screen {
+    fn default() -> Screen {
+        Screen::new()
+    }
+}
+
+/*
  * E.G. THIS =>
  * 
 
@@ -45,10 +169,195 @@ pub struct Button {
     pub width: i32,
     pub height: i32,
     pub label: String,
+    pressed: bool, // the mouse went down on it and hasn't come up yet
+    focused: bool,
+    on_click: Option<Box<dyn FnMut()>>,
+}
+
+impl Button {
+    pub fn new(width: i32, height: i32, label: &str) -> Button {
+        Button { width, height, label: label.to_string(), pressed: false, focused: false, on_click: None }
+    }
+
+    pub fn on_click<F: FnMut() + 'static>(mut self, f: F) -> Button {
+        self.on_click = Some(Box::new(f));
+        self
+    }
+
+    pub fn click(&mut self) {
+        if let Some(f) = &mut self.on_click {
+            f();
+        }
+    }
 }
 
 impl Draw for Button {
-    fn draw(&self) {
-        // code to draw a button would go here.
+    fn draw(&self, ctx: &mut Context) {
+        let bounds = ctx.bounds();
+        ctx.fill_rect(bounds, if self.pressed { Color::DARK_GREY } else { Color::GREY });
+        ctx.stroke_rect(bounds, if self.focused { Color::BLUE } else { Color::BLACK });
+        ctx.centered_text(bounds, &self.label, Color::BLACK);
+    }
+
+    fn preferred_size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    // A click is the mouse going down and coming back up on the button - or Enter or space while it has the focus.
+    // Every MouseUp reaches the button, wherever it is, so a press that's let go of somewhere else still ends.
+    fn handle_event(&mut self, event: &Event, size: Size) -> bool {
+        match event {
+            Event::MouseDown(_) => {
+                self.pressed = true;
+                self.focused = true;
+            }
+            Event::MouseUp(point) if self.pressed => {
+                self.pressed = false;
+                if Rect::from_size(size).contains(*point) {
+                    self.click();
+                }
+            }
+            Event::Key(Key::Enter) | Event::Key(Key::Char(' ')) if self.focused => self.click(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn focus_next(&mut self, _forward: bool) -> bool {
+        self.focused = !self.focused;
+        self.focused
+    }
+
+    fn blur(&mut self) {
+        self.focused = false;
+        self.pressed = false;
+    }
+
+    fn is_focused(&self) -> bool {
+        self.focused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const LEGEND: &[(Color, char)] = &[(Color::WHITE, '.'), (Color::BLACK, '#'), (Color::GREY, ' ')];
+
+    // Everything the widgets report, in order
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    // A form: a text field, then a row with a checkbox and a button, then a select box
+    fn form(log: &Log) -> Screen {
+        let (name, agree, ok, pick) = (Rc::clone(log), Rc::clone(log), Rc::clone(log), Rc::clone(log));
+
+        let field = TextField::new(60, 10).on_change(move |text| name.borrow_mut().push(format!("name {}", text)));
+        let checkbox =
+            Checkbox::new(40, 10, "Agree").on_toggle(move |on| agree.borrow_mut().push(format!("agree {}", on)));
+        let button = Button::new(20, 10, "OK").on_click(move || ok.borrow_mut().push(String::from("ok")));
+        let options = vec![String::from("Yes"), String::from("No")];
+        let select =
+            SelectBox::new(60, 20, options).on_select(move |_, option| pick.borrow_mut().push(format!("pick {}", option)));
+
+        let row = Stack::horizontal()
+            .child(Box::new(checkbox), Sizing::Preferred)
+            .child(Box::new(button), Sizing::Preferred);
+        Screen::from_components(vec![Box::new(field), Box::new(row), Box::new(select)])
+    }
+
+    #[test]
+    fn events_reach_nested_widgets_by_hit_testing_and_focus() {
+        let log = Log::default();
+        let mut screen = form(&log);
+        let size = Size::new(60, 50);
+        let mut send = |event| screen.handle_event(&event, size);
+
+        send(Event::MouseDown(Point::new(5, 5))); // the text field
+        send(Event::Key(Key::Char('B')));
+        send(Event::Key(Key::Tab)); // the checkbox
+        send(Event::Key(Key::Char('b'))); // goes to the checkbox now, which ignores it
+        send(Event::Key(Key::Char(' ')));
+        send(Event::Key(Key::Tab)); // the button
+        send(Event::Key(Key::Enter));
+        send(Event::MouseDown(Point::new(45, 12))); // the button again, by mouse
+        send(Event::MouseUp(Point::new(45, 12)));
+        send(Event::MouseDown(Point::new(5, 35))); // "No" in the select box
+        assert!(!send(Event::MouseDown(Point::new(5, 45)))); // below everything
+
+        assert_eq!(*log.borrow(), vec!["name B", "agree true", "ok", "ok", "pick No"]);
+    }
+
+    #[test]
+    fn letting_go_off_a_button_isnt_a_click() {
+        let log = Log::default();
+        let mut screen = form(&log);
+        let size = Size::new(60, 50);
+        let mut send = |event| screen.handle_event(&event, size);
+
+        send(Event::MouseDown(Point::new(45, 12))); // on the button
+        send(Event::MouseUp(Point::new(5, 45))); // below everything
+        send(Event::MouseUp(Point::new(45, 12))); // back on the button, but it isn't pressed any more
+        assert_eq!(*log.borrow(), Vec::<String>::new());
+
+        send(Event::MouseDown(Point::new(45, 12)));
+        send(Event::MouseUp(Point::new(46, 13)));
+        assert_eq!(*log.borrow(), vec!["ok"]);
+    }
+
+    // Which of the screen's components has the focus
+    fn position_of_focus(screen: &Screen) -> Option<usize> {
+        let tree = screen.tree();
+        tree.focused().and_then(|id| tree.children(tree.root()).iter().position(|&child| child == id))
+    }
+
+    #[test]
+    fn tab_goes_round_every_focusable_widget() {
+        let log = Log::default();
+        let mut screen = form(&log);
+        let mut focused = Vec::new();
+
+        for _ in 0..5 {
+            screen.handle_event(&Event::Key(Key::Tab), Size::new(60, 40));
+            focused.push(position_of_focus(&screen));
+        }
+        screen.handle_event(&Event::Key(Key::BackTab), Size::new(60, 40));
+        focused.push(position_of_focus(&screen));
+
+        // text field, checkbox and button (both in the row), select box, then back round to the text field
+        assert_eq!(focused, vec![Some(0), Some(1), Some(1), Some(2), Some(0), Some(2)]);
+        let tree = screen.tree();
+        assert_eq!(tree.children(tree.root()).iter().filter(|&&id| tree.widget(id).unwrap().is_focused()).count(), 1);
+    }
+
+    #[test]
+    fn it_draws_components_in_order_down_the_screen() {
+        let mut screen = Screen::from_components(vec![
+            Box::new(Button::new(9, 11, "OK")),
+            Box::new(Button::new(4, 3, "")),
+        ]);
+
+        // "OK" is wider than its button, so it gets cut off at the button's edges
+        assert_eq!(
+            screen.render(10, 15, Color::WHITE).to_ascii(LEGEND),
+            "\
+#########.
+#       #.
+###  #  #.
+#  # #  #.
+#  # # ##.
+#  # ## #.
+#  # # ##.
+#  # #  #.
+###  #  #.
+#       #.
+#########.
+####......
+#  #......
+####......
+..........
+"
+        );
     }
 }
\ No newline at end of file
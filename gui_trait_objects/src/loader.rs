@@ -0,0 +1,433 @@
+// Building screens from a description instead of by hand. A screen is a JSON document listing its components, each
+// an object whose "type" says which constructor in the Registry builds it and whose other keys are its properties:
+//
+//     {"components": [
+//         {"type": "stack", "direction": "horizontal", "spacing": 2, "children": [
+//             {"type": "button", "label": "OK", "width": 10, "height": 3, "sizing": {"flex": 1}},
+//             {"type": "checkbox", "label": "Again?", "width": 12, "height": 1, "self_align": "center"}
+//         ]}
+//     ]}
+//
+// A stack's "align" lines its children up across it, and a child's "self_align" overrides that for just the one child,
+// so a stack inside a stack can have both.
+// The built-in types are button, text_field, checkbox, select_box and stack. Any other Draw type can be used too, once
+// its constructor is registered under a name. A property that nothing reads is an error, so typos don't go unnoticed.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::json::{self, SyntaxError, Value};
+use crate::layout::{Align, Direction, Sizing, Stack};
+use crate::widgets::{Checkbox, SelectBox, TextField};
+use crate::{Button, Draw, Screen};
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Syntax(SyntaxError),
+    Invalid { path: String, message: String }, // the document is JSON, but not a screen we can build
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Syntax(e) => write!(f, "{}", e),
+            LoadError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+impl From<SyntaxError> for LoadError {
+    fn from(e: SyntaxError) -> LoadError {
+        LoadError::Syntax(e)
+    }
+}
+
+// One component's description, with getters that turn missing or mistyped properties into LoadErrors that say
+// where in the document the problem is.
+pub struct Properties<'a> {
+    value: &'a Value,
+    path: String,
+    used: RefCell<Vec<String>>,
+}
+
+impl<'a> Properties<'a> {
+    fn new(value: &'a Value, path: String) -> Properties<'a> {
+        Properties { value, path, used: RefCell::new(Vec::new()) }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn error(&self, message: &str) -> LoadError {
+        LoadError::Invalid { path: self.path.clone(), message: message.to_string() }
+    }
+
+    // The raw value of a property, if it's there
+    pub fn get(&self, key: &str) -> Option<&'a Value> {
+        self.used.borrow_mut().push(key.to_string());
+        self.value.get(key)
+    }
+
+    fn wrong_type(&self, key: &str, expected: &str, found: &Value) -> LoadError {
+        self.error(&format!("'{}' should be {}, not {}", key, expected, found.kind()))
+    }
+
+    pub fn int(&self, key: &str) -> Result<i32, LoadError> {
+        match self.get(key) {
+            Some(Value::Number(n)) if n.fract() == 0.0 && n.abs() <= i32::MAX as f64 => Ok(*n as i32),
+            Some(other) => Err(self.wrong_type(key, "a whole number", other)),
+            None => Err(self.error(&format!("'{}' is missing", key))),
+        }
+    }
+
+    pub fn int_or(&self, key: &str, default: i32) -> Result<i32, LoadError> {
+        match self.get(key) {
+            None => Ok(default),
+            Some(_) => self.int(key),
+        }
+    }
+
+    pub fn string(&self, key: &str) -> Result<&'a str, LoadError> {
+        match self.get(key) {
+            Some(Value::String(s)) => Ok(s),
+            Some(other) => Err(self.wrong_type(key, "a string", other)),
+            None => Err(self.error(&format!("'{}' is missing", key))),
+        }
+    }
+
+    pub fn string_or(&self, key: &str, default: &'a str) -> Result<&'a str, LoadError> {
+        match self.get(key) {
+            None => Ok(default),
+            Some(_) => self.string(key),
+        }
+    }
+
+    pub fn bool_or(&self, key: &str, default: bool) -> Result<bool, LoadError> {
+        match self.get(key) {
+            None => Ok(default),
+            Some(Value::Bool(b)) => Ok(*b),
+            Some(other) => Err(self.wrong_type(key, "true or false", other)),
+        }
+    }
+
+    pub fn strings(&self, key: &str) -> Result<Vec<String>, LoadError> {
+        match self.get(key) {
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| match item {
+                    Value::String(s) => Ok(s.clone()),
+                    other => Err(self.wrong_type(key, "a list of strings", other)),
+                })
+                .collect(),
+            Some(other) => Err(self.wrong_type(key, "a list of strings", other)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    // The objects in a list property, each with its own path - for containers to build their children from
+    pub fn list(&self, key: &str) -> Result<Vec<Properties<'a>>, LoadError> {
+        match self.get(key) {
+            Some(Value::Array(items)) => Ok(items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| Properties::new(item, format!("{}.{}[{}]", self.path, key, i)))
+                .collect()),
+            Some(other) => Err(self.wrong_type(key, "a list", other)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    // Keys nobody has asked for
+    fn unused(&self) -> Vec<&'a str> {
+        let used = self.used.borrow();
+        match self.value {
+            Value::Object(fields) => {
+                fields.iter().map(|(k, _)| k.as_str()).filter(|k| !used.iter().any(|u| u == k)).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+pub type Constructor = Box<dyn Fn(&Properties, &Registry) -> Result<Box<dyn Draw>, LoadError>>;
+
+pub struct Registry {
+    constructors: HashMap<String, Constructor>,
+}
+
+impl Registry {
+    // A registry that knows no types at all
+    pub fn empty() -> Registry {
+        Registry { constructors: HashMap::new() }
+    }
+
+    // A registry that knows the crate's own components
+    pub fn new() -> Registry {
+        let mut registry = Registry::empty();
+        registry.register("button", |props, _| {
+            Ok(Box::new(Button::new(props.int("width")?, props.int("height")?, props.string_or("label", "")?)))
+        });
+        registry.register("text_field", |props, _| {
+            let field = TextField::new(props.int("width")?, props.int("height")?);
+            Ok(Box::new(field.with_text(props.string_or("text", "")?)))
+        });
+        registry.register("checkbox", |props, _| {
+            let checkbox = Checkbox::new(props.int("width")?, props.int("height")?, props.string_or("label", "")?);
+            Ok(Box::new(checkbox.with_checked(props.bool_or("checked", false)?)))
+        });
+        registry.register("select_box", |props, _| {
+            Ok(Box::new(SelectBox::new(props.int("width")?, props.int("height")?, props.strings("options")?)))
+        });
+        registry.register("stack", build_stack);
+        registry
+    }
+
+    // Lets `name` be used as a "type". Registering a name again replaces the old constructor.
+    pub fn register<F>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn(&Properties, &Registry) -> Result<Box<dyn Draw>, LoadError> + 'static,
+    {
+        self.constructors.insert(name.to_string(), Box::new(constructor));
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.constructors.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    // Builds the component `props` describes
+    pub fn build(&self, props: &Properties) -> Result<Box<dyn Draw>, LoadError> {
+        if !matches!(props.value, Value::Object(_)) {
+            return Err(props.error(&format!("a component should be an object, not {}", props.value.kind())));
+        }
+        let kind = props.string("type")?;
+        let constructor = match self.constructors.get(kind) {
+            Some(constructor) => constructor,
+            None => return Err(props.error(&format!("unknown component type '{}'", kind))),
+        };
+
+        let component = constructor(props, self)?;
+        match props.unused().first() {
+            Some(key) => Err(props.error(&format!("'{}' isn't a property of {}", key, kind))),
+            None => Ok(component),
+        }
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry::new()
+    }
+}
+
+fn build_stack(props: &Properties, registry: &Registry) -> Result<Box<dyn Draw>, LoadError> {
+    let direction = match props.string_or("direction", "vertical")? {
+        "vertical" => Direction::Vertical,
+        "horizontal" => Direction::Horizontal,
+        other => return Err(props.error(&format!("'direction' should be vertical or horizontal, not '{}'", other))),
+    };
+
+    let mut stack = Stack::new(direction)
+        .padding(props.int_or("padding", 0)?)
+        .spacing(props.int_or("spacing", 0)?)
+        .align(align(props, "align")?.unwrap_or(Align::Start))
+        .justify(align(props, "justify")?.unwrap_or(Align::Start));
+
+    for child in props.list("children")? {
+        // how a child sits in the stack is read here, before the child's own constructor checks for unused keys
+        let sizing = sizing(&child)?;
+        let child_align = align(&child, "self_align")?;
+        let component = registry.build(&child)?;
+        stack = match child_align {
+            Some(a) => stack.aligned_child(component, sizing, a),
+            None => stack.child(component, sizing),
+        };
+    }
+
+    Ok(Box::new(stack))
+}
+
+// "preferred", {"fixed": n} or {"flex": n}, going with preferred when it's left out
+fn sizing(props: &Properties) -> Result<Sizing, LoadError> {
+    let bad = || props.error("'sizing' should be \"preferred\", {\"fixed\": n} or {\"flex\": n}");
+    let value = match props.get("sizing") {
+        None => return Ok(Sizing::Preferred),
+        Some(value) => value,
+    };
+
+    match value {
+        Value::String(s) if s == "preferred" => Ok(Sizing::Preferred),
+        Value::Object(fields) if fields.len() == 1 => {
+            let inner = Properties::new(value, format!("{}.sizing", props.path));
+            match fields[0].0.as_str() {
+                "fixed" => Ok(Sizing::Fixed(inner.int("fixed")?)),
+                "flex" => Ok(Sizing::Flex(inner.int("flex")?.max(0) as u32)),
+                _ => Err(bad()),
+            }
+        }
+        _ => Err(bad()),
+    }
+}
+
+fn align(props: &Properties, key: &str) -> Result<Option<Align>, LoadError> {
+    let name = match props.get(key) {
+        None => return Ok(None),
+        Some(_) => props.string(key)?,
+    };
+    match name {
+        "start" => Ok(Some(Align::Start)),
+        "center" => Ok(Some(Align::Center)),
+        "end" => Ok(Some(Align::End)),
+        "stretch" => Ok(Some(Align::Stretch)),
+        other => Err(props.error(&format!("'{}' should be start, center, end or stretch, not '{}'", key, other))),
+    }
+}
+
+// A screen from a document like the one at the top of this file
+pub fn load_screen(text: &str, registry: &Registry) -> Result<Screen, LoadError> {
+    let document = json::parse(text)?;
+    let root = Properties::new(&document, String::new());
+    if !matches!(document, Value::Object(_)) || root.get("components").is_none() {
+        return Err(LoadError::Invalid {
+            path: String::from("document"),
+            message: String::from("should be an object with a list of \"components\""),
+        });
+    }
+
+    let mut components = Vec::new();
+    for (i, props) in root.list("components")?.into_iter().enumerate() {
+        let props = Properties::new(props.value, format!("components[{}]", i));
+        components.push(registry.build(&props)?);
+    }
+    Ok(Screen::from_components(components))
+}
+
+pub fn load_screen_file<P: AsRef<Path>>(path: P, registry: &Registry) -> Result<Screen, LoadError> {
+    load_screen(&fs::read_to_string(path)?, registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::{Color, Context};
+    use crate::geometry::{Point, Size};
+
+    const FORM: &str = r#"{"components": [
+        {"type": "text_field", "width": 30, "height": 10, "text": "Bertie"},
+        {"type": "stack", "direction": "horizontal", "spacing": 2, "align": "center", "children": [
+            {"type": "checkbox", "label": "Yes", "width": 20, "height": 9, "checked": true},
+            {"type": "button", "label": "OK", "width": 15, "height": 12, "sizing": {"fixed": 20}, "self_align": "end"}
+        ]},
+        {"type": "select_box", "width": 30, "height": 20, "options": ["A", "B"]}
+    ]}"#;
+
+    #[test]
+    fn it_builds_the_same_screen_as_code_would() {
+        let mut loaded = load_screen(FORM, &Registry::new()).unwrap();
+        let mut built = Screen::from_components(vec![
+            Box::new(TextField::new(30, 10).with_text("Bertie")),
+            Box::new(
+                Stack::horizontal()
+                    .spacing(2)
+                    .align(Align::Center)
+                    .child(Box::new(Checkbox::new(20, 9, "Yes").with_checked(true)), Sizing::Preferred)
+                    .aligned_child(Box::new(Button::new(15, 12, "OK")), Sizing::Fixed(20), Align::End),
+            ),
+            Box::new(SelectBox::new(30, 20, vec![String::from("A"), String::from("B")])),
+        ]);
+
+        assert_eq!(loaded.layout(Size::new(50, 60)), built.layout(Size::new(50, 60)));
+        assert_eq!(loaded.render(50, 60, Color::WHITE), built.render(50, 60, Color::WHITE));
+    }
+
+    #[test]
+    fn nested_stacks_keep_their_own_align_apart_from_where_they_sit() {
+        let document = r#"{"components": [
+            {"type": "stack", "align": "start", "children": [
+                {"type": "stack", "align": "end", "self_align": "center", "children": [
+                    {"type": "button", "label": "A", "width": 4, "height": 2},
+                    {"type": "button", "label": "B", "width": 10, "height": 2}
+                ]}
+            ]}
+        ]}"#;
+        let mut loaded = load_screen(document, &Registry::new()).unwrap();
+        let inner = Stack::vertical()
+            .align(Align::End)
+            .child(Box::new(Button::new(4, 2, "A")), Sizing::Preferred)
+            .child(Box::new(Button::new(10, 2, "B")), Sizing::Preferred);
+        let outer = Stack::vertical().aligned_child(Box::new(inner), Sizing::Preferred, Align::Center);
+        let mut built = Screen::from_components(vec![Box::new(outer)]);
+
+        assert_eq!(loaded.layout(Size::new(30, 10)), built.layout(Size::new(30, 10)));
+        assert_eq!(loaded.render(30, 10, Color::WHITE), built.render(30, 10, Color::WHITE));
+    }
+
+    // Something from outside the crate
+    struct Dot {
+        color: Color,
+    }
+
+    impl Draw for Dot {
+        fn draw(&self, ctx: &mut Context) {
+            ctx.line(Point::new(0, 0), Point::new(0, 0), self.color);
+        }
+
+        fn preferred_size(&self) -> Size {
+            Size::new(1, 1)
+        }
+    }
+
+    #[test]
+    fn other_types_can_be_registered() {
+        let mut registry = Registry::new();
+        registry.register("dot", |props, _| {
+            let color = if props.bool_or("dark", false)? { Color::BLACK } else { Color::GREY };
+            Ok(Box::new(Dot { color }))
+        });
+        let document = r#"{"components": [{"type": "dot"}, {"type": "dot", "dark": true}]}"#;
+        let mut screen = load_screen(document, &registry).unwrap();
+
+        let framebuffer = screen.render(1, 2, Color::WHITE);
+        assert_eq!(framebuffer.pixel(0, 0), Some(Color::GREY));
+        assert_eq!(framebuffer.pixel(0, 1), Some(Color::BLACK));
+        assert!(registry.names().contains(&"dot"));
+    }
+
+    #[test]
+    fn mistakes_are_reported_with_their_path() {
+        let error = |text: &str| load_screen(text, &Registry::new()).err().unwrap().to_string();
+
+        assert_eq!(error(r#"{"components": [{"type": "slider"}]}"#), "components[0]: unknown component type 'slider'");
+        assert_eq!(
+            error(r#"{"components": [{"type": "stack", "children": [{"type": "button", "width": 1, "hieght": 2}]}]}"#),
+            "components[0].children[0]: 'height' is missing"
+        );
+        assert_eq!(
+            error(r#"{"components": [{"type": "button", "width": 1, "height": 2, "lable": "OK"}]}"#),
+            "components[0]: 'lable' isn't a property of button"
+        );
+        assert_eq!(
+            error(r#"{"components": [{"type": "stack", "children": [{"type": "stack", "sizing": {"flex": "1"}}]}]}"#),
+            "components[0].children[0].sizing: 'flex' should be a whole number, not a string"
+        );
+        assert_eq!(error(r#"{"components": [{"type": "button",}]}"#), "line 1, column 35: expected a key in quotes");
+        assert_eq!(error("[]"), "document: should be an object with a list of \"components\"");
+    }
+}
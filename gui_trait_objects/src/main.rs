@@ -1,6 +1,13 @@
 extern crate gui_trait_objects;
-use gui_trait_objects::Draw;
+use gui_trait_objects::terminal::{self, Flow, RawMode, Terminal};
+use gui_trait_objects::{Checkbox, Color, Context, Draw, Event, Key, Point, Rect, Size, Sizing, Stack, TextField};
 use gui_trait_objects::{Screen, Button};
+use std::cell::Cell;
+use std::env;
+use std::fs;
+use std::io;
+use std::process;
+use std::rc::Rc;
 
 // If someone using our library decides to implement a SelectBox struct that has width, height, and options fields, they implement the Draw trait on the SelectBox type as well
 
@@ -11,32 +18,90 @@ struct SelectBox {
 }
 
 impl Draw for SelectBox {
-    fn draw(&self) {
-        // code to actually draw a select box
+    fn draw(&self, ctx: &mut Context) {
+        let bounds = ctx.bounds();
+        ctx.fill_rect(bounds, Color::WHITE);
+        ctx.stroke_rect(bounds, Color::BLACK);
+
+        let row_height = bounds.height / self.options.len().max(1) as i32;
+        for (i, option) in self.options.iter().enumerate() {
+            let row = Rect::new(0, i as i32 * row_height, bounds.width, row_height);
+            ctx.text(Point::new(3, row.y + (row_height - 7) / 2), option, Color::BLACK);
+        }
+    }
+
+    fn preferred_size(&self) -> Size {
+        Size::new(self.width as i32, self.height as i32)
     }
 }
 
 // Our library’s user can now write their main function to create a Screen instance. To the Screen instance, they can add a SelectBox and a Button by putting each in a Box<T> to become a trait object. They can then call the run method on the Screen instance, which will call draw on each of the components.
 
 fn main() {
-    let screen = Screen {
-        components: vec![
-            Box::new(SelectBox {
-                width: 75,
-                height: 10,
-                options: vec![
-                    String::from("Yes"),
-                    String::from("Maybe"),
-                    String::from("No")
-                ],
-            }),
-            Box::new(Button {
-                width: 50,
-                height: 10,
-                label: String::from("OK"),
-            }),
-        ],
-    };
-
-    screen.run();
+    let mut screen = Screen::from_components(vec![
+        Box::new(SelectBox {
+            width: 75,
+            height: 30,
+            options: vec![
+                String::from("Yes"),
+                String::from("Maybe"),
+                String::from("No")
+            ],
+        }),
+        Box::new(Button::new(50, 10, "OK")),
+    ]);
+
+    // `cargo run -- --tui` runs a little form in the terminal instead
+    if env::args().nth(1).as_deref() == Some("--tui") {
+        if let Err(e) = run_tui() {
+            eprintln!("Terminal error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    // `cargo run -- screen.ppm` saves what the screen looks like, and `cargo run -- screen.svg` does it as an SVG
+    let framebuffer = screen.render(80, 45, Color::WHITE);
+    if let Some(path) = env::args().nth(1) {
+        let written = if path.ends_with(".svg") {
+            fs::write(&path, screen.to_svg(80, 45, Color::WHITE))
+        } else {
+            framebuffer.write_ppm(&path)
+        };
+        written.expect("couldn't write the image");
+        println!("Wrote {}", path);
+    }
+}
+
+// Sizes are in character cells here. Tab moves between the widgets, and Escape, Ctrl-C or the Quit button gets out.
+fn run_tui() -> io::Result<()> {
+    let quit = Rc::new(Cell::new(false));
+    let quit_button = Rc::clone(&quit);
+
+    let options = vec![String::from("Yes"), String::from("Maybe"), String::from("No")];
+    let buttons = Stack::horizontal()
+        .spacing(2)
+        .child(Box::new(Button::new(10, 3, "OK")), Sizing::Preferred)
+        .child(Box::new(Button::new(10, 3, "Quit").on_click(move || quit_button.set(true))), Sizing::Preferred);
+    let form = Stack::vertical()
+        .padding(1)
+        .spacing(1)
+        .child(Box::new(TextField::new(30, 1)), Sizing::Preferred)
+        .child(Box::new(Checkbox::new(30, 1, "Subscribe")), Sizing::Preferred)
+        .child(Box::new(gui_trait_objects::SelectBox::new(30, 3, options)), Sizing::Preferred)
+        .child(Box::new(buttons), Sizing::Preferred);
+    let mut screen = Screen::from_components(vec![Box::new(form)]);
+
+    let size = terminal::terminal_size().unwrap_or(Size::new(80, 24));
+    let _raw = RawMode::enable()?;
+    // dropped before _raw, so the terminal is put back while it's still in raw mode - even if run fails or panics
+    let mut terminal = Terminal::new(io::stdout(), size, Color::WHITE);
+    terminal.enter()?;
+    let result = terminal.run(&mut screen, io::stdin(), |event, _| match event {
+        Event::Key(Key::Escape) | Event::Key(Key::Ctrl('c')) => Flow::Quit,
+        _ if quit.get() => Flow::Quit,
+        _ => Flow::Continue,
+    });
+    terminal.leave()?;
+    result
 }
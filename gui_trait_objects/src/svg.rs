@@ -0,0 +1,234 @@
+// A canvas that writes an SVG document instead of pixels, for mockups and pictures of screens in documentation. Every
+// primitive becomes a shape, so the picture scales to any size and the labels stay real text.
+//
+// Text is measured the same way the Framebuffer measures it (one unit per font pixel), and each <text> is stretched to
+// exactly that width, so a screen lays out the same in both and the SVG looks like a smooth version of the PPM. Clips
+// become <clipPath>s, with everything drawn under the same clip kept together in one group.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::canvas::{Canvas, Color};
+use crate::font;
+use crate::geometry::{Point, Rect, Size};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgCanvas {
+    size: Size,
+    background: Color,
+    clip: Rect,
+    clips: Vec<Rect>, // one <clipPath> each, numbered by where they are in here
+    groups: Vec<(Option<usize>, Vec<String>)>, // runs of elements under the same clip; None for no clip at all
+}
+
+impl SvgCanvas {
+    pub fn new(size: Size, background: Color) -> SvgCanvas {
+        let size = Size::new(size.width.max(0), size.height.max(0));
+        let clip = Rect::from_size(size);
+        SvgCanvas { size, background, clip, clips: Vec::new(), groups: Vec::new() }
+    }
+
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (self.size.width, self.size.height);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            width, height, width, height
+        );
+        if !self.clips.is_empty() {
+            svg.push_str("<defs>\n");
+            for (i, clip) in self.clips.iter().enumerate() {
+                let _ = writeln!(svg, "<clipPath id=\"clip{}\">{}</clipPath>", i, rect(*clip, ""));
+            }
+            svg.push_str("</defs>\n");
+        }
+        let _ = writeln!(svg, "{}", rect(Rect::from_size(self.size), &format!("fill=\"{}\"", hex(self.background))));
+
+        for (clip, elements) in &self.groups {
+            if let Some(clip) = clip {
+                let _ = writeln!(svg, "<g clip-path=\"url(#clip{})\">", clip);
+            }
+            for element in elements {
+                svg.push_str(element);
+                svg.push('\n');
+            }
+            if clip.is_some() {
+                svg.push_str("</g>\n");
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn write_svg<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+
+    fn push(&mut self, element: String) {
+        // a clip that takes in the whole canvas doesn't cut anything off
+        let clip = if self.clip.intersect(Rect::from_size(self.size)) == Rect::from_size(self.size) {
+            None
+        } else {
+            Some(self.clips.iter().position(|&c| c == self.clip).unwrap_or_else(|| {
+                self.clips.push(self.clip);
+                self.clips.len() - 1
+            }))
+        };
+
+        match self.groups.last_mut() {
+            Some((last, elements)) if *last == clip => elements.push(element),
+            _ => self.groups.push((clip, vec![element])),
+        }
+    }
+}
+
+impl Canvas for SvgCanvas {
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn set_clip(&mut self, clip: Rect) {
+        self.clip = clip;
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        if !rect.is_empty() {
+            self.push(self::rect(rect, &format!("fill=\"{}\"", hex(color))));
+        }
+    }
+
+    // The stroke is centred on its path, so the path runs half a unit in from the edges to keep the line inside
+    fn stroke_rect(&mut self, rect: Rect, color: Color) {
+        if rect.is_empty() {
+            return;
+        }
+        self.push(format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"{}\"/>",
+            f64::from(rect.x) + 0.5,
+            f64::from(rect.y) + 0.5,
+            rect.width - 1,
+            rect.height - 1,
+            hex(color)
+        ));
+    }
+
+    // Through the middle of the end points' pixels, with square ends so that both of those pixels are covered
+    fn line(&mut self, from: Point, to: Point, color: Color) {
+        self.push(format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-linecap=\"square\"/>",
+            f64::from(from.x) + 0.5,
+            f64::from(from.y) + 0.5,
+            f64::from(to.x) + 0.5,
+            f64::from(to.y) + 0.5,
+            hex(color)
+        ));
+    }
+
+    // The baseline goes along the bottom of where the Framebuffer's glyphs would be
+    fn text(&mut self, at: Point, text: &str, color: Color) {
+        let size = self.text_size(text);
+        if size.width == 0 {
+            return;
+        }
+        self.push(format!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\" textLength=\"{}\" \
+             lengthAdjust=\"spacingAndGlyphs\" fill=\"{}\">{}</text>",
+            at.x,
+            at.y + size.height,
+            font::LINE_HEIGHT + 2,
+            size.width,
+            hex(color),
+            escape(text)
+        ));
+    }
+
+    fn text_size(&self, text: &str) -> Size {
+        let chars = text.chars().count() as i32;
+        if chars == 0 {
+            return Size::new(0, 0);
+        }
+        Size::new(chars * font::ADVANCE - 1, font::GLYPH_HEIGHT)
+    }
+}
+
+fn rect(rect: Rect, attributes: &str) -> String {
+    let attributes = if attributes.is_empty() { String::new() } else { format!(" {}", attributes) };
+    let (x, y, width, height) = (rect.x, rect.y, rect.width, rect.height);
+    format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}/>", x, y, width, height, attributes)
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Button, Checkbox, Screen};
+
+    #[test]
+    fn it_writes_each_primitive_as_a_shape() {
+        let mut screen = Screen::from_components(vec![
+            Box::new(Button::new(20, 11, "OK")),
+            Box::new(Checkbox::new(40, 9, "<b> & co").with_checked(true)),
+        ]);
+        let svg = screen.to_svg(60, 30, Color::WHITE);
+
+        assert_eq!(
+            svg,
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="60" height="30" viewBox="0 0 60 30">
+<defs>
+<clipPath id="clip0"><rect x="0" y="0" width="20" height="11"/></clipPath>
+<clipPath id="clip1"><rect x="0" y="11" width="40" height="9"/></clipPath>
+</defs>
+<rect x="0" y="0" width="60" height="30" fill="#ffffff"/>
+<g clip-path="url(#clip0)">
+<rect x="0" y="0" width="20" height="11" fill="#c0c0c0"/>
+<rect x="0.5" y="0.5" width="19" height="10" fill="none" stroke="#000000"/>
+<text x="4" y="9" font-family="monospace" font-size="10" textLength="11" lengthAdjust="spacingAndGlyphs" fill="#000000">OK</text>
+</g>
+<g clip-path="url(#clip1)">
+<rect x="0" y="11" width="9" height="9" fill="#ffffff"/>
+<rect x="0.5" y="11.5" width="8" height="8" fill="none" stroke="#000000"/>
+<line x1="2.5" y1="13.5" x2="6.5" y2="17.5" stroke="#000000" stroke-linecap="square"/>
+<line x1="6.5" y1="13.5" x2="2.5" y2="17.5" stroke="#000000" stroke-linecap="square"/>
+<text x="12" y="19" font-family="monospace" font-size="10" textLength="47" lengthAdjust="spacingAndGlyphs" fill="#000000">&lt;b&gt; &amp; co</text>
+</g>
+</svg>
+"##
+        );
+    }
+
+    #[test]
+    fn a_clip_over_the_whole_canvas_needs_no_clip_path() {
+        let mut canvas = SvgCanvas::new(Size::new(4, 4), Color::BLACK);
+        canvas.line(Point::new(0, 0), Point::new(3, 3), Color::WHITE);
+        canvas.set_clip(Rect::new(-5, -5, 20, 20));
+        canvas.fill_rect(Rect::new(1, 1, 2, 2), Color::BLUE);
+
+        assert_eq!(
+            canvas.to_svg(),
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4" viewBox="0 0 4 4">
+<rect x="0" y="0" width="4" height="4" fill="#000000"/>
+<line x1="0.5" y1="0.5" x2="3.5" y2="3.5" stroke="#ffffff" stroke-linecap="square"/>
+<rect x="1" y="1" width="2" height="2" fill="#005ac8"/>
+</svg>
+"##
+        );
+    }
+}
@@ -0,0 +1,559 @@
+// A terminal backend, for building TUI tools out of the same components. Everything is measured in character cells:
+// a CellBuffer is a Canvas with one cell per unit, and a Terminal keeps the last frame it drew so each new one only
+// sends the cells that changed, as ANSI escape codes.
+//
+// Input comes from the terminal in raw mode (set up with `stty`, so there's nothing to link against) with mouse
+// reporting switched on, and parse_input turns the bytes into the same Events the rest of the crate uses.
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+use crate::canvas::{Canvas, Color};
+use crate::event::{Event, Key};
+use crate::geometry::{Point, Rect, Size};
+use crate::Screen;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellBuffer {
+    size: Size,
+    cells: Vec<Cell>,
+    clip: Rect,
+}
+
+impl CellBuffer {
+    pub fn new(size: Size, background: Color) -> CellBuffer {
+        let size = Size::new(size.width.max(0), size.height.max(0));
+        let blank = Cell { ch: ' ', fg: Color::BLACK, bg: background };
+        let len = (size.width as usize).checked_mul(size.height as usize).expect("cell buffer too big");
+        CellBuffer { size, cells: vec![blank; len], clip: Rect::from_size(size) }
+    }
+
+    pub fn cell(&self, x: i32, y: i32) -> Option<Cell> {
+        if Rect::from_size(self.size).contains(Point::new(x, y)) {
+            Some(self.cells[self.index(x, y)])
+        } else {
+            None
+        }
+    }
+
+    // The characters alone, one line per row - for tests
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for row in self.cells.chunks(self.size.width.max(1) as usize) {
+            text.extend(row.iter().map(|cell| cell.ch));
+            text.push('\n');
+        }
+        text
+    }
+
+    // Changes the cell at (x, y) if it's inside the clip
+    fn update<F: FnOnce(&mut Cell)>(&mut self, x: i32, y: i32, f: F) {
+        if self.clip.contains(Point::new(x, y)) {
+            let i = self.index(x, y);
+            f(&mut self.cells[i]);
+        }
+    }
+
+    // The escape codes that turn `previous` (what's on the terminal now) into this buffer. With no previous frame, or
+    // one of a different size, the whole terminal is cleared and redrawn.
+    pub fn diff(&self, previous: Option<&CellBuffer>) -> String {
+        let previous = previous.filter(|previous| previous.size == self.size);
+        let mut out = String::new();
+        if previous.is_none() {
+            out.push_str("\x1b[0m\x1b[2J");
+        }
+
+        let mut cursor = None; // where the terminal's cursor is, once we know
+        let mut colors = None;
+        for y in 0..self.size.height {
+            for x in 0..self.size.width {
+                let cell = self.cell(x, y).unwrap();
+                if previous.is_some_and(|previous| previous.cell(x, y) == Some(cell)) {
+                    continue;
+                }
+
+                if cursor != Some((x, y)) {
+                    write!(out, "\x1b[{};{}H", y + 1, x + 1).unwrap();
+                }
+                if colors != Some((cell.fg, cell.bg)) {
+                    let (fg, bg) = (cell.fg, cell.bg);
+                    write!(out, "\x1b[38;2;{};{};{};48;2;{};{};{}m", fg.r, fg.g, fg.b, bg.r, bg.g, bg.b).unwrap();
+                    colors = Some((fg, bg));
+                }
+                out.push(cell.ch);
+                cursor = Some((x + 1, y));
+            }
+        }
+
+        if colors.is_some() {
+            out.push_str("\x1b[0m");
+        }
+        out
+    }
+
+    // Where (x, y) is in `cells`, for a point that's already known to be inside
+    fn index(&self, x: i32, y: i32) -> usize {
+        y as usize * self.size.width as usize + x as usize
+    }
+}
+
+impl Canvas for CellBuffer {
+    fn size(&self) -> Size {
+        self.size
+    }
+
+    fn set_clip(&mut self, clip: Rect) {
+        self.clip = clip.intersect(Rect::from_size(self.size));
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let visible = rect.intersect(self.clip);
+        for y in visible.y..visible.bottom() {
+            for x in visible.x..visible.right() {
+                self.update(x, y, |cell| {
+                    cell.ch = ' ';
+                    cell.bg = color;
+                });
+            }
+        }
+    }
+
+    // Drawn with box-drawing characters, keeping whatever background is already there
+    fn stroke_rect(&mut self, rect: Rect, color: Color) {
+        if rect.is_empty() {
+            return;
+        }
+        let (right, bottom) = (rect.right() - 1, rect.bottom() - 1);
+        for y in rect.y..=bottom {
+            for x in rect.x..=right {
+                let ch = match (x == rect.x, x == right, y == rect.y, y == bottom) {
+                    _ if rect.width == 1 && rect.height == 1 => '□',
+                    _ if rect.height == 1 => '─',
+                    _ if rect.width == 1 => '│',
+                    (true, _, true, _) => '┌',
+                    (_, true, true, _) => '┐',
+                    (true, _, _, true) => '└',
+                    (_, true, _, true) => '┘',
+                    (_, _, true, _) | (_, _, _, true) => '─',
+                    (true, _, _, _) | (_, true, _, _) => '│',
+                    _ => continue,
+                };
+                self.update(x, y, |cell| {
+                    cell.ch = ch;
+                    cell.fg = color;
+                });
+            }
+        }
+    }
+
+    // Straight lines use box-drawing characters; anything else is dotted in along the same path a Framebuffer takes
+    fn line(&mut self, from: Point, to: Point, color: Color) {
+        let ch = match (from.x == to.x, from.y == to.y) {
+            (_, true) => '─',
+            (true, _) => '│',
+            _ => '•',
+        };
+        let (dx, dy) = ((to.x - from.x).abs(), -(to.y - from.y).abs());
+        let (step_x, step_y) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+        let (mut x, mut y) = (from.x, from.y);
+        let mut error = dx + dy;
+
+        loop {
+            self.update(x, y, |cell| {
+                cell.ch = ch;
+                cell.fg = color;
+            });
+            if x == to.x && y == to.y {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    fn text(&mut self, at: Point, text: &str, color: Color) {
+        for (i, ch) in text.chars().enumerate() {
+            self.update(at.x + i as i32, at.y, |cell| {
+                cell.ch = ch;
+                cell.fg = color;
+            });
+        }
+    }
+
+    fn text_size(&self, text: &str) -> Size {
+        let width = text.chars().count() as i32;
+        Size::new(width, if width == 0 { 0 } else { 1 })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+pub struct Terminal<W: Write> {
+    output: W,
+    size: Size,
+    background: Color,
+    previous: Option<CellBuffer>, // what's on the terminal now
+    entered: bool,                // so dropping the terminal knows to leave
+}
+
+impl<W: Write> Terminal<W> {
+    pub fn new(output: W, size: Size, background: Color) -> Terminal<W> {
+        Terminal { output, size, background, previous: None, entered: false }
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    // The next draw starts from a blank terminal
+    pub fn resize(&mut self, size: Size) {
+        self.size = size;
+        self.previous = None;
+    }
+
+    pub fn output(&self) -> &W {
+        &self.output
+    }
+
+    // Switches to the alternate screen with the cursor hidden and mouse clicks reported, until leave is called or the
+    // terminal is dropped
+    pub fn enter(&mut self) -> io::Result<()> {
+        self.previous = None;
+        self.entered = true;
+        self.output.write_all(b"\x1b[?1049h\x1b[?25l\x1b[?1000h\x1b[?1006h")?;
+        self.output.flush()
+    }
+
+    // Puts everything enter changed back
+    pub fn leave(&mut self) -> io::Result<()> {
+        self.entered = false;
+        self.output.write_all(b"\x1b[?1006l\x1b[?1000l\x1b[0m\x1b[?25h\x1b[?1049l")?;
+        self.output.flush()
+    }
+
+    // Draws `screen`, sending only the cells that changed since the last draw. Returns how many bytes that took. The
+    // screen only redraws the parts of itself that changed too, on top of a copy of the last frame.
+    pub fn draw(&mut self, screen: &mut Screen) -> io::Result<usize> {
+        let buffer = match &self.previous {
+            Some(previous) => {
+                let mut buffer = previous.clone();
+                screen.redraw(&mut buffer, self.background);
+                buffer
+            }
+            None => {
+                let mut buffer = CellBuffer::new(self.size, self.background);
+                screen.run(&mut buffer);
+                buffer
+            }
+        };
+
+        let diff = buffer.diff(self.previous.as_ref());
+        self.output.write_all(diff.as_bytes())?;
+        self.output.flush()?;
+        self.previous = Some(buffer);
+        Ok(diff.len())
+    }
+
+    // The event loop: draws the screen, waits for input, hands each event to the screen and then to `handler`, and
+    // redraws - until `handler` says to quit or the input runs out. An escape sequence split across two reads is kept
+    // until the rest of it arrives.
+    pub fn run<R, F>(&mut self, screen: &mut Screen, mut input: R, mut handler: F) -> io::Result<()>
+    where
+        R: Read,
+        F: FnMut(&Event, &mut Screen) -> Flow,
+    {
+        let mut bytes = [0; 256];
+        let mut pending = Vec::new();
+        loop {
+            self.draw(screen)?;
+
+            let read = input.read(&mut bytes)?;
+            if read == 0 {
+                return Ok(());
+            }
+            pending.extend_from_slice(&bytes[..read]);
+            let complete = complete_input(&pending, read == bytes.len());
+            let events = parse_input(&pending[..complete]);
+            pending.drain(..complete);
+            for event in events {
+                screen.handle_event(&event, self.size);
+                if handler(&event, screen) == Flow::Quit {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+// Turns bytes from a terminal in raw mode into events. Mouse clicks are expected in the SGR format that enter asks for.
+pub fn parse_input(bytes: &[u8]) -> Vec<Event> {
+    let text = String::from_utf8_lossy(bytes);
+    let chars: Vec<char> = text.chars().collect();
+    let mut events = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (event, length) = match chars[i] {
+            '\x1b' if chars.get(i + 1) == Some(&'[') => match escape_sequence(&chars[i + 2..]) {
+                Some((event, length)) => (event, length + 2),
+                None => (None, 1),
+            },
+            '\x1b' => (Some(Event::Key(Key::Escape)), 1),
+            '\r' | '\n' => (Some(Event::Key(Key::Enter)), 1),
+            '\t' => (Some(Event::Key(Key::Tab)), 1),
+            '\x7f' | '\x08' => (Some(Event::Key(Key::Backspace)), 1),
+            c @ '\x01'..='\x1a' => (Some(Event::Key(Key::Ctrl((b'a' + c as u8 - 1) as char))), 1),
+            c if c.is_control() => (None, 1),
+            c => (Some(Event::Key(Key::Char(c))), 1),
+        };
+        events.extend(event);
+        i += length;
+    }
+
+    events
+}
+
+// How much of `bytes` can be parsed now, leaving out an escape sequence at the end that hasn't finished arriving. A
+// lone ESC at the end is the Escape key, unless the read filled the buffer (`more_waiting`), when it's more likely the
+// start of a sequence. Anything too long to be a sequence is let through to be parsed as it is.
+fn complete_input(bytes: &[u8], more_waiting: bool) -> usize {
+    let start = match bytes.iter().rposition(|&b| b == 0x1b) {
+        Some(start) if bytes.len() - start <= 32 => start,
+        _ => return bytes.len(),
+    };
+    let unfinished = match &bytes[start + 1..] {
+        [] => more_waiting,
+        [b'[', rest @ ..] => !rest.iter().any(|b| (b'@'..=b'~').contains(b)),
+        _ => false,
+    };
+    if unfinished {
+        start
+    } else {
+        bytes.len()
+    }
+}
+
+// The event for the escape sequence that follows "ESC [", and how long it is. Sequences that are understood but
+// don't mean anything here give None as the event; ones that aren't understood at all give None altogether.
+fn escape_sequence(chars: &[char]) -> Option<(Option<Event>, usize)> {
+    let end = chars.iter().position(|c| ('@'..='~').contains(c))?;
+    let params: String = chars[..end].iter().collect();
+
+    let key = |key| Some(Event::Key(key));
+    let event = match (params.as_str(), chars[end]) {
+        ("", 'A') => key(Key::Up),
+        ("", 'B') => key(Key::Down),
+        ("", 'C') => key(Key::Right),
+        ("", 'D') => key(Key::Left),
+        ("", 'H') | ("1", '~') | ("7", '~') => key(Key::Home),
+        ("", 'F') | ("4", '~') | ("8", '~') => key(Key::End),
+        ("3", '~') => key(Key::Delete),
+        ("", 'Z') => key(Key::BackTab),
+        (mouse, 'M') | (mouse, 'm') if mouse.starts_with('<') => {
+            let numbers: Vec<i32> = mouse[1..].split(';').filter_map(|n| n.parse().ok()).collect();
+            match numbers[..] {
+                // only the left button; terminals count from 1
+                [0, x, y] => {
+                    let point = Point::new(x - 1, y - 1);
+                    Some(if chars[end] == 'M' { Event::MouseDown(point) } else { Event::MouseUp(point) })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    Some((event, end + 1))
+}
+
+impl<W: Write> Drop for Terminal<W> {
+    // so an error or a panic in the middle of run doesn't leave the alternate screen up with the cursor hidden
+    fn drop(&mut self) {
+        if self.entered {
+            let _ = self.leave();
+        }
+    }
+}
+
+// Puts the terminal into raw mode - keys arrive one at a time and aren't echoed - until it's dropped
+pub struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<RawMode> {
+        let saved = stty(&["-g"])?.trim().to_string();
+        stty(&["raw", "-echo"])?;
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+// How big the terminal on stdin is, if it is one
+pub fn terminal_size() -> Option<Size> {
+    let output = stty(&["size"]).ok()?;
+    let mut numbers = output.split_whitespace().map(|n| n.parse::<i32>());
+    match (numbers.next(), numbers.next()) {
+        (Some(Ok(rows)), Some(Ok(columns))) => Some(Size::new(columns, rows)),
+        _ => None,
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Button, TextField};
+    use std::cell::Cell as Counter;
+    use std::rc::Rc;
+
+    #[test]
+    fn it_draws_components_in_cells() {
+        let field = TextField::new(8, 1).with_text("hi");
+        let mut screen = Screen::from_components(vec![Box::new(Button::new(8, 3, "OK")), Box::new(field)]);
+        let mut buffer = CellBuffer::new(Size::new(9, 4), Color::WHITE);
+        screen.run(&mut buffer);
+
+        assert_eq!(buffer.to_text(), "┌──────┐ \n│  OK  │ \n└──────┘ \n──hi──── \n");
+        assert_eq!(buffer.cell(3, 1), Some(Cell { ch: 'O', fg: Color::BLACK, bg: Color::GREY }));
+    }
+
+    #[test]
+    fn negative_sizes_make_empty_cell_buffers() {
+        let buffer = CellBuffer::new(Size::new(5, -1), Color::WHITE);
+
+        assert_eq!(buffer.cell(0, 0), None);
+        assert_eq!(buffer.to_text(), "");
+    }
+
+    #[test]
+    fn redraws_only_send_changed_cells() {
+        let mut first = CellBuffer::new(Size::new(4, 2), Color::WHITE);
+        first.text(Point::new(0, 0), "ab", Color::BLACK);
+        let mut second = first.clone();
+        second.text(Point::new(1, 1), "xy", Color::BLACK);
+
+        assert!(first.diff(None).starts_with("\x1b[0m\x1b[2J\x1b[1;1H"));
+        assert_eq!(second.diff(Some(&first)), "\x1b[2;2H\x1b[38;2;0;0;0;48;2;255;255;255mxy\x1b[0m");
+        assert_eq!(second.diff(Some(&second)), "");
+    }
+
+    #[test]
+    fn it_parses_keys_and_mouse_clicks() {
+        assert_eq!(
+            parse_input(b"a\xc3\xa9\r\t\x7f\x03\x1b[A\x1b[Z\x1b[3~\x1b[<0;5;2M\x1b[<0;5;2m\x1b[<2;1;1M\x1b"),
+            vec![
+                Event::Key(Key::Char('a')),
+                Event::Key(Key::Char('é')),
+                Event::Key(Key::Enter),
+                Event::Key(Key::Tab),
+                Event::Key(Key::Backspace),
+                Event::Key(Key::Ctrl('c')),
+                Event::Key(Key::Up),
+                Event::Key(Key::BackTab),
+                Event::Key(Key::Delete),
+                Event::MouseDown(Point::new(4, 1)),
+                Event::MouseUp(Point::new(4, 1)),
+                Event::Key(Key::Escape),
+            ]
+        );
+    }
+
+    struct Chunks<'a>(std::vec::IntoIter<&'a [u8]>);
+
+    impl Read for Chunks<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.next() {
+                Some(chunk) => {
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    Ok(chunk.len())
+                }
+                None => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn the_event_loop_feeds_input_to_the_screen_until_told_to_quit() {
+        let clicks = Rc::new(Counter::new(0));
+        let counter = Rc::clone(&clicks);
+        let button = Button::new(6, 3, "Go").on_click(move || counter.set(counter.get() + 1));
+        let mut screen = Screen::from_components(vec![Box::new(TextField::new(6, 1)), Box::new(button)]);
+        let mut terminal = Terminal::new(Vec::new(), Size::new(6, 4), Color::WHITE);
+
+        // each read gets the next chunk, like keys arriving one burst at a time
+        let chunks: Vec<&[u8]> = vec![b"\tab", b"\t\r", b"\x1b[<0;2", b";3M", b"\x1b[", b"<0;2;3m", b"\x03never read"];
+        let input = Chunks(chunks.into_iter());
+        let quit_on_ctrl_c = |event: &Event, _: &mut Screen| match event {
+            Event::Key(Key::Ctrl('c')) => Flow::Quit,
+            _ => Flow::Continue,
+        };
+        terminal.run(&mut screen, input, quit_on_ctrl_c).unwrap();
+
+        assert_eq!(clicks.get(), 2);
+        let tree = screen.tree();
+        assert_eq!(tree.focused(), Some(tree.children(tree.root())[1]));
+        let output = String::from_utf8(terminal.output().clone()).unwrap();
+        assert!(output.contains("ab"));
+        // one full draw to start with, and everything after it is a partial one
+        assert_eq!(output.matches("\x1b[2J").count(), 1);
+    }
+
+    #[test]
+    fn unfinished_escape_sequences_wait_for_the_rest() {
+        assert_eq!(complete_input(b"ab\x1b[<0;2", false), 2);
+        assert_eq!(complete_input(b"ab\x1b[<0;2;3M", false), 11);
+        assert_eq!(complete_input(b"ab\x1b", false), 3); // the Escape key
+        assert_eq!(complete_input(b"ab\x1b", true), 2);
+        assert_eq!(complete_input(b"\x1bx", false), 2);
+    }
+
+    #[test]
+    fn dropping_an_entered_terminal_leaves_it() {
+        let mut output = Vec::new();
+        {
+            let mut terminal = Terminal::new(&mut output, Size::new(4, 2), Color::WHITE);
+            terminal.enter().unwrap();
+        }
+        assert!(output.ends_with(b"\x1b[?25h\x1b[?1049l"));
+
+        let mut output = Vec::new();
+        {
+            let mut terminal = Terminal::new(&mut output, Size::new(4, 2), Color::WHITE);
+            terminal.enter().unwrap();
+            terminal.leave().unwrap();
+        }
+        assert_eq!(String::from_utf8(output).unwrap().matches("\x1b[?1049l").count(), 1);
+    }
+}
@@ -0,0 +1,500 @@
+// The retained widget tree a Screen keeps its components in. Containers lay their children out with a Layout, and
+// widgets are the components themselves - anything that implements Draw, including a Stack, which the tree treats as
+// a single widget. Every node knows its parent and children, where it was last laid out and whether it's dirty.
+//
+// Dirty nodes are the ones that need drawing again: a widget that used an event, one handed out through widget_mut,
+// anything that moved, and containers that lost a child. redraw only draws the dirty subtrees, clearing each one to
+// the background first, so a canvas kept from the last frame ends up looking as if the whole screen was drawn again.
+// That relies on widgets staying inside their rects and not overlapping their siblings, which layouts never do.
+//
+// Nodes live in a Vec and a WidgetId is an index into it. A removed node leaves a hole rather than moving the others,
+// so ids held on to elsewhere stay good (the ids of removed nodes just stop finding anything).
+
+use crate::canvas::{Canvas, Color, Context};
+use crate::event::{self, Event};
+use crate::geometry::{Rect, Size};
+use crate::layout::{Align, Item, Layout, Sizing};
+use crate::Draw;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WidgetId(usize);
+
+enum Kind {
+    Container(Layout),
+    Widget(Box<dyn Draw>),
+}
+
+struct Node {
+    kind: Kind,
+    parent: Option<WidgetId>,
+    children: Vec<WidgetId>,
+    sizing: Sizing,
+    align: Option<Align>,
+    rect: Rect, // where the last layout put it, in screen coordinates
+    dirty: bool,
+}
+
+pub struct WidgetTree {
+    nodes: Vec<Option<Node>>,
+    root: WidgetId,
+    laid_out: Option<Size>, // the size the rects were worked out for; None when they need working out again
+}
+
+impl WidgetTree {
+    // A tree with just a root container, which fills the screen and lays its children out with `layout`
+    pub fn new(layout: Layout) -> WidgetTree {
+        let root = Node {
+            kind: Kind::Container(layout),
+            parent: None,
+            children: Vec::new(),
+            sizing: Sizing::Flex(1),
+            align: None,
+            rect: Rect::new(0, 0, 0, 0),
+            dirty: true,
+        };
+        WidgetTree { nodes: vec![Some(root)], root: WidgetId(0), laid_out: None }
+    }
+
+    pub fn root(&self) -> WidgetId {
+        self.root
+    }
+
+    // Adds a container to the end of `parent`'s children. Panics if `parent` isn't a container in this tree.
+    pub fn add_container(&mut self, parent: WidgetId, layout: Layout, sizing: Sizing) -> WidgetId {
+        self.insert(parent, Kind::Container(layout), sizing)
+    }
+
+    // Adds a widget to the end of `parent`'s children. Panics if `parent` isn't a container in this tree.
+    pub fn add_widget(&mut self, parent: WidgetId, widget: Box<dyn Draw>, sizing: Sizing) -> WidgetId {
+        self.insert(parent, Kind::Widget(widget), sizing)
+    }
+
+    fn insert(&mut self, parent: WidgetId, kind: Kind, sizing: Sizing) -> WidgetId {
+        match self.node(parent).map(|node| &node.kind) {
+            Some(Kind::Container(_)) => {}
+            Some(Kind::Widget(_)) => panic!("{:?} is a widget, and only containers can have children", parent),
+            None => panic!("{:?} isn't in the tree", parent),
+        }
+
+        let id = WidgetId(self.nodes.len());
+        let rect = Rect::new(0, 0, 0, 0);
+        let node = Node { kind, parent: Some(parent), children: Vec::new(), sizing, align: None, rect, dirty: true };
+        self.nodes.push(Some(node));
+        self.node_mut(parent).unwrap().children.push(id);
+        self.laid_out = None;
+        id
+    }
+
+    // Lines `id` up differently to the rest of its parent's children
+    pub fn set_align(&mut self, id: WidgetId, align: Align) {
+        if let Some(node) = self.node_mut(id) {
+            node.align = Some(align);
+            self.laid_out = None;
+        }
+    }
+
+    // Takes `id` and everything under it out of the tree, handing back the widgets that were in it. Panics if asked to
+    // remove the root.
+    pub fn remove(&mut self, id: WidgetId) -> Vec<Box<dyn Draw>> {
+        assert!(id != self.root, "the root of a widget tree can't be removed");
+        let parent = match self.node(id) {
+            Some(node) => node.parent.unwrap(),
+            None => return Vec::new(),
+        };
+        let parent = self.node_mut(parent).unwrap();
+        parent.children.retain(|&child| child != id);
+        parent.dirty = true; // to clear away what was drawn where `id` was
+        self.laid_out = None;
+
+        let mut widgets = Vec::new();
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let node = self.nodes[id.0].take().unwrap();
+            stack.extend(node.children);
+            if let Kind::Widget(widget) = node.kind {
+                widgets.push(widget);
+            }
+        }
+        widgets
+    }
+
+    pub fn contains(&self, id: WidgetId) -> bool {
+        self.node(id).is_some()
+    }
+
+    pub fn parent(&self, id: WidgetId) -> Option<WidgetId> {
+        self.node(id).and_then(|node| node.parent)
+    }
+
+    pub fn children(&self, id: WidgetId) -> &[WidgetId] {
+        self.node(id).map_or(&[], |node| &node.children)
+    }
+
+    // Every node under `id` (but not `id` itself), parents before their children, in drawing order
+    pub fn descendants(&self, id: WidgetId) -> Vec<WidgetId> {
+        let mut found = Vec::new();
+        let mut stack: Vec<WidgetId> = self.children(id).iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            found.push(id);
+            stack.extend(self.children(id).iter().rev());
+        }
+        found
+    }
+
+    // How many nodes there are, counting the root
+    pub fn len(&self) -> usize {
+        self.nodes.iter().filter(|node| node.is_some()).count()
+    }
+
+    // Never true, since there's always the root
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The component at `id`, if it's a widget
+    pub fn widget(&self, id: WidgetId) -> Option<&dyn Draw> {
+        match self.node(id).map(|node| &node.kind) {
+            Some(Kind::Widget(widget)) => Some(widget.as_ref()),
+            _ => None,
+        }
+    }
+
+    // The component at `id` to change, if it's a widget. It's marked dirty and laid out again, since there's no
+    // telling what's being changed about it.
+    pub fn widget_mut(&mut self, id: WidgetId) -> Option<&mut dyn Draw> {
+        self.laid_out = None;
+        let node = self.nodes.get_mut(id.0).and_then(|node| node.as_mut())?;
+        node.dirty = true;
+        match &mut node.kind {
+            Kind::Widget(widget) => Some(widget.as_mut()),
+            Kind::Container(_) => None,
+        }
+    }
+
+    // The layout of the container at `id` to change; the container gets laid out and drawn again
+    pub fn layout_mut(&mut self, id: WidgetId) -> Option<&mut Layout> {
+        self.laid_out = None;
+        let node = self.nodes.get_mut(id.0).and_then(|node| node.as_mut())?;
+        node.dirty = true;
+        match &mut node.kind {
+            Kind::Container(layout) => Some(layout),
+            Kind::Widget(_) => None,
+        }
+    }
+
+    pub fn mark_dirty(&mut self, id: WidgetId) {
+        if let Some(node) = self.node_mut(id) {
+            node.dirty = true;
+        }
+    }
+
+    pub fn is_dirty(&self, id: WidgetId) -> bool {
+        self.node(id).is_some_and(|node| node.dirty)
+    }
+
+    // Where `id` was last laid out, in screen coordinates
+    pub fn rect(&self, id: WidgetId) -> Option<Rect> {
+        self.node(id).map(|node| node.rect)
+    }
+
+    // The widget with the focus, if any does
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.widgets().into_iter().find(|&id| self.widget(id).is_some_and(|widget| widget.is_focused()))
+    }
+
+    // The widgets, in drawing order
+    fn widgets(&self) -> Vec<WidgetId> {
+        self.descendants(self.root).into_iter().filter(|&id| self.widget(id).is_some()).collect()
+    }
+
+    // Works out where everything goes on a screen of `size`. Only does anything if something has changed since last
+    // time; whatever ends up somewhere new is marked dirty, along with its parent so that the old spot gets cleared.
+    pub fn layout(&mut self, size: Size) {
+        if self.laid_out == Some(size) {
+            return;
+        }
+        self.laid_out = Some(size);
+
+        let root = self.node_mut(self.root).unwrap();
+        if root.rect != Rect::from_size(size) {
+            root.rect = Rect::from_size(size);
+            root.dirty = true;
+        }
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            let node = self.node(id).unwrap();
+            let layout = match &node.kind {
+                Kind::Container(layout) => layout,
+                Kind::Widget(_) => continue,
+            };
+            let items: Vec<Item> = node.children.iter().map(|&child| self.item(child)).collect();
+            let rects = layout.arrange(node.rect.size(), &items);
+            let (origin, children) = (node.rect, node.children.clone());
+
+            let mut moved = false;
+            for (child, rect) in children.into_iter().zip(rects) {
+                let node = self.node_mut(child).unwrap();
+                let rect = rect.translate(origin.origin());
+                if node.rect != rect {
+                    node.rect = rect;
+                    node.dirty = true;
+                    moved = true;
+                }
+                stack.push(child);
+            }
+            if moved {
+                self.mark_dirty(id);
+            }
+        }
+    }
+
+    fn item(&self, id: WidgetId) -> Item {
+        let node = self.node(id).unwrap();
+        Item { preferred: self.preferred_size(id), sizing: node.sizing, align: node.align }
+    }
+
+    // How much room `id` would like: a widget says so itself, and a container wants enough for its children
+    pub fn preferred_size(&self, id: WidgetId) -> Size {
+        match self.node(id).map(|node| &node.kind) {
+            Some(Kind::Widget(widget)) => widget.preferred_size(),
+            Some(Kind::Container(layout)) => {
+                let items: Vec<Item> = self.children(id).iter().map(|&child| self.item(child)).collect();
+                layout.preferred_size(&items)
+            }
+            None => Size::new(0, 0),
+        }
+    }
+
+    // Draws the whole tree on `canvas`, which leaves nothing dirty
+    pub fn draw(&mut self, canvas: &mut dyn Canvas) {
+        self.layout(canvas.size());
+        self.draw_subtree(self.root, &mut Context::new(canvas));
+        self.clean();
+    }
+
+    // Draws only the dirty parts of the tree, on a canvas that still has the last frame on it, clearing each part to
+    // `background` first. Returns how many widgets that drew.
+    pub fn redraw(&mut self, canvas: &mut dyn Canvas, background: Color) -> usize {
+        self.layout(canvas.size());
+
+        // the dirty nodes nearest the root - anything under those gets drawn with them anyway
+        let mut dirty = Vec::new();
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            if self.is_dirty(id) {
+                dirty.push(id);
+            } else {
+                stack.extend(self.children(id).iter().rev());
+            }
+        }
+
+        let mut ctx = Context::new(canvas);
+        let mut drawn = 0;
+        for id in dirty {
+            ctx.fill_rect(self.node(id).unwrap().rect, background);
+            drawn += self.draw_subtree(id, &mut ctx);
+        }
+        self.clean();
+        drawn
+    }
+
+    fn draw_subtree(&self, id: WidgetId, ctx: &mut Context) -> usize {
+        let node = self.node(id).unwrap();
+        match &node.kind {
+            Kind::Widget(widget) => {
+                widget.draw(&mut ctx.child(node.rect));
+                1
+            }
+            Kind::Container(_) => node.children.iter().map(|&child| self.draw_subtree(child, ctx)).sum(),
+        }
+    }
+
+    fn clean(&mut self) {
+        for node in self.nodes.iter_mut().flatten() {
+            node.dirty = false;
+        }
+    }
+
+    // Passes `event` (with screen coordinates) on to the widget it's for, on a screen of `size`: the topmost widget
+    // under the pointer for a MouseDown, every widget for a MouseUp, the focused one for keys. The widget is marked
+    // dirty if it used the event, and so is any widget that loses the focus because of it.
+    pub fn handle_event(&mut self, event: &Event, size: Size) -> bool {
+        self.layout(size);
+        // a MouseUp goes to every widget, as event::dispatch does, since the one the mouse went down on may not be
+        // under the pointer any more
+        if let Event::MouseUp(_) = event {
+            let mut handled = false;
+            for id in self.widgets() {
+                let node = self.node_mut(id).unwrap();
+                let rect = node.rect;
+                if let Kind::Widget(widget) = &mut node.kind {
+                    if widget.handle_event(&event.relative_to(rect), rect.size()) {
+                        node.dirty = true;
+                        handled = true;
+                    }
+                }
+            }
+            return handled;
+        }
+
+        let target = match event.position() {
+            Some(point) => {
+                let mut id = self.root;
+                let under = |tree: &WidgetTree, id| {
+                    let mut children = tree.children(id).iter().rev().copied();
+                    children.find(|&child| tree.node(child).unwrap().rect.contains(point))
+                };
+                while let Some(child) = under(self, id) {
+                    id = child;
+                }
+                Some(id).filter(|&id| self.widget(id).is_some())
+            }
+            None => self.focused(),
+        };
+        let target = match target {
+            Some(target) => target,
+            None => return false,
+        };
+
+        let node = self.node_mut(target).unwrap();
+        let rect = node.rect;
+        let widget = match &mut node.kind {
+            Kind::Widget(widget) => widget,
+            Kind::Container(_) => unreachable!(),
+        };
+        let handled = widget.handle_event(&event.relative_to(rect), rect.size());
+        let focused = widget.is_focused();
+        if handled {
+            node.dirty = true;
+        }
+
+        if focused {
+            for id in self.widgets() {
+                if id != target && self.widget(id).is_some_and(|widget| widget.is_focused()) {
+                    self.widget_mut(id).unwrap().blur();
+                }
+            }
+        }
+        handled
+    }
+
+    // Moves the focus to the next focus stop (the previous, if not `forward`), going through the widgets in drawing
+    // order. Returns false when the focus has gone past the last stop, as event::focus_next does.
+    pub fn focus_next(&mut self, forward: bool) -> bool {
+        let order = self.widgets();
+        let before = self.focused();
+
+        let mut nodes: Vec<Option<&mut Node>> = self.nodes.iter_mut().map(|node| node.as_mut()).collect();
+        let mut widgets: Vec<&mut dyn Draw> = Vec::new();
+        for id in &order {
+            if let Some(Node { kind: Kind::Widget(widget), .. }) = nodes[id.0].take() {
+                widgets.push(widget.as_mut());
+            }
+        }
+        let moved = event::focus_next(&mut widgets, forward);
+
+        // where the focus was and where it is now both look different. The focus can also move around inside a
+        // widget like a Stack, so it counts too.
+        for id in before.into_iter().chain(self.focused()) {
+            self.mark_dirty(id);
+        }
+        moved
+    }
+
+    fn node(&self, id: WidgetId) -> Option<&Node> {
+        self.nodes.get(id.0).and_then(|node| node.as_ref())
+    }
+
+    fn node_mut(&mut self, id: WidgetId) -> Option<&mut Node> {
+        self.nodes.get_mut(id.0).and_then(|node| node.as_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Direction;
+    use crate::{Button, Framebuffer, Key, Point, TextField};
+
+    // A column of text fields, with a row of two buttons in the middle
+    fn tree() -> (WidgetTree, Vec<WidgetId>) {
+        let mut tree = WidgetTree::new(Layout::new(Direction::Vertical));
+        let root = tree.root();
+        let first = tree.add_widget(root, Box::new(TextField::new(30, 11)), Sizing::Preferred);
+        let row = tree.add_container(root, Layout::new(Direction::Horizontal), Sizing::Preferred);
+        let ok = tree.add_widget(row, Box::new(Button::new(20, 11, "OK")), Sizing::Preferred);
+        let cancel = tree.add_widget(row, Box::new(Button::new(20, 11, "NO")), Sizing::Preferred);
+        let last = tree.add_widget(root, Box::new(TextField::new(30, 11)), Sizing::Preferred);
+        (tree, vec![first, row, ok, cancel, last])
+    }
+
+    // What drawing everything from scratch gives (compared as PPMs, since framebuffers remember their last clip too)
+    fn full_render(tree: &mut WidgetTree) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(50, 40, Color::WHITE);
+        tree.mark_dirty(tree.root());
+        tree.draw(&mut framebuffer);
+        framebuffer
+    }
+
+    #[test]
+    fn nodes_know_their_parents_and_children() {
+        let (mut tree, ids) = tree();
+        let (first, row, ok, cancel, last) = (ids[0], ids[1], ids[2], ids[3], ids[4]);
+
+        assert_eq!(tree.children(tree.root()), &[first, row, last]);
+        assert_eq!(tree.parent(ok), Some(row));
+        assert_eq!(tree.parent(tree.root()), None);
+        assert_eq!(tree.descendants(tree.root()), vec![first, row, ok, cancel, last]);
+        assert_eq!(tree.preferred_size(row), Size::new(40, 11));
+
+        tree.layout(Size::new(50, 40));
+        assert_eq!(tree.rect(cancel), Some(Rect::new(20, 11, 20, 11)));
+
+        assert_eq!(tree.remove(row).len(), 2);
+        assert!(!tree.contains(ok));
+        assert_eq!(tree.children(tree.root()), &[first, last]);
+        assert_eq!(tree.len(), 3);
+        tree.layout(Size::new(50, 40));
+        assert_eq!(tree.rect(last), Some(Rect::new(0, 11, 30, 11)));
+    }
+
+    #[test]
+    #[should_panic(expected = "only containers can have children")]
+    fn widgets_cant_have_children() {
+        let (mut tree, ids) = tree();
+        tree.add_widget(ids[0], Box::new(Button::new(1, 1, "")), Sizing::Preferred);
+    }
+
+    #[test]
+    fn only_dirty_subtrees_are_redrawn() {
+        let (mut tree, ids) = tree();
+        let (first, ok, cancel, last) = (ids[0], ids[2], ids[3], ids[4]);
+        let mut framebuffer = Framebuffer::new(50, 40, Color::WHITE);
+
+        assert_eq!(tree.redraw(&mut framebuffer, Color::WHITE), 4);
+        assert_eq!(tree.redraw(&mut framebuffer, Color::WHITE), 0);
+
+        // typing into the focused field only touches that field
+        tree.handle_event(&Event::MouseDown(Point::new(1, 1)), Size::new(50, 40));
+        assert!(tree.is_dirty(first) && !tree.is_dirty(ok));
+        assert_eq!(tree.redraw(&mut framebuffer, Color::WHITE), 1);
+        tree.handle_event(&Event::Key(Key::Char('a')), Size::new(50, 40));
+        assert_eq!(tree.redraw(&mut framebuffer, Color::WHITE), 1);
+        assert_eq!(framebuffer.to_ppm(), full_render(&mut tree).to_ppm());
+
+        // moving the focus redraws where it was and where it went
+        tree.focus_next(true);
+        assert_eq!(tree.focused(), Some(ok));
+        assert!(tree.is_dirty(first) && tree.is_dirty(ok) && !tree.is_dirty(cancel));
+        assert_eq!(tree.redraw(&mut framebuffer, Color::WHITE), 2);
+        assert_eq!(framebuffer.to_ppm(), full_render(&mut tree).to_ppm());
+
+        // and taking something out redraws its parent, so that it's gone from the screen too
+        tree.remove(cancel);
+        assert_eq!(tree.redraw(&mut framebuffer, Color::WHITE), 3);
+        assert_eq!(framebuffer.to_ppm(), full_render(&mut tree).to_ppm());
+
+        tree.widget_mut(last).unwrap().blur();
+        assert_eq!(tree.redraw(&mut framebuffer, Color::WHITE), 1);
+    }
+}
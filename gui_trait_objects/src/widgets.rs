@@ -0,0 +1,379 @@
+// Interactive widgets. Each one keeps its own state and changes it in response to events, then tells whoever is
+// interested through a callback - the screen owns the widgets as trait objects, so callbacks are how the rest of the
+// program hears about a click or an edit.
+
+use crate::canvas::{Color, Context};
+use crate::event::{Event, Key};
+use crate::geometry::{Point, Rect, Size};
+use crate::Draw;
+
+type OnChange = Box<dyn FnMut(&str)>;
+type OnSelect = Box<dyn FnMut(usize, &str)>;
+
+pub struct TextField {
+    pub width: i32,
+    pub height: i32,
+    text: String,
+    cursor: usize, // in chars, not bytes
+    focused: bool,
+    on_change: Option<OnChange>,
+}
+
+impl TextField {
+    pub fn new(width: i32, height: i32) -> TextField {
+        TextField { width, height, text: String::new(), cursor: 0, focused: false, on_change: None }
+    }
+
+    pub fn with_text(mut self, text: &str) -> TextField {
+        self.text = text.to_string();
+        self.cursor = text.chars().count();
+        self
+    }
+
+    pub fn on_change<F: FnMut(&str) + 'static>(mut self, f: F) -> TextField {
+        self.on_change = Some(Box::new(f));
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    // The byte index of the cursor
+    fn byte_index(&self, chars: usize) -> usize {
+        self.text.char_indices().nth(chars).map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn edit(&mut self, key: Key) -> bool {
+        let len = self.text.chars().count();
+        match key {
+            Key::Char(c) => {
+                let at = self.byte_index(self.cursor);
+                self.text.insert(at, c);
+                self.cursor += 1;
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let at = self.byte_index(self.cursor);
+                self.text.remove(at);
+            }
+            Key::Delete if self.cursor < len => {
+                let at = self.byte_index(self.cursor);
+                self.text.remove(at);
+            }
+            Key::Left if self.cursor > 0 => self.cursor -= 1,
+            Key::Right if self.cursor < len => self.cursor += 1,
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = len,
+            _ => return false,
+        }
+
+        let changed = matches!(key, Key::Char(_) | Key::Backspace | Key::Delete);
+        if let (true, Some(f)) = (changed, &mut self.on_change) {
+            f(&self.text);
+        }
+        true
+    }
+}
+
+impl Draw for TextField {
+    fn draw(&self, ctx: &mut Context) {
+        let bounds = ctx.bounds();
+        ctx.fill_rect(bounds, Color::WHITE);
+        ctx.stroke_rect(bounds, if self.focused { Color::BLUE } else { Color::DARK_GREY });
+
+        let text_height = ctx.text_size(&self.text).height;
+        let top = (bounds.height - text_height) / 2;
+        ctx.text(Point::new(2, top), &self.text, Color::BLACK);
+
+        if self.focused {
+            // measuring with one more character counts the gap after the text, on canvases that leave one
+            let before: String = self.text.chars().take(self.cursor).chain(Some('_')).collect();
+            let x = 2 + ctx.text_size(&before).width - ctx.text_size("_").width;
+            ctx.line(Point::new(x, 1), Point::new(x, bounds.height - 2), Color::BLACK);
+        }
+    }
+
+    fn preferred_size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    fn handle_event(&mut self, event: &Event, _size: Size) -> bool {
+        match event {
+            Event::MouseDown(_) => {
+                self.focused = true;
+                self.cursor = self.text.chars().count();
+                true
+            }
+            Event::Key(key) if self.focused => self.edit(*key),
+            _ => false,
+        }
+    }
+
+    fn focus_next(&mut self, _forward: bool) -> bool {
+        self.focused = !self.focused;
+        self.focused
+    }
+
+    fn blur(&mut self) {
+        self.focused = false;
+    }
+
+    fn is_focused(&self) -> bool {
+        self.focused
+    }
+}
+
+pub struct Checkbox {
+    pub width: i32,
+    pub height: i32,
+    pub label: String,
+    checked: bool,
+    focused: bool,
+    on_toggle: Option<Box<dyn FnMut(bool)>>,
+}
+
+impl Checkbox {
+    pub fn new(width: i32, height: i32, label: &str) -> Checkbox {
+        Checkbox { width, height, label: label.to_string(), checked: false, focused: false, on_toggle: None }
+    }
+
+    pub fn with_checked(mut self, checked: bool) -> Checkbox {
+        self.checked = checked;
+        self
+    }
+
+    pub fn on_toggle<F: FnMut(bool) + 'static>(mut self, f: F) -> Checkbox {
+        self.on_toggle = Some(Box::new(f));
+        self
+    }
+
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
+
+    pub fn toggle(&mut self) {
+        self.checked = !self.checked;
+        if let Some(f) = &mut self.on_toggle {
+            f(self.checked);
+        }
+    }
+}
+
+impl Draw for Checkbox {
+    fn draw(&self, ctx: &mut Context) {
+        let bounds = ctx.bounds();
+        let side = bounds.height.min(bounds.width);
+        let check = Rect::new(0, (bounds.height - side) / 2, side, side);
+
+        ctx.fill_rect(check, Color::WHITE);
+        ctx.stroke_rect(check, if self.focused { Color::BLUE } else { Color::BLACK });
+        if self.checked && check.inset(2).is_empty() {
+            // too small for a cross
+            ctx.fill_rect(check, Color::BLACK);
+        } else if self.checked {
+            let inner = check.inset(2);
+            ctx.line(inner.origin(), Point::new(inner.right() - 1, inner.bottom() - 1), Color::BLACK);
+            ctx.line(Point::new(inner.right() - 1, inner.y), Point::new(inner.x, inner.bottom() - 1), Color::BLACK);
+        }
+
+        let text_height = ctx.text_size(&self.label).height;
+        ctx.text(Point::new(side + 3, (bounds.height - text_height) / 2), &self.label, Color::BLACK);
+    }
+
+    fn preferred_size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    fn handle_event(&mut self, event: &Event, _size: Size) -> bool {
+        match event {
+            Event::MouseDown(_) => {
+                self.focused = true;
+                self.toggle();
+                true
+            }
+            Event::Key(Key::Char(' ')) | Event::Key(Key::Enter) if self.focused => {
+                self.toggle();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn focus_next(&mut self, _forward: bool) -> bool {
+        self.focused = !self.focused;
+        self.focused
+    }
+
+    fn blur(&mut self) {
+        self.focused = false;
+    }
+
+    fn is_focused(&self) -> bool {
+        self.focused
+    }
+}
+
+// A list of options with (at most) one selected, all of them showing at once: click one, or move with Up and Down.
+pub struct SelectBox {
+    pub width: i32,
+    pub height: i32,
+    pub options: Vec<String>,
+    selected: Option<usize>,
+    focused: bool,
+    on_select: Option<OnSelect>,
+}
+
+impl SelectBox {
+    pub fn new(width: i32, height: i32, options: Vec<String>) -> SelectBox {
+        SelectBox { width, height, options, selected: None, focused: false, on_select: None }
+    }
+
+    pub fn on_select<F: FnMut(usize, &str) + 'static>(mut self, f: F) -> SelectBox {
+        self.on_select = Some(Box::new(f));
+        self
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.selected.map(|i| self.options[i].as_str())
+    }
+
+    // Does nothing for an index past the last option
+    pub fn select(&mut self, index: usize) {
+        if index >= self.options.len() || self.selected == Some(index) {
+            return;
+        }
+        self.selected = Some(index);
+        if let Some(f) = &mut self.on_select {
+            f(index, &self.options[index]);
+        }
+    }
+
+    fn row_height(&self, size: Size) -> i32 {
+        (size.height / self.options.len().max(1) as i32).max(1)
+    }
+}
+
+impl Draw for SelectBox {
+    fn draw(&self, ctx: &mut Context) {
+        let bounds = ctx.bounds();
+        ctx.fill_rect(bounds, Color::WHITE);
+
+        let row_height = self.row_height(bounds.size());
+        for (i, option) in self.options.iter().enumerate() {
+            let row = Rect::new(0, i as i32 * row_height, bounds.width, row_height);
+            let color = if self.selected == Some(i) {
+                ctx.fill_rect(row, Color::BLUE);
+                Color::WHITE
+            } else {
+                Color::BLACK
+            };
+            let text_height = ctx.text_size(option).height;
+            ctx.text(Point::new(2, row.y + (row_height - text_height) / 2), option, color);
+        }
+
+        ctx.stroke_rect(bounds, if self.focused { Color::BLUE } else { Color::BLACK });
+    }
+
+    fn preferred_size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    fn handle_event(&mut self, event: &Event, size: Size) -> bool {
+        match event {
+            Event::MouseDown(point) => {
+                self.focused = true;
+                self.select((point.y / self.row_height(size)) as usize);
+                true
+            }
+            Event::Key(Key::Down) if self.focused => {
+                self.select(self.selected.map_or(0, |i| i + 1));
+                true
+            }
+            Event::Key(Key::Up) if self.focused => {
+                self.select(self.selected.map_or(self.options.len().saturating_sub(1), |i| i.saturating_sub(1)));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn focus_next(&mut self, _forward: bool) -> bool {
+        self.focused = !self.focused;
+        self.focused
+    }
+
+    fn blur(&mut self) {
+        self.focused = false;
+    }
+
+    fn is_focused(&self) -> bool {
+        self.focused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn keys(widget: &mut dyn Draw, keys: &[Key]) {
+        for key in keys {
+            widget.handle_event(&Event::Key(*key), widget.preferred_size());
+        }
+    }
+
+    fn typed(text: &str) -> Vec<Key> {
+        text.chars().map(Key::Char).collect()
+    }
+
+    #[test]
+    fn text_fields_edit_at_the_cursor() {
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&changes);
+        let mut field = TextField::new(60, 11).on_change(move |text| log.borrow_mut().push(text.to_string()));
+
+        keys(&mut field, &typed("ignored"));
+        field.handle_event(&Event::MouseDown(Point::new(3, 3)), Size::new(60, 11));
+        keys(&mut field, &typed("héllo"));
+        keys(&mut field, &[Key::Left, Key::Left, Key::Backspace, Key::Char('L'), Key::Home, Key::Delete, Key::End]);
+
+        assert_eq!(field.text(), "éLlo");
+        assert_eq!(field.cursor(), 4);
+        assert_eq!(changes.borrow().len(), 8);
+        assert_eq!(changes.borrow().last().unwrap(), "éLlo");
+    }
+
+    #[test]
+    fn checkboxes_toggle_on_click_and_space() {
+        let toggles = Rc::new(RefCell::new(Vec::new()));
+        let log = Rc::clone(&toggles);
+        let mut checkbox = Checkbox::new(40, 9, "Agree").on_toggle(move |checked| log.borrow_mut().push(checked));
+
+        keys(&mut checkbox, &[Key::Char(' ')]);
+        checkbox.handle_event(&Event::MouseDown(Point::new(1, 1)), Size::new(40, 9));
+        keys(&mut checkbox, &[Key::Char(' '), Key::Enter, Key::Char('x')]);
+
+        assert!(checkbox.is_checked());
+        assert_eq!(*toggles.borrow(), vec![true, false, true]);
+    }
+
+    #[test]
+    fn select_boxes_pick_the_clicked_row_and_follow_the_arrows() {
+        let options = vec![String::from("Yes"), String::from("Maybe"), String::from("No")];
+        let mut select = SelectBox::new(40, 30, options);
+
+        select.handle_event(&Event::MouseDown(Point::new(5, 15)), Size::new(40, 30));
+        assert_eq!(select.selected(), Some("Maybe"));
+
+        keys(&mut select, &[Key::Down, Key::Down]);
+        assert_eq!(select.selected(), Some("No"));
+        keys(&mut select, &[Key::Up, Key::Up, Key::Up]);
+        assert_eq!(select.selected(), Some("Yes"));
+    }
+}
@@ -0,0 +1,321 @@
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+use crate::game::{format_code, parse_code, Game, Outcome, Rules};
+use crate::net::{Client, Message, Server};
+use crate::replay::Replay;
+use crate::save;
+use crate::stats::{self, GameRecord, Summary};
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Play { save_to: Option<String> },
+    Replay { path: String },
+    Serve { addr: String },
+    Connect { addr: String },
+    Stats,
+}
+
+impl Command {
+    // mastermind [play [--save FILE]] | replay FILE | serve ADDR | connect ADDR | --stats
+    pub fn new<I: Iterator<Item = String>>(mut args: I) -> Result<Command, &'static str> {
+        args.next(); // the program name
+
+        match args.next().as_deref() {
+            None | Some("play") => {
+                let save_to = match args.next().as_deref() {
+                    None => None,
+                    Some("--save") => Some(args.next().ok_or("--save needs a file name")?),
+                    Some(_) => return Err("usage: mastermind play [--save FILE]"),
+                };
+                Ok(Command::Play { save_to })
+            }
+            Some("replay") => {
+                let path = args.next().ok_or("usage: mastermind replay FILE")?;
+                Ok(Command::Replay { path })
+            }
+            Some("serve") => {
+                let addr = args.next().ok_or("usage: mastermind serve ADDR")?;
+                Ok(Command::Serve { addr })
+            }
+            Some("connect") => {
+                let addr = args.next().ok_or("usage: mastermind connect ADDR")?;
+                Ok(Command::Connect { addr })
+            }
+            Some("--stats") => Ok(Command::Stats),
+            Some(_) => Err("unknown command (expected play, replay, serve, connect or --stats)"),
+        }
+    }
+}
+
+pub fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    match command {
+        Command::Play { save_to } => {
+            let mut game = Game::random(Rules::default());
+            let started = Instant::now();
+            play(&mut game, &mut stdin.lock(), &mut stdout.lock())?;
+            record_stats(&game, started)?;
+
+            if let Some(path) = save_to {
+                save::save(&game, &path)?;
+                println!("Game saved to {}", path);
+            }
+        }
+        Command::Replay { path } => {
+            let game = save::load(&path)?;
+            replay(&game, &mut stdin.lock(), &mut stdout.lock())?;
+        }
+        Command::Serve { addr } => {
+            let rules = Rules::default();
+            println!("Enter the secret code ({} colors from {}):", rules.code_length, format_code(rules.palette()));
+
+            let mut line = String::new();
+            stdin.lock().read_line(&mut line)?;
+            let game = Game::new(rules, parse_code(&line)?)?;
+
+            let server = Server::bind(&addr)?;
+            println!("Waiting for a guesser on {}...", server.local_addr()?);
+            // not recorded here - the guesses were the other player's, and their side records the game
+            let game = server.host(game)?;
+
+            // nothing to wait for between moves here, the guesser has already played them
+            replay(&game, &mut io::empty(), &mut stdout.lock())?;
+        }
+        Command::Connect { addr } => {
+            let client = Client::connect(&addr)?;
+            if let Some(record) = guess_remotely(client, &mut stdin.lock(), &mut stdout.lock())? {
+                stats::record(stats::default_path(), &record)?;
+            }
+        }
+        Command::Stats => {
+            let records = stats::load(stats::default_path())?;
+            print!("{}", Summary::new(&records));
+        }
+    }
+
+    Ok(())
+}
+
+fn record_stats(game: &Game, started: Instant) -> io::Result<()> {
+    match GameRecord::from_game(game, started.elapsed()) {
+        Some(record) => stats::record(stats::default_path(), &record),
+        None => Ok(()),
+    }
+}
+
+// Reads one guess per line until the game is over (or the input runs out) - bad input is reported and asked for again.
+pub fn play<R: BufRead, W: Write>(game: &mut Game, input: &mut R, output: &mut W) -> io::Result<()> {
+    let rules = game.rules();
+    writeln!(
+        output,
+        "Guess the {} color code ({}), you have {} guesses.",
+        rules.code_length,
+        format_code(rules.palette()),
+        rules.max_guesses
+    )?;
+
+    while !game.is_over() {
+        write!(output, "Guess {}: ", game.guesses().len() + 1)?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            break;
+        }
+
+        match parse_code(&line).and_then(|guess| game.guess(guess)) {
+            Ok(feedback) => writeln!(output, "exact: {}, partial: {}", feedback.exact, feedback.partial)?,
+            Err(e) => writeln!(output, "Invalid guess: {}", e)?,
+        }
+    }
+
+    match game.outcome() {
+        Outcome::Won => writeln!(output, "You win in {} guesses!", game.guesses().len()),
+        Outcome::Lost => writeln!(output, "Out of guesses! The code was {}", format_code(game.secret())),
+        Outcome::InProgress => writeln!(output, "Game abandoned."),
+    }
+}
+
+// The guesser's side of a networked game: guesses are checked locally for typos before they go over the wire.
+// Hands back a record of the game for the stats if it was played to the end.
+pub fn guess_remotely<R: BufRead, W: Write>(
+    mut client: Client,
+    input: &mut R,
+    output: &mut W,
+) -> io::Result<Option<GameRecord>> {
+    let rules = client.rules();
+    let started = Instant::now();
+    let record = |guesses: usize, won: bool| Some(GameRecord { rules, guesses, duration: started.elapsed(), won });
+    writeln!(
+        output,
+        "Connected! Guess the {} color code ({}), you have {} guesses.",
+        rules.code_length,
+        format_code(rules.palette()),
+        rules.max_guesses
+    )?;
+
+    loop {
+        write!(output, "Guess: ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return client.quit().map(|_| None);
+        }
+
+        let guess = match parse_code(&line) {
+            Ok(guess) => guess,
+            Err(e) => {
+                writeln!(output, "Invalid guess: {}", e)?;
+                continue;
+            }
+        };
+
+        match client.guess(&guess)? {
+            Message::Feedback(feedback) => writeln!(output, "exact: {}, partial: {}", feedback.exact, feedback.partial)?,
+            Message::Invalid(reason) => writeln!(output, "Invalid guess: {}", reason)?,
+            Message::Won(guesses) => {
+                writeln!(output, "You win in {} guesses!", guesses)?;
+                return Ok(record(guesses, true));
+            }
+            Message::Lost(_, secret) => {
+                writeln!(output, "Out of guesses! The code was {}", format_code(&secret))?;
+                return Ok(record(rules.max_guesses, false));
+            }
+            other => writeln!(output, "Unexpected reply from server: {}", other)?,
+        }
+    }
+}
+
+// Prints the game one move at a time, waiting for a line of input (Enter) between moves.
+pub fn replay<R: BufRead, W: Write>(game: &Game, input: &mut R, output: &mut W) -> io::Result<()> {
+    writeln!(output, "Secret: {}", format_code(game.secret()))?;
+
+    for (i, step) in Replay::new(game).enumerate() {
+        if i > 0 {
+            input.read_line(&mut String::new())?;
+        }
+        writeln!(
+            output,
+            "{:>2}. {}  exact: {}, partial: {}",
+            step.turn,
+            format_code(step.guess),
+            step.feedback.exact,
+            step.feedback.partial
+        )?;
+    }
+
+    match game.outcome() {
+        Outcome::Won => writeln!(output, "Won in {} guesses.", game.guesses().len()),
+        Outcome::Lost => writeln!(output, "Lost."),
+        Outcome::InProgress => writeln!(output, "Unfinished game."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color::*;
+
+    fn args(list: &[&str]) -> impl Iterator<Item = String> {
+        list.iter().map(|s| s.to_string()).collect::<Vec<_>>().into_iter()
+    }
+
+    #[test]
+    fn it_parses_commands() {
+        assert_eq!(Command::new(args(&["mastermind"])), Ok(Command::Play { save_to: None }));
+        assert_eq!(
+            Command::new(args(&["mastermind", "play", "--save", "game.txt"])),
+            Ok(Command::Play { save_to: Some(String::from("game.txt")) })
+        );
+        assert_eq!(
+            Command::new(args(&["mastermind", "replay", "game.txt"])),
+            Ok(Command::Replay { path: String::from("game.txt") })
+        );
+        assert_eq!(
+            Command::new(args(&["mastermind", "serve", "127.0.0.1:7878"])),
+            Ok(Command::Serve { addr: String::from("127.0.0.1:7878") })
+        );
+        assert_eq!(Command::new(args(&["mastermind", "--stats"])), Ok(Command::Stats));
+        assert!(Command::new(args(&["mastermind", "replay"])).is_err());
+        assert!(Command::new(args(&["mastermind", "connect"])).is_err());
+    }
+
+    #[test]
+    fn it_plays_a_scripted_game() {
+        let mut game = Game::new(Rules::default(), vec![Red, Orange, Yellow, Green]).unwrap();
+        let mut input = "RRBB\nnope\nROYG\n".as_bytes();
+        let mut output = Vec::new();
+
+        play(&mut game, &mut input, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(game.guesses().len(), 2);
+        assert!(output.contains("exact: 1, partial: 0"));
+        assert!(output.contains("Invalid guess"));
+        assert!(output.contains("You win in 2 guesses!"));
+    }
+
+    #[test]
+    fn it_guesses_against_a_server() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let game = Game::new(Rules::default(), vec![Blue, Blue, Green, Green]).unwrap();
+        let host = std::thread::spawn(move || server.host(game).unwrap());
+
+        let mut input = "BBBB\nxx\nBBGG\n".as_bytes();
+        let mut output = Vec::new();
+        let record = guess_remotely(Client::connect(addr).unwrap(), &mut input, &mut output).unwrap().unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!((record.guesses, record.won), (2, true));
+        assert!(output.contains("exact: 2, partial: 0"));
+        assert!(output.contains("Invalid guess"));
+        assert!(output.contains("You win in 2 guesses!"));
+        assert_eq!(host.join().unwrap().outcome(), Outcome::Won);
+    }
+
+    #[test]
+    fn it_records_a_remote_loss() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let game = Game::new(Rules::new(4, 6, 2).unwrap(), vec![Blue, Blue, Green, Green]).unwrap();
+        let host = std::thread::spawn(move || server.host(game).unwrap());
+
+        let mut input = "RRRR\nRRRR\n".as_bytes();
+        let record = guess_remotely(Client::connect(addr).unwrap(), &mut input, &mut Vec::new()).unwrap().unwrap();
+
+        assert_eq!((record.guesses, record.won), (2, false));
+        assert_eq!(host.join().unwrap().outcome(), Outcome::Lost);
+    }
+
+    #[test]
+    fn an_abandoned_remote_game_isnt_recorded() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let game = Game::new(Rules::default(), vec![Blue, Blue, Green, Green]).unwrap();
+        let host = std::thread::spawn(move || server.host(game).unwrap());
+
+        let record = guess_remotely(Client::connect(addr).unwrap(), &mut "RRRR\n".as_bytes(), &mut Vec::new()).unwrap();
+
+        assert_eq!(record, None);
+        assert_eq!(host.join().unwrap().outcome(), Outcome::InProgress);
+    }
+
+    #[test]
+    fn it_replays_a_saved_game() {
+        let game = save::from_str("mastermind-save 1\nrules 4 6 10\nsecret ROYG\nguess RRBB\nguess ROYG\n").unwrap();
+        let mut output = Vec::new();
+
+        replay(&game, &mut "\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Secret: ROYG\n 1. RRBB  exact: 1, partial: 0\n 2. ROYG  exact: 4, partial: 0\nWon in 2 guesses.\n"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use rand::{thread_rng, Rng};
+
+use crate::{Color, COLORS};
+
+// The knobs of a game: how long the secret is, how many of the colors are in play and how many guesses the player gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub code_length: usize,
+    pub colors: usize,
+    pub max_guesses: usize,
+}
+
+impl Rules {
+    pub fn new(code_length: usize, colors: usize, max_guesses: usize) -> Result<Rules, &'static str> {
+        if code_length == 0 {
+            return Err("code length must be at least 1");
+        }
+        if colors == 0 || colors > COLORS.len() {
+            return Err("number of colors must be between 1 and 6");
+        }
+        if max_guesses == 0 {
+            return Err("max guesses must be at least 1");
+        }
+
+        Ok(Rules { code_length, colors, max_guesses })
+    }
+
+    /// The colors a code may use under these rules - always the first `colors` entries of `COLORS`.
+    pub fn palette(&self) -> &'static [Color] {
+        &COLORS[..self.colors]
+    }
+
+    pub fn check_code(&self, code: &[Color]) -> Result<(), &'static str> {
+        if code.len() != self.code_length {
+            return Err("code has the wrong length");
+        }
+        if code.iter().any(|c| !self.palette().contains(c)) {
+            return Err("code uses a color that is not in play");
+        }
+        Ok(())
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules { code_length: 4, colors: 6, max_guesses: 10 }
+    }
+}
+
+// Classic mastermind pegs: `exact` is right color in the right place, `partial` is right color in the wrong place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feedback {
+    pub exact: usize,
+    pub partial: usize,
+}
+
+impl Feedback {
+    pub fn score(secret: &[Color], guess: &[Color]) -> Feedback {
+        let exact = secret.iter().zip(guess.iter()).filter(|(s, g)| s == g).count();
+
+        // every color that appears in both codes counts once per matching occurrence, wherever it is...
+        let common: usize = COLORS
+            .iter()
+            .map(|color| {
+                let in_secret = secret.iter().filter(|c| *c == color).count();
+                let in_guess = guess.iter().filter(|c| *c == color).count();
+                in_secret.min(in_guess)
+            })
+            .sum();
+
+        // ...so the ones in the right place have to be taken back out
+        Feedback { exact, partial: common - exact }
+    }
+
+    pub fn is_win(&self, rules: &Rules) -> bool {
+        self.exact == rules.code_length
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    InProgress,
+    Won,
+    Lost,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    rules: Rules,
+    secret: Vec<Color>,
+    guesses: Vec<Vec<Color>>,
+}
+
+impl Game {
+    pub fn new(rules: Rules, secret: Vec<Color>) -> Result<Game, &'static str> {
+        rules.check_code(&secret)?;
+
+        Ok(Game { rules, secret, guesses: Vec::new() })
+    }
+
+    pub fn random(rules: Rules) -> Game {
+        let mut rng = thread_rng();
+        let palette = rules.palette();
+        let secret = (0..rules.code_length)
+            .map(|_| palette[rng.gen_range(0, palette.len())])
+            .collect();
+
+        Game { rules, secret, guesses: Vec::new() }
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn secret(&self) -> &[Color] {
+        &self.secret
+    }
+
+    pub fn guesses(&self) -> &[Vec<Color>] {
+        &self.guesses
+    }
+
+    pub fn guess(&mut self, guess: Vec<Color>) -> Result<Feedback, &'static str> {
+        if self.is_over() {
+            return Err("the game is already over");
+        }
+        self.rules.check_code(&guess)?;
+
+        let feedback = Feedback::score(&self.secret, &guess);
+        self.guesses.push(guess);
+        Ok(feedback)
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        match self.guesses.last() {
+            Some(last) if *last == self.secret => Outcome::Won,
+            _ if self.guesses.len() >= self.rules.max_guesses => Outcome::Lost,
+            _ => Outcome::InProgress,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome() != Outcome::InProgress
+    }
+}
+
+pub fn parse_code(input: &str) -> Result<Vec<Color>, &'static str> {
+    input
+        .trim()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| Color::from_letter(c).ok_or("unknown color letter (use R O Y G B P)"))
+        .collect()
+}
+
+pub fn format_code(code: &[Color]) -> String {
+    code.iter().map(|c| c.letter()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color::*;
+
+    #[test]
+    fn it_scores_exact_and_partial_pegs() {
+        let secret = [Red, Red, Blue, Green];
+
+        assert_eq!(Feedback::score(&secret, &[Red, Red, Blue, Green]), Feedback { exact: 4, partial: 0 });
+        assert_eq!(Feedback::score(&secret, &[Green, Blue, Red, Red]), Feedback { exact: 0, partial: 4 });
+        assert_eq!(Feedback::score(&secret, &[Red, Yellow, Red, Yellow]), Feedback { exact: 1, partial: 1 });
+        assert_eq!(Feedback::score(&secret, &[Purple, Purple, Purple, Purple]), Feedback { exact: 0, partial: 0 });
+    }
+
+    #[test]
+    fn it_rejects_codes_that_break_the_rules() {
+        let rules = Rules::new(4, 3, 10).unwrap();
+
+        assert!(Game::new(rules, vec![Red, Red, Red]).is_err());
+        assert!(Game::new(rules, vec![Red, Red, Red, Purple]).is_err());
+        assert!(Rules::new(4, 7, 10).is_err());
+    }
+
+    #[test]
+    fn it_ends_on_a_win_or_when_guesses_run_out() {
+        let rules = Rules::new(2, 6, 2).unwrap();
+
+        let mut won = Game::new(rules, vec![Red, Blue]).unwrap();
+        won.guess(vec![Red, Blue]).unwrap();
+        assert_eq!(won.outcome(), Outcome::Won);
+        assert!(won.guess(vec![Red, Blue]).is_err());
+
+        let mut lost = Game::new(rules, vec![Red, Blue]).unwrap();
+        lost.guess(vec![Blue, Red]).unwrap();
+        assert_eq!(lost.outcome(), Outcome::InProgress);
+        lost.guess(vec![Blue, Red]).unwrap();
+        assert_eq!(lost.outcome(), Outcome::Lost);
+    }
+
+    #[test]
+    fn it_parses_and_formats_codes() {
+        assert_eq!(parse_code("royg").unwrap(), vec![Red, Orange, Yellow, Green]);
+        assert_eq!(parse_code(" R O B P\n").unwrap(), vec![Red, Orange, Blue, Purple]);
+        assert!(parse_code("RZ").is_err());
+        assert_eq!(format_code(&[Blue, Purple]), "BP");
+    }
+}
@@ -1,7 +1,16 @@
 use rand::{thread_rng, Rng};
 
-#[derive(Debug, PartialEq)]
-enum Color {
+pub mod cli;
+pub mod game;
+pub mod net;
+pub mod replay;
+pub mod save;
+pub mod stats;
+
+pub use game::{Feedback, Game, Outcome, Rules};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
     Red,
     Orange,
     Yellow,
@@ -10,7 +19,7 @@ enum Color {
     Purple
 }
 
-const COLORS: [Color; 6] = [
+pub const COLORS: [Color; 6] = [
     Color::Red,
     Color::Orange,
     Color::Yellow,
@@ -19,11 +28,29 @@ const COLORS: [Color; 6] = [
     Color::Purple
 ];
 
-fn generate_code<'a>() -> Vec<&'a Color> {
+impl Color {
+    // Each color is written as a single letter, so a whole code fits in a short word like "ROYG"
+    pub fn letter(self) -> char {
+        match self {
+            Color::Red => 'R',
+            Color::Orange => 'O',
+            Color::Yellow => 'Y',
+            Color::Green => 'G',
+            Color::Blue => 'B',
+            Color::Purple => 'P',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Color> {
+        COLORS.iter().cloned().find(|c| c.letter() == letter.to_ascii_uppercase())
+    }
+}
+
+pub fn generate_code<'a>() -> Vec<&'a Color> {
     let mut rng = thread_rng();
     // start with an empty array - length 4
     // .map(|c| *c)
-    (0..4).into_iter().map(|_| rng.gen_range(0, 6)).map(|i| &COLORS[i]).collect()
+    (0..4).map(|_| rng.gen_range(0, 6)).map(|i| &COLORS[i]).collect()
     // map over it - generate random number (0-5)
     // grab color at that index
     // populate it!
@@ -61,4 +88,13 @@ mod tests {
     fn it_populates_array_of_colors() {
         assert_eq!(generate_code(), vec![&Color::Red]);
     }
+
+    #[test]
+    fn it_round_trips_color_letters() {
+        for color in COLORS.iter() {
+            assert_eq!(Color::from_letter(color.letter()), Some(*color));
+        }
+        assert_eq!(Color::from_letter('g'), Some(Color::Green));
+        assert_eq!(Color::from_letter('X'), None);
+    }
 }
@@ -0,0 +1,17 @@
+extern crate mastermind;
+use std::env;
+use std::process;
+
+use mastermind::cli::{self, Command};
+
+fn main() {
+    let command = Command::new(env::args()).unwrap_or_else(|err| {
+        eprintln!("Problem parsing arguments: {}", err);
+        process::exit(1);
+    });
+
+    if let Err(e) = cli::run(command) {
+        eprintln!("Application error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,262 @@
+// Two-player mastermind over TCP: the server holds the secret (the code setter) and the client makes the guesses.
+//
+// The protocol is one message per line:
+//
+//     server -> client   RULES <code length> <colors> <max guesses>   (once, straight after connecting)
+//     client -> server   GUESS ROYG
+//     server -> client   FEEDBACK <exact> <partial>                 (the game goes on)
+//                        WON <guesses used>
+//                        LOST <exact> <partial> <secret>            (out of guesses, the secret is revealed)
+//                        INVALID <reason>                           (the guess didn't count)
+//     client -> server   QUIT
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::game::{format_code, parse_code, Feedback, Game, Outcome, Rules};
+use crate::Color;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Rules(Rules),
+    Guess(Vec<Color>),
+    Feedback(Feedback),
+    Won(usize),
+    Lost(Feedback, Vec<Color>),
+    Invalid(String),
+    Quit,
+}
+
+impl Message {
+    pub fn parse(line: &str) -> Result<Message, String> {
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap_or("");
+        let rest: Vec<&str> = words.collect();
+
+        let number = |i: usize| -> Result<usize, String> {
+            rest.get(i)
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(|| format!("{} is missing a number", keyword))
+        };
+
+        let message = match keyword {
+            "RULES" => Message::Rules(Rules::new(number(0)?, number(1)?, number(2)?)?),
+            "GUESS" => Message::Guess(parse_code(rest.first().unwrap_or(&""))?),
+            "FEEDBACK" => Message::Feedback(Feedback { exact: number(0)?, partial: number(1)? }),
+            "WON" => Message::Won(number(0)?),
+            "LOST" => Message::Lost(
+                Feedback { exact: number(0)?, partial: number(1)? },
+                parse_code(rest.get(2).unwrap_or(&""))?,
+            ),
+            "INVALID" => Message::Invalid(rest.join(" ")),
+            "QUIT" => Message::Quit,
+            _ => return Err(format!("unknown message: {}", line.trim())),
+        };
+
+        Ok(message)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Rules(r) => write!(f, "RULES {} {} {}", r.code_length, r.colors, r.max_guesses),
+            Message::Guess(code) => write!(f, "GUESS {}", format_code(code)),
+            Message::Feedback(fb) => write!(f, "FEEDBACK {} {}", fb.exact, fb.partial),
+            Message::Won(guesses) => write!(f, "WON {}", guesses),
+            Message::Lost(fb, secret) => write!(f, "LOST {} {} {}", fb.exact, fb.partial, format_code(secret)),
+            Message::Invalid(reason) => write!(f, "INVALID {}", reason),
+            Message::Quit => write!(f, "QUIT"),
+        }
+    }
+}
+
+fn send<W: Write>(stream: &mut W, message: &Message) -> io::Result<()> {
+    writeln!(stream, "{}", message)?;
+    stream.flush()
+}
+
+// None means the other side hung up
+fn receive<R: BufRead>(stream: &mut R) -> io::Result<Option<Message>> {
+    let mut line = String::new();
+    if stream.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+
+    Message::parse(&line)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub struct Server {
+    listener: TcpListener,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Server> {
+        Ok(Server { listener: TcpListener::bind(addr)? })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Waits for one guesser to connect and referees the game against them, handing back the game as it stood when they left.
+    pub fn host(&self, game: Game) -> io::Result<Game> {
+        let (stream, _) = self.listener.accept()?;
+        host_game(game, &mut BufReader::new(stream.try_clone()?), &mut &stream)
+    }
+}
+
+pub fn host_game<R: BufRead, W: Write>(mut game: Game, reader: &mut R, writer: &mut W) -> io::Result<Game> {
+    send(writer, &Message::Rules(game.rules()))?;
+
+    loop {
+        let message = match receive(reader) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            // a garbled line from the guesser doesn't end the game, it just doesn't count
+            Err(ref e) if e.kind() == io::ErrorKind::InvalidData => {
+                send(writer, &Message::Invalid(e.to_string()))?;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let reply = match message {
+            Message::Guess(guess) => match game.guess(guess) {
+                Ok(feedback) => match game.outcome() {
+                    Outcome::InProgress => Message::Feedback(feedback),
+                    Outcome::Won => Message::Won(game.guesses().len()),
+                    Outcome::Lost => Message::Lost(feedback, game.secret().to_vec()),
+                },
+                Err(e) => Message::Invalid(e.to_string()),
+            },
+            Message::Quit => break,
+            other => Message::Invalid(format!("unexpected message: {}", other)),
+        };
+
+        send(writer, &reply)?;
+        if game.is_over() {
+            break;
+        }
+    }
+
+    Ok(game)
+}
+
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    rules: Rules,
+}
+
+impl Client {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Client> {
+        let writer = TcpStream::connect(addr)?;
+        let mut reader = BufReader::new(writer.try_clone()?);
+
+        let rules = match receive(&mut reader)? {
+            Some(Message::Rules(rules)) => rules,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "server didn't send the rules")),
+        };
+
+        Ok(Client { reader, writer, rules })
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// Sends a guess and waits for the server's verdict on it.
+    pub fn guess(&mut self, guess: &[Color]) -> io::Result<Message> {
+        send(&mut self.writer, &Message::Guess(guess.to_vec()))?;
+
+        receive(&mut self.reader)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "server closed the connection"))
+    }
+
+    pub fn quit(mut self) -> io::Result<()> {
+        send(&mut self.writer, &Message::Quit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color::*;
+    use std::thread;
+
+    fn start_server(rules: Rules, secret: Vec<Color>) -> (SocketAddr, thread::JoinHandle<Game>) {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let game = Game::new(rules, secret).unwrap();
+
+        (addr, thread::spawn(move || server.host(game).unwrap()))
+    }
+
+    #[test]
+    fn it_round_trips_messages() {
+        let messages = vec![
+            Message::Rules(Rules::default()),
+            Message::Guess(vec![Red, Orange]),
+            Message::Feedback(Feedback { exact: 1, partial: 2 }),
+            Message::Won(3),
+            Message::Lost(Feedback { exact: 0, partial: 1 }, vec![Blue, Blue]),
+            Message::Invalid(String::from("code has the wrong length")),
+            Message::Quit,
+        ];
+
+        for message in messages {
+            assert_eq!(Message::parse(&message.to_string()), Ok(message));
+        }
+        assert!(Message::parse("HELLO").is_err());
+    }
+
+    #[test]
+    fn it_plays_a_scripted_game_over_loopback() {
+        let (addr, server) = start_server(Rules::default(), vec![Red, Orange, Yellow, Green]);
+        let mut client = Client::connect(addr).unwrap();
+        assert_eq!(client.rules(), Rules::default());
+
+        assert_eq!(
+            client.guess(&[Red, Red, Blue, Blue]).unwrap(),
+            Message::Feedback(Feedback { exact: 1, partial: 0 })
+        );
+        assert_eq!(
+            client.guess(&[Red, Red]).unwrap(),
+            Message::Invalid(String::from("code has the wrong length"))
+        );
+        assert_eq!(client.guess(&[Red, Orange, Yellow, Green]).unwrap(), Message::Won(2));
+
+        let game = server.join().unwrap();
+        assert_eq!(game.outcome(), Outcome::Won);
+        assert_eq!(game.guesses().len(), 2);
+    }
+
+    #[test]
+    fn it_reveals_the_secret_when_the_guesser_loses() {
+        let (addr, server) = start_server(Rules::new(2, 6, 1).unwrap(), vec![Blue, Green]);
+        let mut client = Client::connect(addr).unwrap();
+
+        assert_eq!(
+            client.guess(&[Green, Purple]).unwrap(),
+            Message::Lost(Feedback { exact: 0, partial: 1 }, vec![Blue, Green])
+        );
+        assert_eq!(server.join().unwrap().outcome(), Outcome::Lost);
+    }
+
+    #[test]
+    fn it_stops_when_the_guesser_quits() {
+        let (addr, server) = start_server(Rules::default(), vec![Red, Red, Red, Red]);
+        let mut client = Client::connect(addr).unwrap();
+
+        client.guess(&[Blue, Blue, Blue, Blue]).unwrap();
+        client.quit().unwrap();
+
+        let game = server.join().unwrap();
+        assert_eq!(game.outcome(), Outcome::InProgress);
+        assert_eq!(game.guesses().len(), 1);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::game::{Feedback, Game};
+use crate::Color;
+
+// One step of a replay: which turn it was, what was guessed and the pegs the guess earned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move<'a> {
+    pub turn: usize,
+    pub guess: &'a [Color],
+    pub feedback: Feedback,
+}
+
+// Walks a game's history one guess at a time - the feedback isn't stored in a save, it's worked out again from the secret.
+pub struct Replay<'a> {
+    game: &'a Game,
+    next: usize,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(game: &'a Game) -> Replay<'a> {
+        Replay { game, next: 0 }
+    }
+}
+
+impl<'a> Iterator for Replay<'a> {
+    type Item = Move<'a>;
+
+    fn next(&mut self) -> Option<Move<'a>> {
+        let guess = self.game.guesses().get(self.next)?;
+        self.next += 1;
+
+        Some(Move {
+            turn: self.next,
+            guess,
+            feedback: Feedback::score(self.game.secret(), guess),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Rules;
+    use crate::Color::*;
+
+    #[test]
+    fn it_steps_through_each_guess() {
+        let mut game = Game::new(Rules::default(), vec![Red, Orange, Yellow, Green]).unwrap();
+        game.guess(vec![Green, Red, Blue, Blue]).unwrap();
+        game.guess(vec![Red, Orange, Yellow, Green]).unwrap();
+
+        let moves: Vec<Move> = Replay::new(&game).collect();
+
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].turn, 1);
+        assert_eq!(moves[0].feedback, Feedback { exact: 0, partial: 2 });
+        assert_eq!(moves[1].guess, &[Red, Orange, Yellow, Green][..]);
+        assert!(moves[1].feedback.is_win(&game.rules()));
+    }
+}
@@ -0,0 +1,156 @@
+// A saved game is a small line-based text file, e.g.
+//
+//     mastermind-save 1
+//     rules 4 6 10
+//     secret ROYG
+//     guess RRBB
+//     guess ROYG
+//
+// The first line names the format and its version so older files can still be recognised if it ever changes.
+// Loading replays every guess through a fresh `Game`, so a file can only describe a game that could really have been played.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::game::{format_code, parse_code, Game, Rules};
+
+pub const HEADER: &str = "mastermind-save 1";
+
+pub fn to_string(game: &Game) -> String {
+    let rules = game.rules();
+    let mut out = format!(
+        "{}\nrules {} {} {}\nsecret {}\n",
+        HEADER,
+        rules.code_length,
+        rules.colors,
+        rules.max_guesses,
+        format_code(game.secret())
+    );
+
+    for guess in game.guesses() {
+        out.push_str(&format!("guess {}\n", format_code(guess)));
+    }
+
+    out
+}
+
+pub fn from_str(text: &str) -> Result<Game, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    match lines.next() {
+        Some((_, HEADER)) => {}
+        Some((n, _)) => return Err(format!("line {}: not a mastermind save file", n)),
+        None => return Err(String::from("save file is empty")),
+    }
+
+    let rules = match lines.next() {
+        Some((n, line)) => parse_rules(line).map_err(|e| format!("line {}: {}", n, e))?,
+        None => return Err(String::from("missing rules line")),
+    };
+
+    let mut game = match lines.next() {
+        Some((n, line)) => {
+            let secret = field(line, "secret")
+                .ok_or("expected a secret line")
+                .and_then(parse_code)
+                .map_err(|e| format!("line {}: {}", n, e))?;
+            Game::new(rules, secret).map_err(|e| format!("line {}: {}", n, e))?
+        }
+        None => return Err(String::from("missing secret line")),
+    };
+
+    for (n, line) in lines {
+        field(line, "guess")
+            .ok_or("expected a guess line")
+            .and_then(parse_code)
+            .and_then(|guess| game.guess(guess))
+            .map_err(|e| format!("line {}: {}", n, e))?;
+    }
+
+    Ok(game)
+}
+
+pub fn save<P: AsRef<Path>>(game: &Game, path: P) -> Result<(), Box<dyn Error>> {
+    fs::write(path, to_string(game))?;
+    Ok(())
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Game, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    Ok(from_str(&text)?)
+}
+
+// "secret ROYG" => Some("ROYG")
+fn field<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let mut parts = line.splitn(2, ' ');
+    if parts.next() == Some(name) {
+        parts.next()
+    } else {
+        None
+    }
+}
+
+fn parse_rules(line: &str) -> Result<Rules, &'static str> {
+    let numbers = field(line, "rules")
+        .ok_or("expected a rules line")?
+        .split_whitespace()
+        .map(|n| n.parse::<usize>().map_err(|_| "rules must be numbers"))
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    match numbers[..] {
+        [code_length, colors, max_guesses] => Rules::new(code_length, colors, max_guesses),
+        _ => Err("rules line needs code length, colors and max guesses"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color::*;
+
+    fn finished_game() -> Game {
+        let mut game = Game::new(Rules::default(), vec![Red, Orange, Yellow, Green]).unwrap();
+        game.guess(vec![Red, Red, Blue, Blue]).unwrap();
+        game.guess(vec![Red, Orange, Yellow, Green]).unwrap();
+        game
+    }
+
+    #[test]
+    fn it_round_trips_a_game() {
+        let game = finished_game();
+        let text = to_string(&game);
+
+        assert_eq!(text, "mastermind-save 1\nrules 4 6 10\nsecret ROYG\nguess RRBB\nguess ROYG\n");
+        assert_eq!(from_str(&text).unwrap(), game);
+    }
+
+    #[test]
+    fn it_round_trips_through_a_file() {
+        let path = std::env::temp_dir().join(format!("mastermind-save-{}.txt", std::process::id()));
+        let game = finished_game();
+
+        save(&game, &path).unwrap();
+        let loaded = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, game);
+    }
+
+    #[test]
+    fn it_rejects_invalid_saves() {
+        assert!(from_str("").is_err());
+        assert!(from_str("chess-save 1\n").is_err());
+        assert!(from_str("mastermind-save 1\nrules 4 9 10\nsecret ROYG\n").is_err());
+        assert!(from_str("mastermind-save 1\nrules 4 6 10\nsecret ROY\n").is_err());
+        assert!(from_str("mastermind-save 1\nrules 4 6 10\nsecret ROYG\nguess RRXB\n").is_err());
+
+        // nothing may be guessed once the game has been won
+        let err = from_str("mastermind-save 1\nrules 4 6 10\nsecret ROYG\nguess ROYG\nguess RRBB\n").unwrap_err();
+        assert_eq!(err, "line 5: the game is already over");
+    }
+}
@@ -0,0 +1,269 @@
+// Every finished game is appended to a stats file as one line:
+//
+//     <code length> <colors> <max guesses> <guesses used> <duration in ms> won|lost
+//
+// Appending keeps recording cheap and means a crash mid-write can only ever damage the last line. Only games you
+// guessed in are recorded: a networked game goes in the guesser's stats, not the host's.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::game::{Game, Outcome, Rules};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameRecord {
+    pub rules: Rules,
+    pub guesses: usize,
+    pub duration: Duration,
+    pub won: bool,
+}
+
+impl GameRecord {
+    // Abandoned games aren't finished, so they don't get a record
+    pub fn from_game(game: &Game, duration: Duration) -> Option<GameRecord> {
+        let won = match game.outcome() {
+            Outcome::Won => true,
+            Outcome::Lost => false,
+            Outcome::InProgress => return None,
+        };
+
+        Some(GameRecord { rules: game.rules(), guesses: game.guesses().len(), duration, won })
+    }
+
+    pub fn parse(line: &str) -> Result<GameRecord, &'static str> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err("expected 6 fields");
+        }
+
+        let numbers = fields[..5]
+            .iter()
+            .map(|n| n.parse::<u64>().map_err(|_| "expected a number"))
+            .collect::<Result<Vec<u64>, _>>()?;
+        let won = match fields[5] {
+            "won" => true,
+            "lost" => false,
+            _ => return Err("expected won or lost"),
+        };
+
+        Ok(GameRecord {
+            rules: Rules::new(numbers[0] as usize, numbers[1] as usize, numbers[2] as usize)?,
+            guesses: numbers[3] as usize,
+            duration: Duration::from_millis(numbers[4]),
+            won,
+        })
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.rules.code_length,
+            self.rules.colors,
+            self.rules.max_guesses,
+            self.guesses,
+            self.duration.as_millis(),
+            if self.won { "won" } else { "lost" }
+        )
+    }
+}
+
+// $MASTERMIND_STATS if it's set, otherwise a dot file in the home directory
+pub fn default_path() -> PathBuf {
+    if let Ok(path) = env::var("MASTERMIND_STATS") {
+        return PathBuf::from(path);
+    }
+
+    match env::var("HOME") {
+        Ok(home) => Path::new(&home).join(".mastermind_stats"),
+        Err(_) => PathBuf::from(".mastermind_stats"),
+    }
+}
+
+pub fn record<P: AsRef<Path>>(path: P, record: &GameRecord) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", record)
+}
+
+// A missing file just means no games have been played yet
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<GameRecord>, Box<dyn Error>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| GameRecord::parse(line).map_err(|e| format!("line {}: {}", i + 1, e).into()))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub played: usize,
+    pub won: usize,
+    pub lost: usize,
+    pub average_guesses: Option<f64>, // over games that were won
+    pub average_duration: Option<Duration>,
+    pub histogram: BTreeMap<usize, usize>, // guesses used => number of games won with that many
+    pub best_streak: usize,
+    pub current_streak: usize,
+}
+
+impl Summary {
+    pub fn new(records: &[GameRecord]) -> Summary {
+        let wins: Vec<&GameRecord> = records.iter().filter(|r| r.won).collect();
+
+        let average_guesses = if wins.is_empty() {
+            None
+        } else {
+            Some(wins.iter().map(|r| r.guesses).sum::<usize>() as f64 / wins.len() as f64)
+        };
+
+        let average_duration = if records.is_empty() {
+            None
+        } else {
+            Some(records.iter().map(|r| r.duration).sum::<Duration>() / records.len() as u32)
+        };
+
+        let mut histogram = BTreeMap::new();
+        for record in &wins {
+            *histogram.entry(record.guesses).or_insert(0) += 1;
+        }
+
+        let mut best_streak = 0;
+        let mut current_streak = 0;
+        for record in records {
+            current_streak = if record.won { current_streak + 1 } else { 0 };
+            best_streak = best_streak.max(current_streak);
+        }
+
+        Summary {
+            played: records.len(),
+            won: wins.len(),
+            lost: records.len() - wins.len(),
+            average_guesses,
+            average_duration,
+            histogram,
+            best_streak,
+            current_streak,
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.played == 0 {
+            return writeln!(f, "No games played yet.");
+        }
+
+        writeln!(
+            f,
+            "Played: {}  Won: {} ({:.0}%)  Lost: {}",
+            self.played,
+            self.won,
+            100.0 * self.won as f64 / self.played as f64,
+            self.lost
+        )?;
+        if let Some(average) = self.average_guesses {
+            writeln!(f, "Average guesses to win: {:.1}", average)?;
+        }
+        if let Some(average) = self.average_duration {
+            writeln!(f, "Average game length: {:.1}s", average.as_secs_f64())?;
+        }
+        writeln!(f, "Best streak: {}  Current streak: {}", self.best_streak, self.current_streak)?;
+
+        if !self.histogram.is_empty() || self.lost > 0 {
+            writeln!(f, "Guesses to win:")?;
+            for (guesses, games) in &self.histogram {
+                writeln!(f, "{:>4} | {} {}", guesses, "#".repeat(*games), games)?;
+            }
+            if self.lost > 0 {
+                writeln!(f, "lost | {} {}", "#".repeat(self.lost), self.lost)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color::*;
+
+    fn record(guesses: usize, secs: u64, won: bool) -> GameRecord {
+        GameRecord { rules: Rules::default(), guesses, duration: Duration::from_secs(secs), won }
+    }
+
+    #[test]
+    fn it_only_records_finished_games() {
+        let mut game = Game::new(Rules::default(), vec![Red, Red, Red, Red]).unwrap();
+        assert_eq!(GameRecord::from_game(&game, Duration::from_secs(1)), None);
+
+        game.guess(vec![Red, Red, Red, Red]).unwrap();
+        assert_eq!(GameRecord::from_game(&game, Duration::from_secs(1)), Some(record(1, 1, true)));
+    }
+
+    #[test]
+    fn it_round_trips_records_through_a_file() {
+        let path = env::temp_dir().join(format!("mastermind-stats-{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(load(&path).unwrap(), vec![]);
+
+        let records = vec![record(4, 30, true), record(10, 95, false)];
+        for r in &records {
+            super::record(&path, r).unwrap();
+        }
+        let loaded = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, records);
+        assert!(GameRecord::parse("4 6 10 3 100 drew").is_err());
+        assert!(GameRecord::parse("4 6 10 3").is_err());
+    }
+
+    #[test]
+    fn it_summarises_averages_histogram_and_streaks() {
+        let records = vec![
+            record(4, 10, true),
+            record(6, 20, true),
+            record(10, 60, false),
+            record(4, 30, true),
+            record(5, 10, true),
+            record(4, 20, true),
+        ];
+        let summary = Summary::new(&records);
+
+        assert_eq!(summary.played, 6);
+        assert_eq!(summary.won, 5);
+        assert_eq!(summary.lost, 1);
+        assert_eq!(summary.average_guesses, Some(23.0 / 5.0));
+        assert_eq!(summary.average_duration, Some(Duration::from_secs(25)));
+        assert_eq!(summary.histogram.get(&4), Some(&3));
+        assert_eq!(summary.histogram.get(&10), None);
+        assert_eq!(summary.best_streak, 3);
+        assert_eq!(summary.current_streak, 3);
+        assert!(summary.to_string().contains("   4 | ### 3"));
+        assert!(summary.to_string().contains("lost | # 1"));
+        assert!(summary.to_string().contains("Lost: 1"));
+    }
+
+    #[test]
+    fn it_reports_an_empty_history() {
+        let summary = Summary::new(&[]);
+
+        assert_eq!(summary.average_guesses, None);
+        assert_eq!(summary.to_string(), "No games played yet.\n");
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::SystemTime;
+
+use crate::{Action, Status};
+
+// One change of state, as it's written down in a post's history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub from: Status,
+    pub to: Status,
+    pub action: Action,
+    pub actor: String,
+    pub timestamp: SystemTime,
+    pub comment: Option<String>,
+}
+
+// The log of every transition a post has made, oldest first. Only successful transitions are recorded -
+// an approval that doesn't publish the post yet still shows up, as PendingReview -> PendingReview.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    entries: Vec<Transition>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History { entries: Vec::new() }
+    }
+
+    pub(crate) fn record(&mut self, transition: Transition) {
+        self.entries.push(transition);
+    }
+
+    pub fn entries(&self) -> &[Transition] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&Transition> {
+        self.entries.last()
+    }
+
+    pub fn by_actor<'a>(&'a self, actor: &'a str) -> impl Iterator<Item = &'a Transition> + 'a {
+        self.entries.iter().filter(move |t| t.actor == actor)
+    }
+
+    pub fn with_action(&self, action: Action) -> impl Iterator<Item = &Transition> {
+        self.entries.iter().filter(move |t| t.action == action)
+    }
+
+    // Transitions that moved the post into `status` from some other state
+    pub fn entering(&self, status: Status) -> impl Iterator<Item = &Transition> {
+        self.entries.iter().filter(move |t| t.to == status && t.from != status)
+    }
+
+    pub fn between(&self, start: SystemTime, end: SystemTime) -> impl Iterator<Item = &Transition> {
+        self.entries.iter().filter(move |t| t.timestamp >= start && t.timestamp <= end)
+    }
+}
@@ -0,0 +1,666 @@
+// The blog workflow as a small HTTP/JSON service. Every request is handled by the same Post methods as everywhere
+// else - the service only turns requests into calls and results (or TransitionErrors) into responses.
+//
+//     POST /posts                       {"required_approvals": 2}    (body optional) => 201 {"id": "1", "status": "Draft", ...}
+//     GET  /posts                                                    => 200 {"ids": ["1", "2"]}
+//     GET  /posts/{id}                                               => 200 {"id": ..., "status": ..., "content": ...}
+//     POST /posts/{id}/text             {"text": "..."}
+//     POST /posts/{id}/request_review   {"actor": "..."}
+//     POST /posts/{id}/approve          {"actor": "..."}
+//     POST /posts/{id}/reject           {"actor": "...", "comment": "..."}     (comment optional)
+//     GET  /posts/{id}/content                                       => 200 {"content": ...} once published, 409 before
+//     GET  /posts/{id}/html                                          => 200 {"html": ..., "excerpt": ...} likewise
+//
+// Errors come back as {"error": "..."} with 400 for a bad request, 404 for an unknown post or path and 409 for a
+// transition the post's state doesn't allow, 408 for a client that stalls, 413 for a body over MAX_BODY bytes and 431
+// for a header line over MAX_LINE bytes or more than MAX_HEADERS headers. It's deliberately simple: one request per
+// connection, each handled on one of a few worker threads.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::num::NonZeroUsize;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::{Action, Post, Status};
+
+const EXCERPT_LENGTH: usize = 160;
+pub const MAX_BODY: usize = 1024 * 1024;
+pub const MAX_LINE: usize = 8192; // for the request line and each header, newline included
+pub const MAX_HEADERS: usize = 100;
+const TIMEOUT: Duration = Duration::from_secs(10); // for each read and write, so a silent client can't hold a worker
+const WORKERS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String, // always a JSON object
+}
+
+impl Response {
+    fn json(status: u16, fields: &[(&str, Json)]) -> Response {
+        Response { status, body: Json::object(fields).to_string() }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response::json(status, &[("error", Json::from(message))])
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+pub struct BlogService {
+    posts: BTreeMap<u64, Post>,
+    next_id: u64,
+}
+
+impl BlogService {
+    pub fn new() -> BlogService {
+        BlogService { posts: BTreeMap::new(), next_id: 1 }
+    }
+
+    // Answers requests on `listener` until accepting a connection fails. Connections are handed to WORKERS threads,
+    // and once they're all busy (with one more waiting for each) accepting waits for one to come free.
+    pub fn serve(self, listener: TcpListener) -> io::Result<()> {
+        let service = Arc::new(Mutex::new(self));
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(WORKERS);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for _ in 0..WORKERS {
+            let service = Arc::clone(&service);
+            let receiver = Arc::clone(&receiver);
+            thread::spawn(move || loop {
+                let stream = match receiver.lock().unwrap().recv() {
+                    Ok(stream) => stream,
+                    Err(_) => return, // serve has stopped
+                };
+                // one broken client shouldn't take the whole service down
+                if let Err(e) = handle_connection(&service, stream) {
+                    eprintln!("Connection error: {}", e);
+                }
+            });
+        }
+
+        for stream in listener.incoming() {
+            let stream = stream?;
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            if sender.send(stream).is_err() {
+                return Err(io::Error::other("every worker has stopped"));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn handle(&mut self, request: &Request) -> Response {
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+
+        let body = match parse_body(&request.body) {
+            Ok(body) => body,
+            Err(e) => return Response::error(400, &e),
+        };
+
+        match (request.method.as_str(), &segments[..]) {
+            ("POST", ["posts"]) => self.create(&body),
+            ("GET", ["posts"]) => {
+                let ids = self.posts.keys().map(|id| Json::from(id.to_string().as_str())).collect();
+                Response::json(200, &[("ids", Json::Array(ids))])
+            }
+            (method, ["posts", id, rest @ ..]) => {
+                let id = id.parse::<u64>().ok();
+                let post = match id.and_then(|id| self.posts.get_mut(&id)) {
+                    Some(post) => post,
+                    None => return Response::error(404, "no such post"),
+                };
+                handle_post(id.unwrap(), post, method, rest, &body)
+            }
+            _ => Response::error(404, "no such path"),
+        }
+    }
+
+    fn create(&mut self, body: &BTreeMap<String, Json>) -> Response {
+        let required = match body.get("required_approvals") {
+            None => NonZeroUsize::new(1),
+            Some(Json::Number(n)) if *n >= 0.0 && n.fract() == 0.0 => NonZeroUsize::new(*n as usize),
+            Some(_) => None,
+        };
+        let required = match required {
+            Some(required) => required,
+            None => return Response::error(400, "required_approvals must be a whole number of at least 1"),
+        };
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let post = self.posts.entry(id).or_insert_with(|| Post::with_required_approvals(required));
+
+        Response { status: 201, body: describe(id, post).to_string() }
+    }
+}
+
+impl Default for BlogService {
+    fn default() -> BlogService {
+        BlogService::new()
+    }
+}
+
+// The lock is only held while the request is handled, not while a slow client is read from or written to
+fn handle_connection(service: &Mutex<BlogService>, stream: TcpStream) -> io::Result<()> {
+    let request = read_request(&mut BufReader::new(&stream));
+    let response = match &request {
+        Ok(request) => service.lock().unwrap().handle(request),
+        Err(e) => Response::error(status_for(e), &e.to_string()),
+    };
+    write_response(&stream, &response)?;
+
+    // closing with some of the request still unread resets the connection, which can lose the response on the way,
+    // so let the client finish sending first - up to a point
+    if request.is_err() {
+        stream.shutdown(Shutdown::Write)?;
+        io::copy(&mut (&stream).take(MAX_BODY as u64), &mut io::sink())?;
+    }
+    Ok(())
+}
+
+// A request read_request won't read any further, and the status to answer it with
+#[derive(Debug)]
+struct Refused {
+    status: u16,
+    message: String,
+}
+
+impl fmt::Display for Refused {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for Refused {}
+
+fn refuse(status: u16, message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, Refused { status, message })
+}
+
+fn status_for(error: &io::Error) -> u16 {
+    if let Some(refused) = error.get_ref().and_then(|e| e.downcast_ref::<Refused>()) {
+        return refused.status;
+    }
+    match error.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => 408,
+        _ => 400,
+    }
+}
+
+fn handle_post(id: u64, post: &mut Post, method: &str, rest: &[&str], body: &BTreeMap<String, Json>) -> Response {
+    let action = match (method, rest) {
+        ("GET", []) => return Response { status: 200, body: describe(id, post).to_string() },
+        ("GET", ["content"]) => {
+            if post.status() != Status::Published {
+                return Response::error(409, &format!("post {} is {}, not published", id, post.status()));
+            }
+            return Response::json(200, &[("content", Json::from(post.content()))]);
+        }
+        ("GET", ["html"]) => {
+            return match (post.render_html(), post.excerpt(EXCERPT_LENGTH)) {
+                (Some(html), Some(excerpt)) => {
+                    Response::json(200, &[("html", Json::from(html.as_str())), ("excerpt", Json::from(excerpt.as_str()))])
+                }
+                _ => Response::error(409, &format!("post {} is {}, not published", id, post.status())),
+            };
+        }
+        ("POST", ["text"]) => {
+            let text = match string_field(body, "text") {
+                Ok(text) => text,
+                Err(response) => return response,
+            };
+            if post.status() != Status::Draft {
+                return Response::error(409, &format!("can't add text to a post that is {}", post.status()));
+            }
+            post.add_text(text);
+            return Response { status: 200, body: describe(id, post).to_string() };
+        }
+        ("POST", ["request_review"]) => Action::RequestReview,
+        ("POST", ["approve"]) => Action::Approve,
+        ("POST", ["reject"]) => Action::Reject,
+        (_, []) | (_, ["content"]) | (_, ["html"]) | (_, ["text"]) | (_, ["request_review"]) | (_, ["approve"]) | (_, ["reject"]) => {
+            return Response::error(405, "method not allowed")
+        }
+        _ => return Response::error(404, "no such path"),
+    };
+
+    let actor = match string_field(body, "actor") {
+        Ok(actor) => actor,
+        Err(response) => return response,
+    };
+    let comment = match body.get("comment") {
+        Some(Json::String(comment)) => Some(comment.as_str()),
+        _ => None,
+    };
+
+    match post.transition(action, actor, comment) {
+        Ok(_) => Response { status: 200, body: describe(id, post).to_string() },
+        Err(e) => Response::error(409, &e.to_string()),
+    }
+}
+
+fn string_field<'a>(body: &'a BTreeMap<String, Json>, name: &str) -> Result<&'a str, Response> {
+    match body.get(name) {
+        Some(Json::String(value)) => Ok(value),
+        _ => Err(Response::error(400, &format!("expected a string field \"{}\"", name))),
+    }
+}
+
+fn describe(id: u64, post: &Post) -> Json {
+    Json::object(&[
+        ("id", Json::from(id.to_string().as_str())),
+        ("status", Json::from(post.status().to_string().as_str())),
+        ("content", Json::from(post.content())),
+        ("transitions", Json::Number(post.history().len() as f64)),
+    ])
+}
+
+// ======= HTTP =======
+
+pub fn read_request<R: BufRead>(reader: &mut R) -> io::Result<Request> {
+    let bad = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    let line = read_line(reader)?.ok_or_else(|| refuse(400, format!("the request line is over {} bytes", MAX_LINE)))?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or_else(|| bad("empty request"))?.to_string();
+    let path = parts.next().ok_or_else(|| bad("request line has no path"))?.to_string();
+
+    let mut content_length = 0;
+    for count in 0.. {
+        let header = read_line(reader)?.ok_or_else(|| refuse(431, format!("a header is over {} bytes", MAX_LINE)))?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if count == MAX_HEADERS {
+            return Err(refuse(431, format!("there are more than {} headers", MAX_HEADERS)));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| bad("bad Content-Length"))?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY {
+        return Err(refuse(413, format!("the body can be at most {} bytes", MAX_BODY)));
+    }
+    // take stops a client that sends more than it said it would, and the buffer only grows as the bytes turn up
+    let mut body = Vec::new();
+    reader.take(content_length as u64).read_to_end(&mut body)?;
+    if body.len() < content_length {
+        return Err(bad("the body is shorter than its Content-Length"));
+    }
+    let body = String::from_utf8(body).map_err(|_| bad("body isn't UTF-8"))?;
+
+    Ok(Request { method, path, body })
+}
+
+// One line of the request's head, or None if it's longer than MAX_LINE. Reading stops there rather than going on for
+// as long as the client keeps sending.
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.by_ref().take(MAX_LINE as u64).read_line(&mut line)?;
+    if line.len() == MAX_LINE && !line.ends_with('\n') {
+        return Ok(None);
+    }
+    Ok(Some(line))
+}
+
+pub fn write_response<W: Write>(mut writer: W, response: &Response) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        response.body.len(),
+        response.body
+    )?;
+    writer.flush()
+}
+
+// ======= JSON =======
+//
+// Just enough JSON for this service: any value can be written out, but request bodies must be a single flat object.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn object(fields: &[(&str, Json)]) -> Json {
+        Json::Object(fields.iter().map(|(k, v)| (k.to_string(), v.clone())).collect())
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Json {
+        Json::String(s.to_string())
+    }
+}
+
+impl std::fmt::Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_json_string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_json_string(f: &mut std::fmt::Formatter, s: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+// An empty body counts as an empty object
+fn parse_body(body: &str) -> Result<BTreeMap<String, Json>, String> {
+    if body.trim().is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let mut parser = Parser { chars: body.chars().collect(), pos: 0 };
+    let fields = parser.object()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.chars.len() {
+        return Err(String::from("unexpected text after the JSON object"));
+    }
+    Ok(fields)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn object(&mut self) -> Result<BTreeMap<String, Json>, String> {
+        let mut fields = BTreeMap::new();
+        self.expect('{')?;
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(fields);
+        }
+
+        loop {
+            let key = self.string()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            fields.insert(key, value);
+
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(fields),
+                _ => return Err(String::from("expected ',' or '}' in JSON object")),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some('"') => self.string().map(Json::String),
+            Some('t') => self.literal("true", Json::Bool(true)),
+            Some('f') => self.literal("false", Json::Bool(false)),
+            Some('n') => self.literal("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                while self.chars.get(self.pos).is_some_and(|c| "+-.eE".contains(*c) || c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                text.parse().map(Json::Number).map_err(|_| format!("bad number: {}", text))
+            }
+            _ => Err(String::from("expected a string, number, true, false or null (nested values aren't supported)")),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() {
+            if self.chars.get(self.pos) != Some(&expected) {
+                return Err(format!("expected {}", word));
+            }
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            let c = self.chars.get(self.pos).copied().ok_or("unterminated string")?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escaped = self.chars.get(self.pos).copied().ok_or("unterminated string")?;
+                    self.pos += 1;
+                    match escaped {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // characters outside the BMP come as a surrogate pair, like \uD83D\uDE00
+                            if (0xd800..0xdc00).contains(&code) && self.chars[self.pos..].starts_with(&['\\', 'u']) {
+                                let before = self.pos;
+                                self.pos += 2;
+                                match self.hex4()? {
+                                    low @ 0xdc00..=0xdfff => code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00),
+                                    _ => self.pos = before, // not the other half, so it's an escape of its own
+                                }
+                            }
+                            // and a surrogate on its own isn't a character at all
+                            out.push(std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        other => out.push(other), // \" \\ and \/
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    // The four hex digits of a \u escape
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.chars.get(self.pos..self.pos + 4).filter(|digits| digits.iter().all(char::is_ascii_hexdigit));
+        let code = match digits {
+            Some(digits) => u32::from_str_radix(&digits.iter().collect::<String>(), 16).unwrap(),
+            None => return Err(String::from("bad \\u escape")),
+        };
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(format!("expected '{}' in JSON", expected)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request { method: method.to_string(), path: path.to_string(), body: body.to_string() }
+    }
+
+    #[test]
+    fn it_parses_flat_json_objects() {
+        let body = parse_body(r#" {"actor": "al\"ice!", "n": 2, "ok": true, "none": null} "#).unwrap();
+
+        assert_eq!(body.get("actor"), Some(&Json::from("al\"ice!")));
+        assert_eq!(body.get("n"), Some(&Json::Number(2.0)));
+        assert_eq!(body.get("ok"), Some(&Json::Bool(true)));
+        assert!(parse_body(r#"{"nested": {"a": 1}}"#).is_err());
+        assert!(parse_body(r#"{"a": 1} trailing"#).is_err());
+    }
+
+    #[test]
+    fn unicode_escapes_take_four_hex_digits_and_pair_surrogates() {
+        let text = |json: &str| parse_body(&format!(r#"{{"text": "{}"}}"#, json)).map(|body| body["text"].clone());
+
+        assert_eq!(text(r"\u00e9 \uD83D\uDE00"), Ok(Json::from("é 😀")));
+        assert_eq!(text(r"\uD83D!"), Ok(Json::from("\u{fffd}!")));
+        assert_eq!(text(r"\uD83D\u0041"), Ok(Json::from("\u{fffd}A")));
+        assert_eq!(text(r"\u+123"), Err(String::from("bad \\u escape")));
+        assert_eq!(text(r"\u12"), Err(String::from("bad \\u escape")));
+    }
+
+    #[test]
+    fn it_escapes_json_output() {
+        let json = Json::object(&[("text", Json::from("line\n\"quoted\"\t\u{1}"))]);
+
+        assert_eq!(json.to_string(), r#"{"text":"line\n\"quoted\"\t\u0001"}"#);
+    }
+
+    #[test]
+    fn it_maps_requests_onto_the_workflow() {
+        let mut service = BlogService::new();
+
+        assert_eq!(service.handle(&request("POST", "/posts", "")).status, 201);
+        assert_eq!(service.handle(&request("POST", "/posts/1/text", r#"{"text": "Hi"}"#)).status, 200);
+        assert_eq!(service.handle(&request("POST", "/posts/1/approve", r#"{"actor": "alice"}"#)).status, 409);
+        assert_eq!(service.handle(&request("POST", "/posts/1/approve", "{}")).status, 400);
+        assert_eq!(service.handle(&request("GET", "/posts/2", "")).status, 404);
+        assert_eq!(service.handle(&request("DELETE", "/posts/1", "")).status, 405);
+        assert_eq!(service.handle(&request("GET", "/nowhere", "")).status, 404);
+    }
+
+    #[test]
+    fn posts_need_at_least_one_approval() {
+        let mut service = BlogService::new();
+
+        assert_eq!(service.handle(&request("POST", "/posts", r#"{"required_approvals": 0}"#)).status, 400);
+        assert_eq!(service.handle(&request("POST", "/posts", r#"{"required_approvals": 1.5}"#)).status, 400);
+        assert_eq!(service.handle(&request("POST", "/posts", r#"{"required_approvals": 2}"#)).status, 201);
+    }
+
+    #[test]
+    fn it_reads_a_request_with_a_body() {
+        let raw = "POST /posts/1/text HTTP/1.1\r\nHost: localhost\r\ncontent-length: 15\r\n\r\n{\"text\": \"Hi\"}\n";
+
+        assert_eq!(
+            read_request(&mut raw.as_bytes()).unwrap(),
+            request("POST", "/posts/1/text", "{\"text\": \"Hi\"}\n")
+        );
+    }
+
+    #[test]
+    fn it_refuses_bodies_that_are_too_big_or_cut_short() {
+        let huge = format!("POST /posts HTTP/1.1\r\nContent-Length: {}\r\n\r\n{{}}", MAX_BODY + 1);
+        let error = read_request(&mut huge.as_bytes()).unwrap_err();
+        assert_eq!(status_for(&error), 413);
+        assert_eq!(Response::error(413, "").reason(), "Payload Too Large");
+
+        let short = "POST /posts HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}";
+        assert_eq!(status_for(&read_request(&mut short.as_bytes()).unwrap_err()), 400);
+    }
+
+    #[test]
+    fn a_stalled_client_doesnt_hold_up_the_others() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || BlogService::new().serve(listener));
+
+        // connects, then never sends anything
+        let _stalled = TcpStream::connect(address).unwrap();
+
+        let mut client = TcpStream::connect(address).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        client.write_all(b"POST /posts HTTP/1.1\r\nContent-Length: 0\r\n\r\n").unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 201 Created\r\n"), "{}", response);
+    }
+}
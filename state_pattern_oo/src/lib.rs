@@ -1,3 +1,25 @@
+use std::error::Error;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub mod clock;
+pub mod history;
+pub mod http;
+pub mod machine;
+pub mod markdown;
+pub mod scheduler;
+pub mod storage;
+pub mod typestate;
+pub mod workflow;
+
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use history::{History, Transition};
+
+use workflow::PostData;
+
 /**
 
 The State trait defines the behavior shared by different post states, and the Draft, PendingReview, and Published states will all implement the State trait. For now, the trait doesn’t have any methods, and we’ll start by defining just the Draft state because that is the state we want a post to start in.
@@ -5,87 +27,327 @@ The State trait defines the behavior shared by different post states, and the Dr
 When we create a new Post, we set its state field to a Some value that holds a Box. This Box points to a new instance of the Draft struct. This ensures whenever we create a new instance of Post, it will start out as a draft. Because the state field of Post is private, there is no way to create a Post in any other state! In the Post::new function, we set the content field to a new, empty String.
 
  */
-
 pub struct Post {
     state: Option<Box<dyn State>>,
+    data: PostData, // what the rules in workflow::blog_machine go on - the approvals so far, any embargo
     content: String,
+    history: History,
+    clock: Arc<dyn Clock>, // where "now" comes from, for the history and for embargoes - swapped out in tests
 }
 
-// The logic related to the rules lives in the state objects rather than being scattered throughout Post.
+// The rules live in workflow::blog_machine and how a post behaves in each state lives in the state objects, rather
+// than either being scattered throughout Post.
 impl Post {
     pub fn new() -> Post {
+        Post::with_required_approvals(NonZeroUsize::new(1).unwrap())
+    }
+
+    // A post that needs `required` different people to approve it before it's published.
+    pub fn with_required_approvals(required: NonZeroUsize) -> Post {
         Post {
-            state: Some(Box::new(Draft {})),
+            state: Some(Box::new(Draft)),
+            data: PostData::new(required.get()),
             content: String::new(),
+            history: History::new(),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    // A post already in `status`, with the default single approval - used when a post is rebuilt from somewhere else.
+    fn restored(status: Status, content: String) -> Post {
+        let (state, data) = restore_state(status, 1, Vec::new(), None).unwrap();
+        Post {
+            state: Some(state),
+            data,
+            content,
+            history: History::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    // Whether text can be added is up to the state too - only a Draft lets it through.
     pub fn add_text(&mut self, text: &str) {
-        self.content.push_str(text);
+        self.state.as_ref().unwrap().add_text(&mut self.content, text);
     }
 
     // Even after we’ve called add_text and added some content to our post, we still want the content method to return an empty string slice because the post is still in the draft state
 
     pub fn content(&self) -> &str {
-        self.state.as_ref().unwrap().content(&self) // Because the goal is to keep all these rules inside the structs that implement State, we call a content method on the value in state and pass the post instance (that is, self) as an argument. Then we return the value that is returned from using the content method on the state value... We call the as_ref method on the Option because we want a reference to the value inside the Option rather than ownership of the value. Because state is an Option<Box<dyn State>>, when we call as_ref, an Option<&Box<dyn State>> is returned. If we didn’t call as_ref, we would get an error because we can’t move state out of the borrowed &self of the function parameter.
+        self.state.as_ref().unwrap().content(self) // Because the goal is to keep all these rules inside the structs that implement State, we call a content method on the value in state and pass the post instance (that is, self) as an argument. Then we return the value that is returned from using the content method on the state value... We call the as_ref method on the Option because we want a reference to the value inside the Option rather than ownership of the value. Because state is an Option<Box<dyn State>>, when we call as_ref, an Option<&Box<dyn State>> is returned. If we didn’t call as_ref, we would get an error because we can’t move state out of the borrowed &self of the function parameter.
+    }
+
+    // The content rendered from Markdown to HTML - see markdown.rs. Only a published post has anything to render.
+    pub fn render_html(&self) -> Option<String> {
+        if self.status() != Status::Published {
+            return None;
+        }
+        Some(markdown::to_html(&self.content))
+    }
+
+    // A plain-text summary of a published post, at most `max_chars` long, for listings and feeds.
+    pub fn excerpt(&self, max_chars: usize) -> Option<String> {
+        if self.status() != Status::Published {
+            return None;
+        }
+        Some(markdown::excerpt(&self.content, max_chars))
+    }
+
+    pub fn status(&self) -> Status {
+        self.state.as_ref().unwrap().status()
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    // When a scheduled post goes live (or will once it's approved), if it has an embargo at all
+    pub fn publish_at(&self) -> Option<SystemTime> {
+        self.data.publish_at
+    }
+
+    pub fn request_review(&mut self, actor: &str) -> Result<Status, TransitionError> {
+        self.transition(Action::RequestReview, actor, None)
+    }
+
+    pub fn approve(&mut self, approver: &str) -> Result<Status, TransitionError> {
+        self.transition(Action::Approve, approver, None)
+    }
+
+    pub fn reject(&mut self, actor: &str) -> Result<Status, TransitionError> {
+        self.transition(Action::Reject, actor, None)
+    }
+
+    // Sets an embargo on a post under review: once it's approved it waits in Scheduled until `publish_at`.
+    pub fn schedule(&mut self, actor: &str, publish_at: SystemTime) -> Result<Status, TransitionError> {
+        self.transition(Action::Schedule(publish_at), actor, None)
+    }
+
+    // Moves a scheduled post to Published if its embargo is over - see scheduler.rs for doing this to lots of posts.
+    pub fn publish(&mut self, actor: &str) -> Result<Status, TransitionError> {
+        self.transition(Action::Publish, actor, None)
+    }
+
+    // Every change of state goes through here so it can be written down in the history - blog_machine decides whether
+    // the action is allowed, and which state the post moves into if it is.
+    pub fn transition(&mut self, action: Action, actor: &str, comment: Option<&str>) -> Result<Status, TransitionError> {
+        let s = self.state.take().unwrap(); //To consume the old state, the request_review method needs to take ownership of the state value. This is where the Option in the state field of Post comes in: we call the take method to take the Some value out of the state field and leave a None in its place, because Rust doesn’t let us have unpopulated fields in structs. This lets us move the state value out of Post rather than borrowing it... We need to set state to None temporarily rather than setting it directly with code like self.state = self.state.request_review(); to get ownership of the state value. This ensures Post can’t use the old state value after we’ve transformed it into a new state.
+        let from = s.status();
+        let now = self.clock.now();
+
+        match workflow::fire(from, action, actor, now, &mut self.data) {
+            Ok(to) => {
+                self.state = Some(state_for(to));
+                self.history.record(Transition {
+                    from,
+                    to,
+                    action,
+                    actor: actor.to_string(),
+                    timestamp: now,
+                    comment: comment.map(String::from),
+                });
+                Ok(to)
+            }
+            Err(error) => {
+                self.state = Some(s);
+                Err(error)
+            }
+        }
+    }
+}
+
+impl Default for Post {
+    fn default() -> Post {
+        Post::new()
+    }
+}
+
+// The states a post can be in, for callers that need to know without being able to get at the State objects themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    Draft,
+    PendingReview,
+    Scheduled, // approved, but embargoed until a publish-at time
+    Published,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Status, String> {
+        match name {
+            "Draft" => Ok(Status::Draft),
+            "PendingReview" => Ok(Status::PendingReview),
+            "Scheduled" => Ok(Status::Scheduled),
+            "Published" => Ok(Status::Published),
+            _ => Err(format!("unknown state: {}", name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    RequestReview,
+    Approve,
+    Reject,
+    Schedule(SystemTime), // the publish-at time
+    Publish,
+}
+
+// Just the name of the action - the time a post is scheduled for is left out
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Action::RequestReview => "RequestReview",
+            Action::Approve => "Approve",
+            Action::Reject => "Reject",
+            Action::Schedule(_) => "Schedule",
+            Action::Publish => "Publish",
+        };
+        write!(f, "{}", name)
     }
+}
+
+impl FromStr for Action {
+    type Err = String;
 
-    pub fn request_review(&mut self) {
-        if let Some(s) = self.state.take() { //To consume the old state, the request_review method needs to take ownership of the state value. This is where the Option in the state field of Post comes in: we call the take method to take the Some value out of the state field and leave a None in its place, because Rust doesn’t let us have unpopulated fields in structs. This lets us move the state value out of Post rather than borrowing it... We need to set state to None temporarily rather than setting it directly with code like self.state = self.state.request_review(); to get ownership of the state value. This ensures Post can’t use the old state value after we’ve transformed it into a new state.
-            self.state = Some(s.request_review())
+    fn from_str(name: &str) -> Result<Action, String> {
+        match name {
+            "RequestReview" => Ok(Action::RequestReview),
+            "Approve" => Ok(Action::Approve),
+            "Reject" => Ok(Action::Reject),
+            "Publish" => Ok(Action::Publish),
+            _ => Err(format!("unknown action: {}", name)),
         }
     }
+}
 
-    pub fn approve(&mut self) {
-        if let Some(s) = self.state.take() {
-            self.state = Some(s.approve())
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransitionError {
+    NotAllowed { action: Action, status: Status }, // e.g. approving a post that's still a draft
+    AlreadyApproved { approver: String },
+    Embargoed { until: SystemTime }, // publishing a scheduled post before its time
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransitionError::NotAllowed { action, status } => write!(f, "can't {} a post that is {}", action, status),
+            TransitionError::AlreadyApproved { approver } => write!(f, "{} has already approved this post", approver),
+            TransitionError::Embargoed { until } => {
+                let since_epoch = until.duration_since(UNIX_EPOCH).unwrap_or_default();
+                write!(f, "the post is embargoed until {} seconds after the unix epoch", since_epoch.as_secs())
+            }
         }
     }
 }
 
-trait State {
-    fn request_review(self: Box<Self>) -> Box<dyn State>; // Note that rather than having self, &self, or &mut self as the first parameter of the method, we have self: Box<Self>. This syntax means the method is only valid when called on a Box holding the type. This syntax takes ownership of Box<Self>, invalidating the old state so the state value of the Post can transform into a new state.
+impl Error for TransitionError {}
 
-    fn approve(self: Box<Self>) -> Box<dyn State>;
+// Builds the State object and rule data for a post read back from storage - the inverse of status() and the data
+// saved alongside it. Anything blog_machine could never have got a post into is refused.
+fn restore_state(status: Status, required_approvals: usize, approvals: Vec<String>, publish_at: Option<SystemTime>) -> Result<(Box<dyn State>, PostData), String> {
+    if required_approvals == 0 {
+        return Err(String::from("a post needs at least one approval"));
+    }
+    if status != Status::PendingReview && !approvals.is_empty() {
+        return Err(format!("a post that is {} can't have approvals", status));
+    }
+    if publish_at.is_some() && status != Status::PendingReview && status != Status::Scheduled {
+        return Err(format!("a post that is {} can't have a publish-at time", status));
+    }
 
-    fn content<'a>(&self, post: &'a Post) -> &'a str { // We add a default implementation for the content method that returns an empty string slice. That means we don’t need to implement content on the Draft and PendingReview structs. 
+    match status {
+        Status::PendingReview => {
+            if approvals.iter().enumerate().any(|(i, a)| approvals[..i].contains(a)) {
+                return Err(String::from("the same person can't approve a post twice"));
+            }
+            if approvals.len() >= required_approvals {
+                return Err(String::from("a post with enough approvals would already be published"));
+            }
+        }
+        Status::Scheduled if publish_at.is_none() => return Err(String::from("a scheduled post needs a publish-at time")),
+        _ => {}
+    }
+
+    let mut data = PostData::new(required_approvals);
+    data.approvals = approvals;
+    data.publish_at = publish_at;
+    Ok((state_for(status), data))
+}
+
+// Which state is which is decided by the rules in blog_machine - what's left to the state objects is how a post
+// behaves while it's in each one. They're Send + Sync so posts can be shared between the HTTP service's workers.
+trait State: Send + Sync {
+    fn status(&self) -> Status;
+
+    fn add_text(&self, _content: &mut String, _text: &str) {} // By default the text is dropped - only Draft overrides this to actually add it.
+
+    fn content<'a>(&self, _post: &'a Post) -> &'a str { // We add a default implementation for the content method that returns an empty string slice. That means we don’t need to implement content on the Draft and PendingReview structs. 
         ""
     }
 }
 
-struct Draft {}
+fn state_for(status: Status) -> Box<dyn State> {
+    match status {
+        Status::Draft => Box::new(Draft),
+        Status::PendingReview => Box::new(PendingReview),
+        Status::Scheduled => Box::new(Scheduled),
+        Status::Published => Box::new(Published),
+    }
+}
+
+struct Draft;
 
 impl State for Draft {
-    fn request_review(self: Box<Self>) -> Box<dyn State> {
-        Box::new(PendingReview {}) // The request_review method on Draft needs to return a new, boxed instance of a new PendingReview struct, which represents the state when a post is waiting for a review.
+    fn status(&self) -> Status {
+        Status::Draft
     }
 
-    fn approve(self: Box<Self>) -> Box<dyn State> {
-        self
+    fn add_text(&self, content: &mut String, text: &str) {
+        content.push_str(text);
     }
 }
 
-struct PendingReview {}
+struct PendingReview;
 
 impl State for PendingReview {
-    fn request_review(self: Box<Self>) -> Box<dyn State> {
-        self // The PendingReview struct also implements the request_review method but doesn’t do any transformations. Rather, it returns itself, because when we request a review on a post already in the PendingReview state, it should stay in the PendingReview state.
+    fn status(&self) -> Status {
+        Status::PendingReview
+    }
+}
+
+// Approved, but held back until its publish-at time. The content shows up as soon as that time has passed, even if
+// nothing has moved the post on to Published yet - the scheduler only catches the state up with the clock.
+struct Scheduled;
+
+impl State for Scheduled {
+    fn status(&self) -> Status {
+        Status::Scheduled
     }
 
-    fn approve(self: Box<Self>) -> Box<dyn State> {
-        Box::new(Published {})
+    fn content<'a>(&self, post: &'a Post) -> &'a str {
+        match post.data.publish_at {
+            Some(publish_at) if post.clock.now() >= publish_at => &post.content,
+            _ => "",
+        }
     }
 }
 
-struct Published {}
+struct Published;
 
 impl State for Published {
-    fn request_review(self: Box<Self>) -> Box<dyn State> {
-        self
-    }
-
-    fn approve(self: Box<Self>) -> Box<dyn State> {
-        self
+    fn status(&self) -> Status {
+        Status::Published
     }
 
     // The Published struct will override the content method and return the value in post.content.
@@ -175,4 +437,165 @@ The DraftPost struct has an add_text method, so we can add text to content as be
 // So how do we get a published post? We want to enforce the rule that a draft post has to be reviewed and approved before it can be published. A post in the pending review state should still not display any content. Let’s implement these constraints by adding another struct, PendingReviewPost, defining the request_review method on DraftPost to return a PendingReviewPost, and defining an approve method on PendingReviewPost to return a Post.
 
 // The request_review and approve methods take ownership of self, thus consuming the DraftPost and PendingReviewPost instances and transforming them into a PendingReviewPost and a published Post, respectively. This way, we won’t have any lingering DraftPost instances after we’ve called request_review on them, and so forth. The PendingReviewPost struct doesn’t have a content method defined on it, so attempting to read its content results in a compiler error, as with DraftPost. Because the only way to get a published Post instance that does have a content method defined is to call the approve method on a PendingReviewPost, and the only way to get a PendingReviewPost is to call the request_review method on a DraftPost, we’ve now encoded the blog post workflow into the type system.
- */
\ No newline at end of file
+ */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn it_publishes_after_one_approval_by_default() {
+        let mut post = Post::new();
+        post.add_text("I ate a salad for lunch today");
+        assert_eq!("", post.content());
+
+        assert_eq!(post.request_review("bertie"), Ok(Status::PendingReview));
+        assert_eq!("", post.content());
+
+        assert_eq!(post.approve("alice"), Ok(Status::Published));
+        assert_eq!("I ate a salad for lunch today", post.content());
+    }
+
+    #[test]
+    fn only_published_posts_are_rendered() {
+        let mut post = Post::new();
+        post.add_text("# Lunch\n\nI ate a *salad* for lunch today");
+        post.request_review("bertie").unwrap();
+        assert_eq!(post.render_html(), None);
+        assert_eq!(post.excerpt(20), None);
+
+        post.approve("alice").unwrap();
+        assert_eq!(post.render_html().unwrap(), "<h1>Lunch</h1>\n<p>I ate a <em>salad</em> for lunch today</p>\n");
+        assert_eq!(post.excerpt(20).unwrap(), "Lunch I ate a salad…");
+    }
+
+    #[test]
+    fn it_needs_the_configured_number_of_distinct_approvals() {
+        let mut post = Post::with_required_approvals(NonZeroUsize::new(2).unwrap());
+        post.add_text("Two heads are better than one");
+        post.request_review("bertie").unwrap();
+
+        assert_eq!(post.approve("alice"), Ok(Status::PendingReview));
+        assert_eq!(
+            post.approve("alice"),
+            Err(TransitionError::AlreadyApproved { approver: String::from("alice") })
+        );
+        assert_eq!("", post.content());
+
+        assert_eq!(post.approve("bob"), Ok(Status::Published));
+        assert_eq!("Two heads are better than one", post.content());
+    }
+
+    #[test]
+    fn rejecting_sends_the_post_back_to_draft_and_clears_approvals() {
+        let mut post = Post::with_required_approvals(NonZeroUsize::new(2).unwrap());
+        post.add_text("First try.");
+        post.request_review("bertie").unwrap();
+        post.approve("alice").unwrap();
+
+        assert_eq!(post.reject("bob"), Ok(Status::Draft));
+        post.add_text(" Second try.");
+        post.request_review("bertie").unwrap();
+        post.approve("bob").unwrap();
+        assert_eq!("", post.content());
+
+        post.approve("alice").unwrap();
+        assert_eq!("First try. Second try.", post.content());
+    }
+
+    #[test]
+    fn text_can_only_be_added_to_a_draft() {
+        let mut post = Post::new();
+        post.add_text("Draft text.");
+        post.request_review("bertie").unwrap();
+        post.add_text(" Sneaked in during review.");
+        post.approve("alice").unwrap();
+        post.add_text(" Sneaked in after publishing.");
+
+        assert_eq!("Draft text.", post.content());
+    }
+
+    #[test]
+    fn invalid_transitions_are_errors_that_leave_the_post_alone() {
+        let mut post = Post::new();
+        post.add_text("Not ready yet");
+
+        assert_eq!(
+            post.approve("alice"),
+            Err(TransitionError::NotAllowed { action: Action::Approve, status: Status::Draft })
+        );
+        assert_eq!(
+            post.reject("alice").unwrap_err().to_string(),
+            "can't Reject a post that is Draft"
+        );
+        assert_eq!(post.status(), Status::Draft);
+        assert!(post.history().is_empty());
+
+        post.request_review("bertie").unwrap();
+        assert!(post.request_review("bertie").is_err());
+        post.approve("alice").unwrap();
+        assert!(post.approve("bob").is_err());
+        assert_eq!("Not ready yet", post.content());
+    }
+
+    #[test]
+    fn it_records_the_history_of_transitions() {
+        let mut post = Post::with_required_approvals(NonZeroUsize::new(2).unwrap());
+        post.request_review("bertie").unwrap();
+        post.transition(Action::Reject, "alice", Some("needs a title")).unwrap();
+        post.request_review("bertie").unwrap();
+        post.approve("alice").unwrap();
+        post.approve("bob").unwrap();
+
+        let history = post.history();
+        assert_eq!(history.len(), 5);
+
+        let reject = &history.entries()[1];
+        assert_eq!((reject.from, reject.to, reject.action), (Status::PendingReview, Status::Draft, Action::Reject));
+        assert_eq!(reject.actor, "alice");
+        assert_eq!(reject.comment.as_deref(), Some("needs a title"));
+
+        assert_eq!(history.by_actor("bertie").count(), 2);
+        assert_eq!(history.entering(Status::Published).map(|t| t.actor.as_str()).collect::<Vec<_>>(), vec!["bob"]);
+        assert_eq!(history.last().unwrap().to, Status::Published);
+        assert!(history.entries().windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    }
+
+    #[test]
+    fn a_scheduled_post_stays_hidden_until_its_embargo_passes() {
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let publish_at = SystemTime::UNIX_EPOCH + Duration::from_secs(3600);
+
+        let mut post = Post::new();
+        post.set_clock(clock.clone());
+        post.add_text("Embargoed news");
+        post.request_review("bertie").unwrap();
+        assert_eq!(post.schedule("bertie", publish_at), Ok(Status::PendingReview));
+
+        assert_eq!(post.approve("alice"), Ok(Status::Scheduled));
+        assert_eq!(post.publish_at(), Some(publish_at));
+        assert_eq!(post.content(), "");
+        assert_eq!(post.publish("scheduler"), Err(TransitionError::Embargoed { until: publish_at }));
+        assert_eq!(
+            post.publish("scheduler").unwrap_err().to_string(),
+            "the post is embargoed until 3600 seconds after the unix epoch"
+        );
+
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(post.content(), "Embargoed news");
+        assert_eq!(post.publish("scheduler"), Ok(Status::Published));
+        assert_eq!(post.history().last().unwrap().timestamp, publish_at);
+    }
+
+    #[test]
+    fn only_posts_under_review_can_be_scheduled() {
+        let mut post = Post::new();
+        assert!(post.schedule("bertie", SystemTime::UNIX_EPOCH).is_err());
+
+        post.request_review("bertie").unwrap();
+        post.schedule("bertie", SystemTime::now() + Duration::from_secs(60)).unwrap();
+        post.approve("alice").unwrap();
+        assert_eq!(post.reject("alice"), Ok(Status::Draft));
+        assert_eq!(post.publish_at(), None);
+    }
+}
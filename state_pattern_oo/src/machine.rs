@@ -0,0 +1,237 @@
+// A reusable state machine: the states and events are plain Copy values (usually enums), and everything a workflow
+// needs to remember besides which state it's in lives in a context value `C` that guards, actions and hooks work on.
+//
+// The machine itself is only the definition - it doesn't know which state anything is in. Callers keep the current
+// state next to their context and hand both to `fire`, so one machine can drive any number of posts, orders or tickets.
+//
+// When an event is fired:
+//   1. the rules for (current state, event) are tried in the order they were added, and the first whose guard passes wins
+//   2. the exit hooks of the old state run, then the rule's action, then the entry hooks of the new state
+// A rule that goes from a state back to the same state only runs its action - the state is never left, so it isn't re-entered.
+
+use std::error::Error;
+use std::fmt;
+
+type Guard<C> = Box<dyn Fn(&C) -> bool>;
+type Hook<C> = Box<dyn Fn(&mut C)>;
+
+pub struct Rule<S, E, C> {
+    from: S,
+    event: E,
+    to: S,
+    guard: Option<Guard<C>>,
+    action: Option<Hook<C>>,
+}
+
+impl<S, E, C> Rule<S, E, C> {
+    pub fn new(from: S, event: E, to: S) -> Rule<S, E, C> {
+        Rule { from, event, to, guard: None, action: None }
+    }
+
+    // Only take this rule when `guard` says so
+    pub fn guard<F: Fn(&C) -> bool + 'static>(mut self, guard: F) -> Rule<S, E, C> {
+        self.guard = Some(Box::new(guard));
+        self
+    }
+
+    // Something to do to the context when this rule is taken
+    pub fn action<F: Fn(&mut C) + 'static>(mut self, action: F) -> Rule<S, E, C> {
+        self.action = Some(Box::new(action));
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MachineError<S, E> {
+    NoTransition { state: S, event: E }, // nothing handles this event in this state
+    Blocked { state: S, event: E },      // there are rules for it, but every guard said no
+}
+
+impl<S: fmt::Debug, E: fmt::Debug> fmt::Display for MachineError<S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MachineError::NoTransition { state, event } => write!(f, "{:?} can't happen in {:?}", event, state),
+            MachineError::Blocked { state, event } => write!(f, "{:?} was refused in {:?}", event, state),
+        }
+    }
+}
+
+impl<S: fmt::Debug, E: fmt::Debug> Error for MachineError<S, E> {}
+
+pub struct StateMachine<S, E, C> {
+    rules: Vec<Rule<S, E, C>>,
+    on_enter: Vec<(S, Hook<C>)>,
+    on_exit: Vec<(S, Hook<C>)>,
+}
+
+impl<S: Copy + PartialEq, E: Copy + PartialEq, C> StateMachine<S, E, C> {
+    pub fn new() -> StateMachine<S, E, C> {
+        StateMachine { rules: Vec::new(), on_enter: Vec::new(), on_exit: Vec::new() }
+    }
+
+    pub fn add(&mut self, rule: Rule<S, E, C>) -> &mut StateMachine<S, E, C> {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn on_enter<F: Fn(&mut C) + 'static>(&mut self, state: S, hook: F) -> &mut StateMachine<S, E, C> {
+        self.on_enter.push((state, Box::new(hook)));
+        self
+    }
+
+    pub fn on_exit<F: Fn(&mut C) + 'static>(&mut self, state: S, hook: F) -> &mut StateMachine<S, E, C> {
+        self.on_exit.push((state, Box::new(hook)));
+        self
+    }
+
+    // Which state `event` would lead to, without changing anything
+    pub fn next_state(&self, state: S, event: E, context: &C) -> Result<S, MachineError<S, E>> {
+        self.find(state, event, context).map(|rule| rule.to)
+    }
+
+    // The events that have at least one rule out of `state` (whether or not their guards would pass right now)
+    pub fn events(&self, state: S) -> Vec<E> {
+        let mut events = Vec::new();
+        for rule in self.rules.iter().filter(|r| r.from == state) {
+            if !events.contains(&rule.event) {
+                events.push(rule.event);
+            }
+        }
+        events
+    }
+
+    pub fn fire(&self, state: S, event: E, context: &mut C) -> Result<S, MachineError<S, E>> {
+        let rule = self.find(state, event, context)?;
+        let leaving = rule.to != state;
+
+        if leaving {
+            self.run_hooks(&self.on_exit, state, context);
+        }
+        if let Some(action) = &rule.action {
+            action(context);
+        }
+        if leaving {
+            self.run_hooks(&self.on_enter, rule.to, context);
+        }
+
+        Ok(rule.to)
+    }
+
+    fn find(&self, state: S, event: E, context: &C) -> Result<&Rule<S, E, C>, MachineError<S, E>> {
+        let mut candidates = self.rules.iter().filter(|r| r.from == state && r.event == event).peekable();
+        if candidates.peek().is_none() {
+            return Err(MachineError::NoTransition { state, event });
+        }
+
+        candidates
+            .find(|r| r.guard.as_ref().is_none_or(|guard| guard(context)))
+            .ok_or(MachineError::Blocked { state, event })
+    }
+
+    fn run_hooks(&self, hooks: &[(S, Hook<C>)], state: S, context: &mut C) {
+        for (_, hook) in hooks.iter().filter(|(s, _)| *s == state) {
+            hook(context);
+        }
+    }
+}
+
+impl<S: Copy + PartialEq, E: Copy + PartialEq, C> Default for StateMachine<S, E, C> {
+    fn default() -> StateMachine<S, E, C> {
+        StateMachine::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Something other than a blog post: a support ticket that can be reopened twice before it has to be escalated.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Ticket {
+        Open,
+        Resolved,
+        Escalated,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Event {
+        Resolve,
+        Reopen,
+        Comment,
+    }
+
+    #[derive(Default)]
+    struct Log {
+        reopened: usize,
+        comments: usize,
+        events: Vec<String>,
+    }
+
+    fn ticket_machine() -> StateMachine<Ticket, Event, Log> {
+        let mut machine = StateMachine::new();
+        machine
+            .add(Rule::new(Ticket::Open, Event::Resolve, Ticket::Resolved))
+            .add(Rule::new(Ticket::Open, Event::Comment, Ticket::Open).action(|log: &mut Log| log.comments += 1))
+            .add(
+                Rule::new(Ticket::Resolved, Event::Reopen, Ticket::Open)
+                    .guard(|log: &Log| log.reopened < 2)
+                    .action(|log: &mut Log| log.reopened += 1),
+            )
+            .add(Rule::new(Ticket::Resolved, Event::Reopen, Ticket::Escalated))
+            .on_exit(Ticket::Open, |log| log.events.push(String::from("exit open")))
+            .on_enter(Ticket::Open, |log| log.events.push(String::from("enter open")))
+            .on_enter(Ticket::Escalated, |log| log.events.push(String::from("enter escalated")));
+        machine
+    }
+
+    #[test]
+    fn guards_pick_between_rules_for_the_same_event() {
+        let machine = ticket_machine();
+        let mut log = Log::default();
+        let mut state = Ticket::Open;
+
+        for _ in 0..2 {
+            state = machine.fire(state, Event::Resolve, &mut log).unwrap();
+            state = machine.fire(state, Event::Reopen, &mut log).unwrap();
+            assert_eq!(state, Ticket::Open);
+        }
+        state = machine.fire(state, Event::Resolve, &mut log).unwrap();
+        state = machine.fire(state, Event::Reopen, &mut log).unwrap();
+
+        assert_eq!(state, Ticket::Escalated);
+        assert_eq!(log.reopened, 2);
+    }
+
+    #[test]
+    fn hooks_run_in_order_and_not_for_self_transitions() {
+        let machine = ticket_machine();
+        let mut log = Log::default();
+
+        let state = machine.fire(Ticket::Open, Event::Comment, &mut log).unwrap();
+        assert_eq!(state, Ticket::Open);
+        assert_eq!(log.comments, 1);
+        assert!(log.events.is_empty());
+
+        let state = machine.fire(state, Event::Resolve, &mut log).unwrap();
+        machine.fire(state, Event::Reopen, &mut log).unwrap();
+        assert_eq!(log.events, vec!["exit open", "enter open"]);
+    }
+
+    #[test]
+    fn unknown_and_blocked_events_are_errors() {
+        let mut machine = ticket_machine();
+        machine.add(Rule::new(Ticket::Escalated, Event::Resolve, Ticket::Resolved).guard(|_| false));
+        let mut log = Log::default();
+
+        assert_eq!(
+            machine.fire(Ticket::Open, Event::Reopen, &mut log),
+            Err(MachineError::NoTransition { state: Ticket::Open, event: Event::Reopen })
+        );
+        assert_eq!(
+            machine.fire(Ticket::Escalated, Event::Resolve, &mut log),
+            Err(MachineError::Blocked { state: Ticket::Escalated, event: Event::Resolve })
+        );
+        assert_eq!(machine.events(Ticket::Open), vec![Event::Resolve, Event::Comment]);
+        assert_eq!(machine.next_state(Ticket::Open, Event::Resolve, &log), Ok(Ticket::Resolved));
+    }
+}
@@ -8,17 +8,45 @@
 Any other changes attempted on a post should have no effect. For example, if we try to approve a draft blog post before we’ve requested a review, the post should remain an unpublished draft.
  */
 extern crate state_pattern_oo;
+use std::env;
+use std::net::TcpListener;
+use std::process;
+
+use state_pattern_oo::http::BlogService;
 use state_pattern_oo::Post;
 
 fn main() {
+    // `cargo run -- serve 127.0.0.1:8080` runs the blog as an HTTP service instead of the walkthrough below
+    let args: Vec<String> = env::args().collect();
+    if args.len() > 1 {
+        if args[1] != "serve" || args.len() != 3 {
+            eprintln!("usage: state_pattern_oo [serve ADDR]");
+            process::exit(1);
+        }
+        let listener = TcpListener::bind(&args[2]).unwrap_or_else(|err| {
+            eprintln!("Problem binding {}: {}", args[2], err);
+            process::exit(1);
+        });
+        println!("Serving the blog on http://{}", args[2]);
+        if let Err(e) = BlogService::new().serve(listener) {
+            eprintln!("Server error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     let mut post = Post::new();
 
     post.add_text("I ate a salad for lunch today");
     assert_eq!("", post.content());
 
-    post.request_review();
+    post.request_review("bertie").unwrap();
     assert_eq!("", post.content());
 
-    post.approve();
+    post.approve("alice").unwrap();
     assert_eq!("I ate a salad for lunch today", post.content());
+
+    for transition in post.history().entries() {
+        println!("{} -> {} ({} by {})", transition.from, transition.to, transition.action, transition.actor);
+    }
 }
@@ -0,0 +1,412 @@
+// A small Markdown renderer for published posts. It covers the parts of Markdown people actually write in blog posts:
+//
+//   # Headings (one to six #s)         *emphasis* or _emphasis_         **strong**
+//   - unordered and 1. ordered lists   `inline code`                    [links](https://example.com)
+//   ``` fenced code blocks ```         \* backslash escapes
+//
+// Anything else is just paragraph text. All text is HTML-escaped, so a post can't smuggle tags into the page, and
+// links are only allowed to http(s), mailto and relative URLs.
+//
+// The text is parsed into blocks first, so the same parse can be written out as HTML or as plain text for excerpts.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading(usize, Vec<Inline>),
+    Paragraph(Vec<Inline>),
+    List { ordered: bool, items: Vec<Vec<Inline>> },
+    Code { language: Option<String>, text: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Code(String),
+    Emphasis(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Link { text: Vec<Inline>, url: String },
+}
+
+pub fn to_html(markdown: &str) -> String {
+    let mut html = String::new();
+
+    for block in parse(markdown) {
+        match block {
+            Block::Heading(level, text) => html.push_str(&format!("<h{0}>{1}</h{0}>\n", level, inline_html(&text))),
+            Block::Paragraph(text) => html.push_str(&format!("<p>{}</p>\n", inline_html(&text))),
+            Block::List { ordered, items } => {
+                let tag = if ordered { "ol" } else { "ul" };
+                html.push_str(&format!("<{}>\n", tag));
+                for item in items {
+                    html.push_str(&format!("<li>{}</li>\n", inline_html(&item)));
+                }
+                html.push_str(&format!("</{}>\n", tag));
+            }
+            Block::Code { language, text } => {
+                match language {
+                    Some(language) => html.push_str(&format!("<pre><code class=\"language-{}\">", escape(&language))),
+                    None => html.push_str("<pre><code>"),
+                }
+                html.push_str(&escape(&text));
+                html.push_str("</code></pre>\n");
+            }
+        }
+    }
+
+    html
+}
+
+// The text with all the markup taken out, one block per line
+pub fn to_text(markdown: &str) -> String {
+    let mut lines = Vec::new();
+
+    for block in parse(markdown) {
+        match block {
+            Block::Heading(_, text) | Block::Paragraph(text) => lines.push(inline_text(&text)),
+            Block::List { items, .. } => lines.extend(items.iter().map(|item| inline_text(item))),
+            Block::Code { text, .. } => lines.push(text),
+        }
+    }
+
+    lines.join("\n")
+}
+
+// The start of the post's prose as one line of plain text, cut at a word boundary so it's at most `max_chars`
+// characters long (counting the "…" added when anything was cut). Code blocks are left out - they make poor summaries.
+pub fn excerpt(markdown: &str, max_chars: usize) -> String {
+    let mut words = Vec::new();
+    for block in parse(markdown) {
+        let text = match block {
+            Block::Heading(_, text) | Block::Paragraph(text) => inline_text(&text),
+            Block::List { items, .. } => items.iter().map(|item| inline_text(item)).collect::<Vec<_>>().join(" "),
+            Block::Code { .. } => continue,
+        };
+        words.extend(text.split_whitespace().map(String::from));
+    }
+
+    let full = words.join(" ");
+    if full.chars().count() <= max_chars {
+        return full;
+    }
+    // not even room for the "…"
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    let mut summary = String::new();
+    for word in &words {
+        let needed = summary.chars().count() + usize::from(!summary.is_empty()) + word.chars().count() + 1;
+        if needed > max_chars {
+            break;
+        }
+        if !summary.is_empty() {
+            summary.push(' ');
+        }
+        summary.push_str(word);
+    }
+    // a first word too long to fit at all gets cut mid-word rather than leaving nothing
+    if summary.is_empty() {
+        summary = full.chars().take(max_chars.saturating_sub(1)).collect();
+    }
+    summary.push('…');
+    summary
+}
+
+// ======= Blocks =======
+
+pub fn parse(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut lines = markdown.lines().peekable();
+
+    // Ends the paragraph being collected, if there is one
+    fn flush(paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>) {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(parse_inline(&paragraph.join("\n"))));
+            paragraph.clear();
+        }
+    }
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else if let Some(info) = trimmed.strip_prefix("```") {
+            flush(&mut paragraph, &mut blocks);
+            let language = Some(info.trim().to_string()).filter(|l| !l.is_empty());
+            let mut code = Vec::new();
+            // an unclosed fence runs to the end of the post
+            for line in lines.by_ref() {
+                if line.trim() == "```" {
+                    break;
+                }
+                code.push(line);
+            }
+            let mut text = code.join("\n");
+            if !code.is_empty() {
+                text.push('\n');
+            }
+            blocks.push(Block::Code { language, text });
+        } else if let Some((level, text)) = heading(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Heading(level, parse_inline(text)));
+        } else if let Some((ordered, text)) = list_item(trimmed) {
+            flush(&mut paragraph, &mut blocks);
+            let mut items = vec![parse_inline(text)];
+            while let Some((next_ordered, text)) = lines.peek().and_then(|line| list_item(line.trim())) {
+                if next_ordered != ordered {
+                    break;
+                }
+                items.push(parse_inline(text));
+                lines.next();
+            }
+            blocks.push(Block::List { ordered, items });
+        } else {
+            paragraph.push(trimmed);
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+
+    blocks
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+        Some((level, rest.trim().trim_end_matches('#').trim_end()))
+    } else {
+        None
+    }
+}
+
+// Some((ordered, text)) for "- item", "* item", "+ item" and "1. item"
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    for bullet in &["- ", "* ", "+ "] {
+        if let Some(text) = line.strip_prefix(bullet) {
+            return Some((false, text.trim()));
+        }
+    }
+
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    match line[digits..].strip_prefix(". ") {
+        Some(text) if digits > 0 => Some((true, text.trim())),
+        _ => None,
+    }
+}
+
+// ======= Inline =======
+
+pub fn parse_inline(text: &str) -> Vec<Inline> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = Vec::new();
+    let mut plain = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let parsed = match c {
+            '\\' if chars.get(i + 1).is_some_and(|c| c.is_ascii_punctuation()) => {
+                plain.push(chars[i + 1]);
+                i += 2;
+                continue;
+            }
+            '`' => find(&chars, i + 1, "`").map(|end| (Inline::Code(collect(&chars[i + 1..end])), end + 1)),
+            '*' if chars.get(i + 1) == Some(&'*') => find(&chars, i + 2, "**")
+                // in "***" the strong closes on the last two, so "**a *b***" keeps its emphasis inside
+                .map(|end| end + chars[end + 2..].iter().take_while(|&&c| c == '*').count())
+                .filter(|&end| end > i + 2)
+                .map(|end| (Inline::Strong(parse_inline(&collect(&chars[i + 2..end]))), end + 2)),
+            '*' if chars.get(i + 1).is_some_and(|c| !c.is_whitespace()) => find(&chars, i + 1, "*")
+                .map(|end| (Inline::Emphasis(parse_inline(&collect(&chars[i + 1..end]))), end + 1)),
+            // like CommonMark, _ only counts at the edges of words, so snake_case_names stay as they are
+            '_' if i.checked_sub(1).is_none_or(|before| !chars[before].is_alphanumeric())
+                && chars.get(i + 1).is_some_and(|c| !c.is_whitespace()) =>
+            {
+                closing_underscore(&chars, i + 1)
+                    .map(|end| (Inline::Emphasis(parse_inline(&collect(&chars[i + 1..end]))), end + 1))
+            }
+            '[' => link(&chars, i),
+            _ => None,
+        };
+
+        match parsed {
+            Some((inline, next)) => {
+                if !plain.is_empty() {
+                    out.push(Inline::Text(std::mem::take(&mut plain)));
+                }
+                out.push(inline);
+                i = next;
+            }
+            // an opener with nothing to close it is just text
+            None => {
+                plain.push(c);
+                i += 1;
+            }
+        }
+    }
+    if !plain.is_empty() {
+        out.push(Inline::Text(plain));
+    }
+
+    out
+}
+
+// [text](url) starting at `start`, and where the text after it starts
+fn link(chars: &[char], start: usize) -> Option<(Inline, usize)> {
+    let close = find(chars, start + 1, "](")?;
+    // URLs can have brackets of their own, like wikipedia's, so the link ends at the ")" that balances the "("
+    let mut depth = 0;
+    let end = (close + 2..chars.len()).find(|&i| match chars[i] {
+        '(' => {
+            depth += 1;
+            false
+        }
+        ')' if depth == 0 => true,
+        ')' => {
+            depth -= 1;
+            false
+        }
+        _ => false,
+    })?;
+    let text = parse_inline(&collect(&chars[start + 1..close]));
+    let url = collect(&chars[close + 2..end]).trim().to_string();
+
+    Some((Inline::Link { text, url }, end + 1))
+}
+
+// The next _ after `from` that ends a word - not after a space, and not followed by a letter or digit
+fn closing_underscore(chars: &[char], from: usize) -> Option<usize> {
+    (from + 1..chars.len()).find(|&i| {
+        chars[i] == '_' && !chars[i - 1].is_whitespace() && chars.get(i + 1).is_none_or(|c| !c.is_alphanumeric())
+    })
+}
+
+// The index of the next `pattern` at or after `from`
+fn find(chars: &[char], from: usize, pattern: &str) -> Option<usize> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    (from..chars.len()).find(|&i| chars[i..].starts_with(&pattern))
+}
+
+fn collect(chars: &[char]) -> String {
+    chars.iter().collect()
+}
+
+fn inline_html(inlines: &[Inline]) -> String {
+    let mut html = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => html.push_str(&escape(text)),
+            Inline::Code(code) => html.push_str(&format!("<code>{}</code>", escape(code))),
+            Inline::Emphasis(inner) => html.push_str(&format!("<em>{}</em>", inline_html(inner))),
+            Inline::Strong(inner) => html.push_str(&format!("<strong>{}</strong>", inline_html(inner))),
+            Inline::Link { text, url } if is_safe_url(url) => {
+                html.push_str(&format!("<a href=\"{}\">{}</a>", escape(url), inline_html(text)))
+            }
+            // a javascript: link or similar keeps its text but loses the link
+            Inline::Link { text, .. } => html.push_str(&inline_html(text)),
+        }
+    }
+    html
+}
+
+fn inline_text(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) | Inline::Code(text) => text.clone(),
+            Inline::Emphasis(inner) | Inline::Strong(inner) | Inline::Link { text: inner, .. } => inline_text(inner),
+        })
+        .collect()
+}
+
+fn is_safe_url(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    match lower.find(':') {
+        // no scheme at all (or a colon after the path has started) means a relative URL
+        Some(colon) if !lower[..colon].contains(['/', '?', '#']) => {
+            ["http", "https", "mailto"].contains(&&lower[..colon])
+        }
+        _ => true,
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_blocks() {
+        let markdown = "# Lunch\n\nI ate\na salad.\n\n- lettuce\n- tomato\n\n1. wash\n2. eat\n\n```rust\nlet x = 1 < 2;\n```\n";
+
+        assert_eq!(
+            to_html(markdown),
+            "<h1>Lunch</h1>\n<p>I ate\na salad.</p>\n\
+             <ul>\n<li>lettuce</li>\n<li>tomato</li>\n</ul>\n\
+             <ol>\n<li>wash</li>\n<li>eat</li>\n</ol>\n\
+             <pre><code class=\"language-rust\">let x = 1 &lt; 2;\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn it_renders_inline_markup() {
+        assert_eq!(
+            to_html("Some *very* **good** _news_ about `Box<T>` from [the book](https://doc.rust-lang.org/book/)"),
+            "<p>Some <em>very</em> <strong>good</strong> <em>news</em> about <code>Box&lt;T&gt;</code> from \
+             <a href=\"https://doc.rust-lang.org/book/\">the book</a></p>\n"
+        );
+        assert_eq!(to_html("**bold *and em***"), "<p><strong>bold <em>and em</em></strong></p>\n");
+        assert_eq!(to_html(r"2 * 3 = 6, \*not em\*, [no link"), "<p>2 * 3 = 6, *not em*, [no link</p>\n");
+    }
+
+    #[test]
+    fn snake_case_names_arent_emphasised() {
+        assert_eq!(to_html("call snake_case_name or my_var_"), "<p>call snake_case_name or my_var_</p>\n");
+        assert_eq!(to_html("_one_two_ and (_three_)"), "<p><em>one_two</em> and (<em>three</em>)</p>\n");
+        assert_eq!(to_html("*in*side words still works"), "<p><em>in</em>side words still works</p>\n");
+    }
+
+    #[test]
+    fn it_escapes_html_and_unsafe_links() {
+        assert_eq!(
+            to_html("<script>alert('hi')</script> & [click](javascript:alert(1)) [ok](/posts/2)"),
+            "<p>&lt;script&gt;alert(&#39;hi&#39;)&lt;/script&gt; &amp; click <a href=\"/posts/2\">ok</a></p>\n"
+        );
+        assert_eq!(to_html("## A \"quote\" #"), "<h2>A &quot;quote&quot;</h2>\n");
+    }
+
+    #[test]
+    fn it_strips_markup_for_plain_text() {
+        let markdown = "# Lunch\nI ate a *big* [salad](https://example.com).\n\n```\nlet x = 1;\n```\n- tomato";
+
+        assert_eq!(to_text(markdown), "Lunch\nI ate a big salad.\nlet x = 1;\n\ntomato");
+        assert_eq!(excerpt(markdown, 100), "Lunch I ate a big salad. tomato");
+    }
+
+    #[test]
+    fn excerpts_are_cut_at_word_boundaries() {
+        let markdown = "I ate a salad for lunch today";
+
+        assert_eq!(excerpt(markdown, 14), "I ate a salad…");
+        assert_eq!(excerpt(markdown, 13), "I ate a…");
+        assert_eq!(excerpt("Supercalifragilistic", 6), "Super…");
+        assert_eq!(excerpt(markdown, 29), markdown);
+        assert_eq!(excerpt(markdown, 1), "…");
+        assert_eq!(excerpt(markdown, 0), "");
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{Action, Post, Status};
+
+// Publishes every scheduled post whose embargo is over. Nothing runs it on a timer - call `tick` from wherever
+// suits (a loop, a cron job, a request handler) and it catches the posts up with their clocks.
+pub struct Scheduler {
+    actor: String, // who the transitions are recorded as
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler::with_actor("scheduler")
+    }
+
+    pub fn with_actor(actor: &str) -> Scheduler {
+        Scheduler { actor: actor.to_string() }
+    }
+
+    // Returns how many posts were published
+    pub fn tick<'a, I: IntoIterator<Item = &'a mut Post>>(&self, posts: I) -> usize {
+        posts
+            .into_iter()
+            .filter(|post| post.status() == Status::Scheduled)
+            .filter_map(|post| post.transition(Action::Publish, &self.actor, None).ok())
+            .count()
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Scheduler {
+        Scheduler::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ManualClock;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    fn scheduled_post(clock: &Arc<ManualClock>, text: &str, hours: u64) -> Post {
+        let mut post = Post::new();
+        post.set_clock(clock.clone());
+        post.add_text(text);
+        post.request_review("bertie").unwrap();
+        post.schedule("bertie", SystemTime::UNIX_EPOCH + Duration::from_secs(hours * 3600)).unwrap();
+        post.approve("alice").unwrap();
+        post
+    }
+
+    #[test]
+    fn it_publishes_posts_as_their_embargoes_pass() {
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let mut unscheduled = Post::new();
+        unscheduled.set_clock(clock.clone());
+
+        let mut posts = vec![
+            scheduled_post(&clock, "in one hour", 1),
+            scheduled_post(&clock, "in two hours", 2),
+            unscheduled,
+        ];
+        let scheduler = Scheduler::new();
+
+        assert_eq!(scheduler.tick(&mut posts), 0);
+
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(scheduler.tick(&mut posts), 1);
+        assert_eq!(posts[0].status(), Status::Published);
+        assert_eq!(posts[1].status(), Status::Scheduled);
+        assert_eq!(posts[0].history().last().unwrap().actor, "scheduler");
+
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(scheduler.tick(posts.iter_mut()), 1);
+        assert_eq!(posts[1].content(), "in two hours");
+        assert_eq!(posts[2].status(), Status::Draft);
+    }
+}
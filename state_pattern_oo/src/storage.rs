@@ -0,0 +1,406 @@
+// Posts are stored one file per post, in a directory of their own. A file looks like
+//
+//     blog-post 1
+//     status	PendingReview
+//     required_approvals	2
+//     approval	alice
+//     publish_at	1561998745.000000000
+//     content	I ate a salad\nfor lunch today
+//     transition	Draft	PendingReview	RequestReview	bertie	1561912345.000000000
+//     transition	...	<comment>
+//
+// with tab separated fields. Tabs, newlines and backslashes inside a field are escaped so every record stays on one line.
+// The State objects themselves can't be written out, so the file keeps the status and what the workflow rules need
+// (the approvals and any embargo), and `restore_state` rebuilds the post from them on load. Times are
+// seconds.nanoseconds since the unix epoch, and a Schedule action in the history carries its publish-at time as
+// Schedule@<time>.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::history::{History, Transition};
+use crate::{restore_state, Action, Clock, Post, Status, SystemClock};
+
+pub const HEADER: &str = "blog-post 1";
+const EXTENSION: &str = "post";
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(io::Error),
+    InvalidId(String),
+    NotFound(String),
+    UnknownState(String),
+    Format { line: usize, message: String },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Io(e) => write!(f, "{}", e),
+            StoreError::InvalidId(id) => write!(f, "invalid post id: {:?}", id),
+            StoreError::NotFound(id) => write!(f, "no post with id {:?}", id),
+            StoreError::UnknownState(name) => write!(f, "unknown state: {}", name),
+            StoreError::Format { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> StoreError {
+        StoreError::Io(e)
+    }
+}
+
+pub struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Store, StoreError> {
+        fs::create_dir_all(&dir)?;
+        Ok(Store { dir: dir.as_ref().to_path_buf() })
+    }
+
+    pub fn save(&self, id: &str, post: &Post) -> Result<(), StoreError> {
+        let path = self.path(id)?;
+
+        // write everything to the side first, so a crash half way through can't leave a truncated post behind
+        let partial = path.with_extension("partial");
+        fs::write(&partial, to_string(post))?;
+        fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    pub fn load(&self, id: &str) -> Result<Post, StoreError> {
+        self.load_with_clock(id, Arc::new(SystemClock))
+    }
+
+    // Loads a post that tells the time by `clock`, as if it had been given to set_clock
+    pub fn load_with_clock(&self, id: &str, clock: Arc<dyn Clock>) -> Result<Post, StoreError> {
+        let text = match fs::read_to_string(self.path(id)?) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Err(StoreError::NotFound(id.to_string())),
+            Err(e) => return Err(e.into()),
+        };
+
+        from_str_with_clock(&text, clock)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<(), StoreError> {
+        match fs::remove_file(self.path(id)?) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Err(StoreError::NotFound(id.to_string())),
+            result => Ok(result?),
+        }
+    }
+
+    // The ids of every stored post, sorted
+    pub fn ids(&self) -> Result<Vec<String>, StoreError> {
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some(EXTENSION) {
+                if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+                    ids.push(id.to_string());
+                }
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    // Ids end up as file names, so they're kept to letters, digits, '-' and '_'
+    fn path(&self, id: &str) -> Result<PathBuf, StoreError> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(StoreError::InvalidId(id.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.{}", id, EXTENSION)))
+    }
+}
+
+pub fn to_string(post: &Post) -> String {
+    let mut out = format!("{}\n", HEADER);
+
+    out.push_str(&format!("status\t{}\n", post.status()));
+    out.push_str(&format!("required_approvals\t{}\n", post.data.required_approvals));
+    for approver in &post.data.approvals {
+        out.push_str(&format!("approval\t{}\n", escape(approver)));
+    }
+    if let Some(publish_at) = post.data.publish_at {
+        out.push_str(&format!("publish_at\t{}\n", format_timestamp(publish_at)));
+    }
+    out.push_str(&format!("content\t{}\n", escape(&post.content)));
+
+    for t in post.history.entries() {
+        let action = match t.action {
+            Action::Schedule(publish_at) => format!("Schedule@{}", format_timestamp(publish_at)),
+            other => other.to_string(),
+        };
+        out.push_str(&format!(
+            "transition\t{}\t{}\t{}\t{}\t{}",
+            t.from,
+            t.to,
+            action,
+            escape(&t.actor),
+            format_timestamp(t.timestamp)
+        ));
+        if let Some(comment) = &t.comment {
+            out.push_str(&format!("\t{}", escape(comment)));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+pub fn from_str(text: &str) -> Result<Post, StoreError> {
+    from_str_with_clock(text, Arc::new(SystemClock))
+}
+
+pub fn from_str_with_clock(text: &str, clock: Arc<dyn Clock>) -> Result<Post, StoreError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    match lines.next() {
+        Some((_, HEADER)) => {}
+        _ => return Err(format_error(1, "not a blog post file")),
+    }
+
+    let mut status = None;
+    let mut required_approvals = None;
+    let mut approvals = Vec::new();
+    let mut publish_at = None;
+    let mut content = None;
+    let mut history = History::new();
+
+    for (n, line) in lines {
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+
+        match fields[..] {
+            ["status", name] => {
+                status = Some(name.parse::<Status>().map_err(|_| StoreError::UnknownState(name.to_string()))?)
+            }
+            ["required_approvals", count] => {
+                required_approvals = Some(count.parse::<usize>().map_err(|_| format_error(n, "expected a number"))?)
+            }
+            ["approval", approver] => approvals.push(unescape(approver)),
+            ["publish_at", time] => publish_at = Some(parse_timestamp(time).ok_or_else(|| format_error(n, "bad timestamp"))?),
+            ["content", text] => content = Some(unescape(text)),
+            ["transition", from, to, action, actor, timestamp] | ["transition", from, to, action, actor, timestamp, _] => {
+                history.record(Transition {
+                    from: from.parse().map_err(|_| StoreError::UnknownState(from.to_string()))?,
+                    to: to.parse().map_err(|_| StoreError::UnknownState(to.to_string()))?,
+                    action: parse_action(action).ok_or_else(|| format_error(n, "unknown action"))?,
+                    actor: unescape(actor),
+                    timestamp: parse_timestamp(timestamp).ok_or_else(|| format_error(n, "bad timestamp"))?,
+                    comment: fields.get(6).map(|c| unescape(c)),
+                })
+            }
+            _ => return Err(format_error(n, "unrecognised line")),
+        }
+    }
+
+    // what's missing or doesn't fit together is only known once the whole record has been read
+    let last = text.lines().count();
+    let status = status.ok_or_else(|| format_error(last, "missing status"))?;
+    let required_approvals = required_approvals.ok_or_else(|| format_error(last, "missing required_approvals"))?;
+    let content = content.ok_or_else(|| format_error(last, "missing content"))?;
+    let (state, data) =
+        restore_state(status, required_approvals, approvals, publish_at).map_err(|e| format_error(last, e))?;
+
+    Ok(Post { state: Some(state), data, content, history, clock })
+}
+
+fn parse_action(text: &str) -> Option<Action> {
+    if let Some(time) = text.strip_prefix("Schedule@") {
+        return parse_timestamp(time).map(Action::Schedule);
+    }
+    text.parse().ok()
+}
+
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!("{}.{:09}", since_epoch.as_secs(), since_epoch.subsec_nanos())
+}
+
+fn format_error<M: Into<String>>(line: usize, message: M) -> StoreError {
+    StoreError::Format { line, message: message.into() }
+}
+
+// "1561912345.000000001" => 1561912345s + 1ns after the epoch
+fn parse_timestamp(text: &str) -> Option<SystemTime> {
+    let mut parts = text.splitn(2, '.');
+    let secs = parts.next()?.parse().ok()?;
+    let nanos = parts.next()?.parse().ok()?;
+    UNIX_EPOCH.checked_add(Duration::new(secs, nanos))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZeroUsize;
+
+    // A store in a directory of its own, which is removed again when the test is done with it
+    struct TempStore(Store);
+
+    impl std::ops::Deref for TempStore {
+        type Target = Store;
+
+        fn deref(&self) -> &Store {
+            &self.0
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0.dir);
+        }
+    }
+
+    fn temp_store(name: &str) -> TempStore {
+        let dir = std::env::temp_dir().join(format!("blog-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        TempStore(Store::open(dir).unwrap())
+    }
+
+    #[test]
+    fn it_round_trips_a_post_in_every_state() {
+        let store = temp_store("round-trip");
+
+        let mut post = Post::with_required_approvals(NonZeroUsize::new(2).unwrap());
+        post.add_text("Tabs\tnewlines\nand back\\slashes");
+        store.save("draft", &post).unwrap();
+
+        post.request_review("bertie").unwrap();
+        post.approve("alice").unwrap();
+        store.save("pending", &post).unwrap();
+
+        post.transition(Action::Approve, "bob", Some("lgtm")).unwrap();
+        store.save("published", &post).unwrap();
+
+        assert_eq!(store.ids().unwrap(), vec!["draft", "pending", "published"]);
+
+        let draft = store.load("draft").unwrap();
+        assert_eq!(draft.status(), Status::Draft);
+        assert!(draft.history().is_empty());
+
+        // the restored post carries on where it left off, approvals and all
+        let mut pending = store.load("pending").unwrap();
+        assert_eq!(pending.status(), Status::PendingReview);
+        assert!(pending.approve("alice").is_err());
+        pending.approve("bob").unwrap();
+        assert_eq!(pending.content(), "Tabs\tnewlines\nand back\\slashes");
+
+        let published = store.load("published").unwrap();
+        assert_eq!(published.content(), "Tabs\tnewlines\nand back\\slashes");
+        assert_eq!(published.history(), post.history());
+        assert_eq!(published.history().last().unwrap().comment.as_deref(), Some("lgtm"));
+
+        store.remove("draft").unwrap();
+        assert!(matches!(store.load("draft"), Err(StoreError::NotFound(_))));
+    }
+
+    #[test]
+    fn it_round_trips_a_scheduled_post() {
+        let store = temp_store("scheduled");
+        let publish_at = UNIX_EPOCH + Duration::new(4_000_000_000, 5);
+
+        let mut post = Post::new();
+        post.add_text("Not yet");
+        post.request_review("bertie").unwrap();
+        post.schedule("bertie", publish_at).unwrap();
+        store.save("pending", &post).unwrap();
+        post.approve("alice").unwrap();
+        store.save("scheduled", &post).unwrap();
+
+        let mut pending = store.load("pending").unwrap();
+        assert_eq!(pending.approve("alice"), Ok(Status::Scheduled));
+
+        let scheduled = store.load("scheduled").unwrap();
+        assert_eq!(scheduled.status(), Status::Scheduled);
+        assert_eq!(scheduled.publish_at(), Some(publish_at));
+        assert_eq!(scheduled.content(), "");
+        assert_eq!(scheduled.history().entries()[1].action, Action::Schedule(publish_at));
+
+        assert!(from_str("blog-post 1\nstatus\tScheduled\nrequired_approvals\t1\ncontent\t\n").is_err());
+    }
+
+    #[test]
+    fn loaded_posts_keep_the_clock_they_are_given() {
+        let store = temp_store("clock");
+        let clock = Arc::new(crate::ManualClock::new(UNIX_EPOCH));
+
+        let mut post = Post::new();
+        post.request_review("bertie").unwrap();
+        post.schedule("bertie", UNIX_EPOCH + Duration::from_secs(3600)).unwrap();
+        post.approve("alice").unwrap();
+        store.save("scheduled", &post).unwrap();
+
+        let mut loaded = store.load_with_clock("scheduled", clock.clone()).unwrap();
+        assert!(loaded.transition(Action::Publish, "scheduler", None).is_err());
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(loaded.transition(Action::Publish, "scheduler", None), Ok(Status::Published));
+        assert_eq!(loaded.history().last().unwrap().timestamp, UNIX_EPOCH + Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn missing_fields_are_reported_at_the_end_of_the_record() {
+        let error = from_str("blog-post 1\nstatus\tDraft\ncontent\thi\n").err().unwrap();
+
+        assert_eq!(error.to_string(), "line 3: missing required_approvals");
+    }
+
+    #[test]
+    fn it_refuses_unknown_states() {
+        let text = "blog-post 1\nstatus\tArchived\nrequired_approvals\t1\ncontent\t\n";
+
+        assert!(matches!(from_str(text), Err(StoreError::UnknownState(ref name)) if name == "Archived"));
+    }
+
+    #[test]
+    fn it_refuses_inconsistent_or_malformed_files() {
+        assert!(from_str("").is_err());
+        assert!(from_str("blog-post 1\nstatus\tDraft\ncontent\thi\n").is_err());
+        assert!(from_str("blog-post 1\nstatus\tDraft\nrequired_approvals\t1\napproval\talice\ncontent\thi\n").is_err());
+        assert!(from_str("blog-post 1\nstatus\tPendingReview\nrequired_approvals\t1\napproval\talice\ncontent\thi\n").is_err());
+        assert!(from_str("blog-post 1\nstatus\tDraft\nrequired_approvals\t1\ncontent\thi\ncolour\tblue\n").is_err());
+    }
+
+    #[test]
+    fn it_keeps_ids_to_safe_file_names() {
+        let store = temp_store("ids");
+
+        assert!(matches!(store.save("../escape", &Post::new()), Err(StoreError::InvalidId(_))));
+        assert!(matches!(store.load(""), Err(StoreError::InvalidId(_))));
+    }
+}
@@ -0,0 +1,241 @@
+//! The same blog workflow with every state as its own type, so mistakes are compile errors instead of empty strings.
+//!
+//! A draft has no `content` method, so its text can't be read:
+//!
+//! ```compile_fail,E0599
+//! use state_pattern_oo::typestate::Post;
+//!
+//! let mut post = Post::new();
+//! post.add_text("I ate a salad for lunch today");
+//! post.content();
+//! ```
+//!
+//! and neither can a post that is waiting for review:
+//!
+//! ```compile_fail,E0599
+//! use state_pattern_oo::typestate::Post;
+//!
+//! let post = Post::new().request_review();
+//! post.content();
+//! ```
+//!
+//! A draft can't skip the review:
+//!
+//! ```compile_fail,E0599
+//! use state_pattern_oo::typestate::Post;
+//!
+//! let post = Post::new().approve();
+//! ```
+//!
+//! Each transition consumes the old post, so it can't be used again afterwards:
+//!
+//! ```compile_fail,E0382
+//! use state_pattern_oo::typestate::Post;
+//!
+//! let mut draft = Post::new();
+//! let pending = draft.request_review();
+//! draft.add_text("too late");
+//! ```
+//!
+//! The only way to a published `Post` is through review and approval:
+//!
+//! ```
+//! use state_pattern_oo::typestate::Post;
+//!
+//! let mut post = Post::new();
+//! post.add_text("I ate a salad for lunch today");
+//!
+//! let post = post.request_review();
+//! let post = post.approve();
+//!
+//! assert_eq!("I ate a salad for lunch today", post.content());
+//! ```
+
+use std::convert::TryFrom;
+
+// HOWEVER! By implementing the state pattern exactly as it’s defined for object-oriented languages, we’re not taking as full advantage of Rust’s strengths as we could. Let’s look at some changes we can make to the blog crate that can make invalid states and transitions into compile time errors.
+
+// The request_review and approve methods return new instances rather than modifying the struct they’re called on, so we need to add more let post = shadowing assignments to save the returned instances. We also can’t have the assertions about the draft and pending review post’s contents be empty strings, nor do we need them: we can’t compile code that tries to use the content of posts in those states any longer.
+
+// The changes we needed to make to main to reassign post mean that this implementation doesn’t quite follow the object-oriented state pattern anymore: the transformations between the states are no longer encapsulated entirely within the Post implementation. However, our gain is that invalid states are now impossible because of the type system and the type checking that happens at compile time! This ensures that certain bugs, such as display of the content of an unpublished post, will be discovered before they make it to production.
+
+// Both the Post and DraftPost structs have a private content field that stores the blog post text. The structs no longer have the state field because we’re moving the encoding of the state to the types of the structs. The Post struct will represent a published post, and it has a content method that returns the content.
+pub struct Post {
+    content: String,
+}
+
+pub struct DraftPost {
+    content: String,
+}
+
+// We still have a Post::new function, but instead of returning an instance of Post, it returns an instance of DraftPost. Because content is private and there aren’t any functions that return Post, it’s not possible to create an instance of Post right now.
+impl Post {
+    #[allow(clippy::new_ret_no_self)] // returning a DraftPost rather than a Post is the whole point
+    pub fn new() -> DraftPost {
+        DraftPost {
+            content: String::new(),
+        }
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+// The DraftPost struct has an add_text method, so we can add text to content as before, but note that DraftPost does not have a content method defined! So now the program ensures all posts start as draft posts, and draft posts don’t have their content available for display. Any attempt to get around these constraints will result in a compiler error.
+impl DraftPost {
+    pub fn add_text(&mut self, text: &str) {
+        self.content.push_str(text);
+    }
+
+    pub fn request_review(self) -> PendingReviewPost {
+        PendingReviewPost {
+            content: self.content,
+        }
+    }
+}
+
+// So how do we get a published post? We want to enforce the rule that a draft post has to be reviewed and approved before it can be published. A post in the pending review state should still not display any content. Let’s implement these constraints by adding another struct, PendingReviewPost, defining the request_review method on DraftPost to return a PendingReviewPost, and defining an approve method on PendingReviewPost to return a Post.
+
+// The request_review and approve methods take ownership of self, thus consuming the DraftPost and PendingReviewPost instances and transforming them into a PendingReviewPost and a published Post, respectively. This way, we won’t have any lingering DraftPost instances after we’ve called request_review on them, and so forth. The PendingReviewPost struct doesn’t have a content method defined on it, so attempting to read its content results in a compiler error, as with DraftPost. Because the only way to get a published Post instance that does have a content method defined is to call the approve method on a PendingReviewPost, and the only way to get a PendingReviewPost is to call the request_review method on a DraftPost, we’ve now encoded the blog post workflow into the type system.
+pub struct PendingReviewPost {
+    content: String,
+}
+
+impl PendingReviewPost {
+    pub fn approve(self) -> Post {
+        Post {
+            content: self.content,
+        }
+    }
+
+    pub fn reject(self) -> DraftPost {
+        DraftPost {
+            content: self.content,
+        }
+    }
+}
+
+// ======= CONVERSIONS =======
+//
+// Going from a typestate post to the dynamic one always works: each type maps onto one State, needing a single approval
+// like the typestate workflow does. The other way round we can't know the type until runtime, so the dynamic post
+// becomes an AnyPost to be matched on. The typestate posts don't keep approvals or history, so those are dropped.
+// They have no embargoes either, so a scheduled post can't be converted at all - as a PendingReviewPost, one approve
+// would publish it early.
+
+impl From<DraftPost> for crate::Post {
+    fn from(post: DraftPost) -> crate::Post {
+        crate::Post::restored(crate::Status::Draft, post.content)
+    }
+}
+
+impl From<PendingReviewPost> for crate::Post {
+    fn from(post: PendingReviewPost) -> crate::Post {
+        crate::Post::restored(crate::Status::PendingReview, post.content)
+    }
+}
+
+impl From<Post> for crate::Post {
+    fn from(post: Post) -> crate::Post {
+        crate::Post::restored(crate::Status::Published, post.content)
+    }
+}
+
+pub enum AnyPost {
+    Draft(DraftPost),
+    PendingReview(PendingReviewPost),
+    Published(Post),
+}
+
+impl TryFrom<crate::Post> for AnyPost {
+    type Error = crate::Post; // a scheduled post, given back as it was
+
+    fn try_from(post: crate::Post) -> Result<AnyPost, crate::Post> {
+        let status = post.status();
+        if status == crate::Status::Scheduled {
+            return Err(post);
+        }
+        let content = post.content;
+
+        Ok(match status {
+            crate::Status::Draft => AnyPost::Draft(DraftPost { content }),
+            crate::Status::PendingReview => AnyPost::PendingReview(PendingReviewPost { content }),
+            crate::Status::Published => AnyPost::Published(Post { content }),
+            crate::Status::Scheduled => unreachable!(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::Status;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn a_rejected_post_can_be_edited_again() {
+        let mut post = Post::new();
+        post.add_text("First try.");
+
+        let mut post = post.request_review().reject();
+        post.add_text(" Second try.");
+
+        assert_eq!("First try. Second try.", post.request_review().approve().content());
+    }
+
+    #[test]
+    fn it_converts_to_the_dynamic_post() {
+        let mut draft = Post::new();
+        draft.add_text("Hello");
+
+        let mut dynamic = crate::Post::from(draft);
+        assert_eq!(dynamic.status(), Status::Draft);
+        assert_eq!(dynamic.content(), "");
+
+        dynamic.request_review("bertie").unwrap();
+        dynamic.approve("alice").unwrap();
+        assert_eq!(dynamic.content(), "Hello");
+
+        let published = crate::Post::from(Post::new().request_review().approve());
+        assert_eq!(published.status(), Status::Published);
+    }
+
+    #[test]
+    fn it_converts_from_the_dynamic_post() {
+        let mut dynamic = crate::Post::new();
+        dynamic.add_text("Round trip");
+        dynamic.request_review("bertie").unwrap();
+
+        let published = match AnyPost::try_from(dynamic) {
+            Ok(AnyPost::PendingReview(post)) => post.approve(),
+            _ => panic!("expected a post pending review"),
+        };
+        assert_eq!(published.content(), "Round trip");
+
+        match AnyPost::try_from(crate::Post::from(published)) {
+            Ok(AnyPost::Published(post)) => assert_eq!(post.content(), "Round trip"),
+            _ => panic!("expected a published post"),
+        }
+    }
+
+    #[test]
+    fn a_scheduled_post_cant_dodge_its_embargo_by_converting() {
+        let clock = Arc::new(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let mut dynamic = crate::Post::new();
+        dynamic.set_clock(clock);
+        dynamic.add_text("Embargoed news");
+        dynamic.request_review("bertie").unwrap();
+        dynamic.schedule("bertie", SystemTime::UNIX_EPOCH + Duration::from_secs(3600)).unwrap();
+        dynamic.approve("alice").unwrap();
+
+        let dynamic = match AnyPost::try_from(dynamic) {
+            Err(post) => post,
+            Ok(_) => panic!("a scheduled post was converted"),
+        };
+        assert_eq!(dynamic.status(), Status::Scheduled);
+        assert_eq!(dynamic.content(), "");
+    }
+}
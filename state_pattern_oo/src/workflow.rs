@@ -0,0 +1,270 @@
+// The rules of the blog workflow, as data for the generic StateMachine. Post::transition fires every action through
+// `fire` here, so `blog_machine` is the one place that says which action is allowed in which state and where it leads.
+// The State objects in lib.rs are only left with how a post behaves while it's in each state.
+
+use std::time::SystemTime;
+
+use crate::machine::{MachineError, Rule, StateMachine};
+use crate::{Action, Status, TransitionError};
+
+// What the machine fires on. The time a Schedule action carries would stop rules from matching it, so it travels in
+// PostData instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    RequestReview,
+    Approve,
+    Reject,
+    Schedule,
+    Publish,
+}
+
+impl From<Action> for Event {
+    fn from(action: Action) -> Event {
+        match action {
+            Action::RequestReview => Event::RequestReview,
+            Action::Approve => Event::Approve,
+            Action::Reject => Event::Reject,
+            Action::Schedule(_) => Event::Schedule,
+            Action::Publish => Event::Publish,
+        }
+    }
+}
+
+// Everything the blog rules need to know besides the current status
+pub struct PostData {
+    pub required_approvals: usize,
+    pub approvals: Vec<String>,
+    pub publish_at: Option<SystemTime>, // the embargo, while the post is under review or scheduled
+    // and about the event being fired
+    pub actor: String,
+    pub now: SystemTime,
+    pub schedule_for: Option<SystemTime>, // the time a Schedule asks for
+}
+
+impl PostData {
+    pub fn new(required_approvals: usize) -> PostData {
+        PostData {
+            required_approvals,
+            approvals: Vec::new(),
+            publish_at: None,
+            actor: String::new(),
+            now: SystemTime::UNIX_EPOCH,
+            schedule_for: None,
+        }
+    }
+
+    fn actor_has_approved(&self) -> bool {
+        self.approvals.contains(&self.actor)
+    }
+
+    // Whether the actor's approval would be the last one needed
+    fn approval_completes(&self) -> bool {
+        !self.actor_has_approved() && self.approvals.len() + 1 >= self.required_approvals
+    }
+
+    fn embargo_is_over(&self) -> bool {
+        self.publish_at.is_some_and(|publish_at| self.now >= publish_at)
+    }
+}
+
+pub fn blog_machine() -> StateMachine<Status, Event, PostData> {
+    let record_approval = |data: &mut PostData| {
+        let actor = data.actor.clone();
+        data.approvals.push(actor);
+    };
+
+    let mut machine = StateMachine::new();
+    machine
+        .add(Rule::new(Status::Draft, Event::RequestReview, Status::PendingReview))
+        // the approval that makes up the numbers publishes the post, or schedules it if it has an embargo...
+        .add(
+            Rule::new(Status::PendingReview, Event::Approve, Status::Scheduled)
+                .guard(|data: &PostData| data.approval_completes() && data.publish_at.is_some())
+                .action(record_approval),
+        )
+        .add(
+            Rule::new(Status::PendingReview, Event::Approve, Status::Published)
+                .guard(PostData::approval_completes)
+                .action(record_approval),
+        )
+        // ...any earlier one is just noted down
+        .add(
+            Rule::new(Status::PendingReview, Event::Approve, Status::PendingReview)
+                .guard(|data: &PostData| !data.actor_has_approved())
+                .action(record_approval),
+        )
+        .add(Rule::new(Status::PendingReview, Event::Reject, Status::Draft))
+        .add(
+            Rule::new(Status::PendingReview, Event::Schedule, Status::PendingReview)
+                .action(|data: &mut PostData| data.publish_at = data.schedule_for),
+        )
+        // until it's actually out, a scheduled post can still be pulled back to draft
+        .add(Rule::new(Status::Scheduled, Event::Reject, Status::Draft))
+        .add(Rule::new(Status::Scheduled, Event::Publish, Status::Published).guard(PostData::embargo_is_over))
+        // a post going back to draft has to be reviewed from scratch, embargo and all
+        .on_enter(Status::Draft, |data| {
+            data.approvals.clear();
+            data.publish_at = None;
+        })
+        .on_enter(Status::Scheduled, |data| data.approvals.clear())
+        .on_enter(Status::Published, |data| {
+            data.approvals.clear();
+            data.publish_at = None;
+        });
+    machine
+}
+
+thread_local! {
+    // The rules' closures aren't Send, so each thread builds the machine once for itself
+    static BLOG_MACHINE: StateMachine<Status, Event, PostData> = blog_machine();
+}
+
+// Takes a post that's in `status` through `action`, turning whatever the machine refuses into the error a caller of
+// Post would expect.
+pub(crate) fn fire(
+    status: Status,
+    action: Action,
+    actor: &str,
+    now: SystemTime,
+    data: &mut PostData,
+) -> Result<Status, TransitionError> {
+    data.actor = actor.to_string();
+    data.now = now;
+    data.schedule_for = match action {
+        Action::Schedule(publish_at) => Some(publish_at),
+        _ => None,
+    };
+
+    BLOG_MACHINE.with(|machine| machine.fire(status, Event::from(action), data)).map_err(|error| match error {
+        // the only guards in the blog machine stop someone approving twice, and a post going out too early
+        MachineError::Blocked { event: Event::Approve, .. } => {
+            TransitionError::AlreadyApproved { approver: actor.to_string() }
+        }
+        MachineError::Blocked { event: Event::Publish, .. } => {
+            TransitionError::Embargoed { until: data.publish_at.expect("a scheduled post has an embargo") }
+        }
+        MachineError::NoTransition { state, .. } | MachineError::Blocked { state, .. } => {
+            TransitionError::NotAllowed { action, status: state }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn in_an_hour() -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(3600)
+    }
+
+    // Fires each action in turn (at `now`), and returns what came of each one
+    fn run(
+        status: &mut Status,
+        data: &mut PostData,
+        now: SystemTime,
+        script: &[(Action, &str)],
+    ) -> Vec<Result<Status, TransitionError>> {
+        script
+            .iter()
+            .map(|&(action, actor)| {
+                let result = fire(*status, action, actor, now, data);
+                if let Ok(next) = result {
+                    *status = next;
+                }
+                result
+            })
+            .collect()
+    }
+
+    #[test]
+    fn each_person_approves_once_until_there_are_enough() {
+        use Action::*;
+        let mut status = Status::Draft;
+        let mut data = PostData::new(2);
+
+        let results = run(&mut status, &mut data, SystemTime::UNIX_EPOCH, &[
+            (Approve, "alice"),
+            (RequestReview, "bertie"),
+            (Approve, "alice"),
+            (Approve, "alice"),
+            (Approve, "bob"),
+            (Reject, "bob"),
+        ]);
+
+        assert_eq!(results, vec![
+            Err(TransitionError::NotAllowed { action: Approve, status: Status::Draft }),
+            Ok(Status::PendingReview),
+            Ok(Status::PendingReview),
+            Err(TransitionError::AlreadyApproved { approver: String::from("alice") }),
+            Ok(Status::Published),
+            Err(TransitionError::NotAllowed { action: Reject, status: Status::Published }),
+        ]);
+        assert!(data.approvals.is_empty());
+    }
+
+    #[test]
+    fn an_embargo_holds_an_approved_post_in_scheduled() {
+        use Action::*;
+        let mut status = Status::Draft;
+        let mut data = PostData::new(1);
+
+        let results = run(&mut status, &mut data, SystemTime::UNIX_EPOCH, &[
+            (Schedule(in_an_hour()), "bertie"),
+            (RequestReview, "bertie"),
+            (Schedule(in_an_hour()), "bertie"),
+            (Approve, "alice"),
+            (Schedule(in_an_hour()), "bertie"),
+            (Publish, "scheduler"),
+        ]);
+        assert_eq!(results, vec![
+            Err(TransitionError::NotAllowed { action: Schedule(in_an_hour()), status: Status::Draft }),
+            Ok(Status::PendingReview),
+            Ok(Status::PendingReview),
+            Ok(Status::Scheduled),
+            Err(TransitionError::NotAllowed { action: Schedule(in_an_hour()), status: Status::Scheduled }),
+            Err(TransitionError::Embargoed { until: in_an_hour() }),
+        ]);
+
+        assert_eq!(run(&mut status, &mut data, in_an_hour(), &[(Publish, "scheduler")]), vec![Ok(Status::Published)]);
+        assert_eq!(data.publish_at, None);
+    }
+
+    #[test]
+    fn rejecting_clears_the_approvals_and_any_embargo() {
+        use Action::*;
+        let mut status = Status::Draft;
+        let mut data = PostData::new(2);
+
+        run(&mut status, &mut data, SystemTime::UNIX_EPOCH, &[
+            (RequestReview, "bertie"),
+            (Schedule(in_an_hour()), "bertie"),
+            (Approve, "alice"),
+            (Reject, "bob"),
+        ]);
+        assert_eq!(status, Status::Draft);
+        assert!(data.approvals.is_empty());
+        assert_eq!(data.publish_at, None);
+
+        // and a scheduled post can be pulled back too
+        run(&mut status, &mut data, SystemTime::UNIX_EPOCH, &[
+            (RequestReview, "bertie"),
+            (Schedule(in_an_hour()), "bertie"),
+            (Approve, "alice"),
+            (Approve, "bob"),
+        ]);
+        assert_eq!(status, Status::Scheduled);
+        assert_eq!(run(&mut status, &mut data, SystemTime::UNIX_EPOCH, &[(Reject, "bob")]), vec![Ok(Status::Draft)]);
+        assert_eq!(data.publish_at, None);
+    }
+
+    #[test]
+    fn it_knows_which_actions_each_state_takes() {
+        let machine = blog_machine();
+
+        assert_eq!(machine.events(Status::Draft), vec![Event::RequestReview]);
+        assert_eq!(machine.events(Status::PendingReview), vec![Event::Approve, Event::Reject, Event::Schedule]);
+        assert_eq!(machine.events(Status::Scheduled), vec![Event::Reject, Event::Publish]);
+        assert!(machine.events(Status::Published).is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::thread;
+
+use state_pattern_oo::http::{BlogService, MAX_HEADERS, MAX_LINE};
+
+// Starts a service on a free local port. serve only returns if accepting fails, so it gets a thread of its own and
+// the test talks to it over the socket.
+fn start() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || BlogService::new().serve(listener));
+    addr
+}
+
+// Sends one request and returns the status code and body
+fn send(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )
+    .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, body.to_string())
+}
+
+#[test]
+fn a_post_goes_from_draft_to_published_over_http() {
+    let addr = start();
+
+    assert_eq!(
+        send(addr, "POST", "/posts", ""),
+        (201, String::from(r#"{"id":"1","status":"Draft","content":"","transitions":0}"#))
+    );
+    assert_eq!(send(addr, "POST", "/posts/1/text", r#"{"text": "I ate a \"salad\"\nfor lunch"}"#).0, 200);
+    assert_eq!(send(addr, "GET", "/posts/1/content", "").0, 409);
+    assert_eq!(send(addr, "GET", "/posts/1/html", "").0, 409);
+
+    assert_eq!(send(addr, "POST", "/posts/1/request_review", r#"{"actor": "bertie"}"#).0, 200);
+    let (status, body) = send(addr, "POST", "/posts/1/approve", r#"{"actor": "alice"}"#);
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""status":"Published""#));
+
+    assert_eq!(
+        send(addr, "GET", "/posts/1/content", ""),
+        (200, String::from(r#"{"content":"I ate a \"salad\"\nfor lunch"}"#))
+    );
+    assert_eq!(
+        send(addr, "GET", "/posts/1/html", ""),
+        (
+            200,
+            String::from(r#"{"html":"<p>I ate a &quot;salad&quot;\nfor lunch</p>\n","excerpt":"I ate a \"salad\" for lunch"}"#)
+        )
+    );
+}
+
+#[test]
+fn reviews_can_be_rejected_and_need_every_approval() {
+    let addr = start();
+    send(addr, "POST", "/posts", r#"{"required_approvals": 2}"#);
+    send(addr, "POST", "/posts/1/request_review", r#"{"actor": "bertie"}"#);
+
+    let (status, body) = send(addr, "POST", "/posts/1/reject", r#"{"actor": "bob", "comment": "too short"}"#);
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""status":"Draft""#));
+
+    send(addr, "POST", "/posts/1/request_review", r#"{"actor": "bertie"}"#);
+    let (_, body) = send(addr, "POST", "/posts/1/approve", r#"{"actor": "alice"}"#);
+    assert!(body.contains(r#""status":"PendingReview""#));
+
+    let (status, body) = send(addr, "POST", "/posts/1/approve", r#"{"actor": "alice"}"#);
+    assert_eq!(status, 409);
+    assert!(body.starts_with(r#"{"error":"#));
+
+    let (_, body) = send(addr, "GET", "/posts/1", "");
+    assert!(body.contains(r#""transitions":4"#));
+}
+
+#[test]
+fn bad_requests_get_error_responses() {
+    let addr = start();
+
+    assert_eq!(send(addr, "GET", "/posts/1", "").0, 404);
+    send(addr, "POST", "/posts", "");
+    assert_eq!(send(addr, "POST", "/posts/1/approve", r#"{"actor": "alice"}"#).0, 409);
+    assert_eq!(send(addr, "POST", "/posts/1/request_review", "not json").0, 400);
+    assert_eq!(send(addr, "POST", "/posts", r#"{"required_approvals": 0}"#).0, 400);
+    assert_eq!(send(addr, "GET", "/posts", ""), (200, String::from(r#"{"ids":["1"]}"#)));
+}
+
+// Sends `head` as it is, with nothing after it, and returns the status code
+fn send_head(addr: SocketAddr, head: &str) -> u16 {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(head.as_bytes()).unwrap();
+    stream.shutdown(Shutdown::Write).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response.split_whitespace().nth(1).unwrap().parse().unwrap()
+}
+
+#[test]
+fn request_heads_are_limited() {
+    let addr = start();
+
+    let long_header = format!("GET /posts HTTP/1.1\r\nX-Long: {}", "a".repeat(MAX_LINE - 8));
+    assert_eq!(send_head(addr, &long_header), 431);
+    let many_headers = format!("GET /posts HTTP/1.1\r\n{}", "X-Many: 1\r\n".repeat(MAX_HEADERS + 1));
+    assert_eq!(send_head(addr, &many_headers), 431);
+    assert_eq!(send_head(addr, &format!("GET /{}", "a".repeat(MAX_LINE))), 400);
+
+    // right up to the limits is fine
+    let headers = format!("{}X-Long: {}\r\n", "X: 1\r\n".repeat(MAX_HEADERS - 1), "a".repeat(MAX_LINE - 10));
+    assert_eq!(send_head(addr, &format!("GET /posts HTTP/1.1\r\n{}\r\n", headers)), 200);
+}